@@ -1,11 +1,15 @@
 // Copyright 2019-2021 koushiro. Licensed under MIT.
 
 use std::fs::File;
-use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use flvparse::{parse, FlvFile, FlvTagType};
-use prettytable::{cell, format, row, Attr, Cell, Row, Table};
+use flvparse::{
+    decrypt_flv, extract_aac, extract_avc, extract_mp3, inject_metadata, validate, Diagnostic,
+    FlvFile, FlvTagType, NomErr, AES_128_KEY_SIZE,
+};
+use prettytable::{format, row, Attr, Cell, Row, Table};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -13,27 +17,180 @@ use structopt::StructOpt;
 struct Opt {
     /// The input FLV file to parse.
     #[structopt(short, long, parse(from_os_str))]
-    input: PathBuf,
+    input: Option<PathBuf>,
     /// Prints all tables about FLV File info.
     #[structopt(short = "p", long)]
     print: bool,
+    /// Skips the corrupted tags instead of stopping at them, and prints the skipped byte ranges.
+    #[structopt(short = "r", long)]
+    recover: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Writes a new FLV file with a correct `onMetaData` computed from the tags.
+    Inject {
+        /// The input FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        input: PathBuf,
+        /// The output FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Extracts the AAC audio to an ADTS (.aac) file, or the MP3 audio to an .mp3 file.
+    ExtractAudio {
+        /// The input FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        input: PathBuf,
+        /// The output ADTS or MP3 file.
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Extracts the AVC video to an H.264 Annex B (.h264) file.
+    ExtractVideo {
+        /// The input FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        input: PathBuf,
+        /// The output H.264 file.
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Checks the FLV file against the spec, prints every issue found as a JSON line
+    /// (`offset`, `tag_index`, `code` and `message`), and exits with 1 if any.
+    Lint {
+        /// The input FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        input: PathBuf,
+    },
+    /// Decrypts the encrypted (filtered) tags with the AES-128 key, and writes a plain FLV file.
+    Decrypt {
+        /// The input FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        input: PathBuf,
+        /// The output FLV file.
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+        /// The AES-128 key, 32 hex digits.
+        #[structopt(short, long, parse(try_from_str = parse_key))]
+        key: [u8; AES_128_KEY_SIZE],
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opt: Opt = Opt::from_args();
 
-    let file = File::open(opt.input)?;
+    match opt.command {
+        Some(Command::Inject { input, output }) => {
+            let contents = read_file(&input)?;
+            let mut writer = BufWriter::new(File::create(output)?);
+            inject_metadata(&contents, &mut writer)?;
+        }
+        Some(Command::ExtractAudio { input, output }) => {
+            let contents = read_file(&input)?;
+            let mut audio = Vec::new();
+            if extract_aac(&contents, &mut audio)? == 0 && extract_mp3(&contents, &mut audio)? == 0
+            {
+                return Err("no AAC or MP3 audio frame found".into());
+            }
+            File::create(output)?.write_all(&audio)?;
+        }
+        Some(Command::ExtractVideo { input, output }) => {
+            let contents = read_file(&input)?;
+            let mut writer = BufWriter::new(File::create(output)?);
+            if extract_avc(&contents, &mut writer)? == 0 {
+                return Err("no AVC video frame found".into());
+            }
+        }
+        Some(Command::Lint { input }) => {
+            let contents = read_file(&input)?;
+            let diagnostics = validate(&contents);
+            for diagnostic in &diagnostics {
+                println!("{}", json_line(diagnostic));
+            }
+            if !diagnostics.is_empty() {
+                eprintln!("{} issue(s) found", diagnostics.len());
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Decrypt { input, output, key }) => {
+            let contents = read_file(&input)?;
+            let mut writer = BufWriter::new(File::create(output)?);
+            let decrypted = decrypt_flv(&contents, key, &mut writer)?;
+            eprintln!("{} tag(s) decrypted", decrypted);
+        }
+        None => {
+            let input = opt.input.ok_or("the input FLV file is required")?;
+            let contents = read_file(&input)?;
+            let flv = if opt.recover {
+                let (flv, skipped) = parse_recovering(&contents)?;
+                for range in skipped {
+                    eprintln!("skipped bytes {}..{}", range.start, range.end);
+                }
+                flv
+            } else {
+                parse(&contents)?
+            };
+            print_table(&flv, opt.print);
+        }
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> std::io::Result<Vec<u8>> {
+    let file = File::open(path)?;
     let mut reader = BufReader::new(file);
     let mut contents = vec![];
     reader.read_to_end(&mut contents)?;
+    Ok(contents)
+}
 
-    let flv = parse(&contents)?;
-    if opt.print {
-        print_table(&flv, true);
-    } else {
-        print_table(&flv, false);
+fn parse_key(key: &str) -> Result<[u8; AES_128_KEY_SIZE], String> {
+    let mut bytes = [0; AES_128_KEY_SIZE];
+    if key.len() != AES_128_KEY_SIZE * 2 || !key.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err("the key must be 32 hex digits".into());
+    }
+    for (byte, hex) in bytes.iter_mut().zip(key.as_bytes().chunks(2)) {
+        let hex = std::str::from_utf8(hex).map_err(|err| err.to_string())?;
+        *byte = u8::from_str_radix(hex, 16).map_err(|err| err.to_string())?;
+    }
+    Ok(bytes)
+}
+
+fn json_line(diagnostic: &Diagnostic) -> String {
+    let tag_index = match diagnostic.tag_index {
+        Some(index) => index.to_string(),
+        None => "null".into(),
+    };
+    let message = diagnostic
+        .kind
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    format!(
+        r#"{{"offset":{},"tag_index":{},"code":"{}","message":"{}"}}"#,
+        diagnostic.offset,
+        tag_index,
+        diagnostic.kind.code(),
+        message
+    )
+}
+
+fn parse(contents: &[u8]) -> Result<FlvFile<'_>, String> {
+    FlvFile::try_parse(contents).map_err(|err| format!("invalid FLV file: {}", err))
+}
+
+fn parse_recovering(contents: &[u8]) -> Result<(FlvFile<'_>, Vec<Range<usize>>), String> {
+    match FlvFile::parse_recovering(contents) {
+        Ok((_, result)) => Ok(result),
+        Err(NomErr::Incomplete(_)) => Err("incomplete FLV file header".into()),
+        Err(NomErr::Error(err)) | Err(NomErr::Failure(err)) => Err(format!(
+            "invalid FLV file header at offset {}: {:?}",
+            contents.len() - err.input.len(),
+            err.code
+        )),
     }
-    Ok(())
 }
 
 fn print_table(flv_file: &FlvFile, print_body: bool) {
@@ -76,7 +233,8 @@ fn print_table(flv_file: &FlvFile, print_body: bool) {
     body.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
     body.add_row(row!(
         "Index",
-        "TagType (1B)",
+        "Filter (1b)",
+        "TagType (5b)",
         "DataSize (3B)",
         "Timestamp (4B)",
         "StreamID (3B)"
@@ -85,6 +243,7 @@ fn print_table(flv_file: &FlvFile, print_body: bool) {
     let mut script_tag_num = 0usize;
     let mut video_tag_num = 0usize;
     let mut audio_tag_num = 0usize;
+    let mut filtered_tag_num = 0usize;
     for (tag, _) in &flv_file.body.tags {
         index += 1;
         match tag.header.tag_type {
@@ -92,8 +251,12 @@ fn print_table(flv_file: &FlvFile, print_body: bool) {
             FlvTagType::Video => video_tag_num += 1,
             FlvTagType::Audio => audio_tag_num += 1,
         }
+        if tag.header.filter {
+            filtered_tag_num += 1;
+        }
         body.add_row(Row::new(vec![
             Cell::new(&format!("{}", index)),
+            Cell::new(&format!("{}", u8::from(tag.header.filter))),
             Cell::new(&format!("{:?}", tag.header.tag_type)),
             Cell::new(&format!("{}", tag.header.data_size)),
             Cell::new(&format!("{}", tag.header.timestamp)),
@@ -110,6 +273,7 @@ fn print_table(flv_file: &FlvFile, print_body: bool) {
         Cell::new("Script tag number").with_style(Attr::Bold),
         Cell::new("Video tag number").with_style(Attr::Bold),
         Cell::new("Audio tag number").with_style(Attr::Bold),
+        Cell::new("Filtered tag number").with_style(Attr::Bold),
     ]));
     result.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
     result.add_row(row!(
@@ -117,6 +281,7 @@ fn print_table(flv_file: &FlvFile, print_body: bool) {
         &format!("{}", script_tag_num),
         &format!("{}", video_tag_num),
         &format!("{}", audio_tag_num),
+        &format!("{}", filtered_tag_num),
     ));
     result.printstd();
 }
@@ -0,0 +1,362 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the AudioSpecificConfig carried by the AAC sequence header.
+// [ISO/IEC 14496-3](https://www.iso.org/standard/76383.html), 1.6.2.1 AudioSpecificConfig
+
+use nom::{error::ErrorKind, IResult};
+
+use super::bits::{BitReader, BitResult};
+use crate::parse::{AACAudioPacket, AACPacketType};
+
+/// The sampling frequencies indexed by `sampling_frequency_index`.
+pub const AAC_SAMPLING_FREQUENCIES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// The audio object type of SBR (HE-AAC).
+pub const AOT_SBR: u8 = 5;
+/// The audio object type of PS (HE-AAC v2).
+pub const AOT_PS: u8 = 29;
+/// The audio object type of ER BSAC.
+pub const AOT_ER_BSAC: u8 = 22;
+
+const SYNC_EXTENSION_TYPE_SBR: u32 = 0x2b7;
+const SYNC_EXTENSION_TYPE_PS: u32 = 0x548;
+
+/// The AudioSpecificConfig of AAC.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AudioSpecificConfig {
+    /// The audio object type (e.g. 1 = AAC Main, 2 = AAC LC).
+    /// For the explicit hierarchical SBR/PS signalling (object type 5 or 29),
+    /// this is the underlying object type.
+    pub audio_object_type: u8,
+    /// The sampling frequency index, 15 if the sampling frequency is given explicitly.
+    pub sampling_frequency_index: u8,
+    /// The sampling frequency (of the core decoder).
+    pub sampling_frequency: u32,
+    /// The channel configuration, 0 if the channels are defined by the program config element.
+    pub channel_configuration: u8,
+    /// The extension audio object type (5 = SBR, 22 = ER BSAC), if signalled.
+    pub extension_audio_object_type: Option<u8>,
+    /// The extension (output) sampling frequency of SBR, if signalled.
+    pub extension_sampling_frequency: Option<u32>,
+    /// Whether SBR is present, `None` if not signalled explicitly.
+    pub sbr_present: Option<bool>,
+    /// Whether PS is present, `None` if not signalled explicitly.
+    pub ps_present: Option<bool>,
+    /// The GASpecificConfig, only for the general audio object types.
+    pub ga_specific_config: Option<GASpecificConfig>,
+}
+
+/// The GASpecificConfig of AAC.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GASpecificConfig {
+    /// The frame length flag, 0 = 1024 (or 128) samples, 1 = 960 (or 120) samples.
+    pub frame_length_flag: bool,
+    /// The core coder delay, if it depends on core coder.
+    pub core_coder_delay: Option<u16>,
+    /// The program config element, only if the channel configuration is 0.
+    pub program_config_element: Option<ProgramConfigElement>,
+    /// The layer number, only for the audio object type 6 and 20.
+    pub layer_nr: Option<u8>,
+    /// The extension flag.
+    pub extension_flag: bool,
+}
+
+/// The program config element of AAC, which defines the channels.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProgramConfigElement {
+    /// The element instance tag.
+    pub element_instance_tag: u8,
+    /// The object type.
+    pub object_type: u8,
+    /// The sampling frequency index.
+    pub sampling_frequency_index: u8,
+    /// The number of front channels.
+    pub front_channels: u8,
+    /// The number of side channels.
+    pub side_channels: u8,
+    /// The number of back channels.
+    pub back_channels: u8,
+    /// The number of LFE channels.
+    pub lfe_channels: u8,
+}
+
+impl ProgramConfigElement {
+    /// The total number of channels.
+    pub fn channels(&self) -> u8 {
+        self.front_channels + self.side_channels + self.back_channels + self.lfe_channels
+    }
+}
+
+impl AudioSpecificConfig {
+    /// Parse AudioSpecificConfig.
+    pub fn parse(input: &[u8]) -> IResult<&[u8], AudioSpecificConfig> {
+        let mut bits = BitReader::new(input);
+        let config = Self::parse_bits(&mut bits)?;
+        Ok((bits.remain(), config))
+    }
+
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, AudioSpecificConfig> {
+        let mut audio_object_type = audio_object_type(bits)?;
+        let (sampling_frequency_index, frequency) = sampling_frequency(bits)?;
+        let channel_configuration = bits.read_u8(4)?;
+
+        let mut config = AudioSpecificConfig {
+            audio_object_type,
+            sampling_frequency_index,
+            sampling_frequency: frequency,
+            channel_configuration,
+            extension_audio_object_type: None,
+            extension_sampling_frequency: None,
+            sbr_present: None,
+            ps_present: None,
+            ga_specific_config: None,
+        };
+
+        // explicit hierarchical signalling of SBR/PS
+        if audio_object_type == AOT_SBR || audio_object_type == AOT_PS {
+            config.extension_audio_object_type = Some(AOT_SBR);
+            config.sbr_present = Some(true);
+            if audio_object_type == AOT_PS {
+                config.ps_present = Some(true);
+            }
+            config.extension_sampling_frequency = Some(sampling_frequency(bits)?.1);
+            audio_object_type = self::audio_object_type(bits)?;
+            if audio_object_type == AOT_ER_BSAC {
+                // extension channel configuration
+                bits.skip(4)?;
+            }
+            config.audio_object_type = audio_object_type;
+        }
+
+        match audio_object_type {
+            1 | 2 | 3 | 4 | 6 | 7 | 17 | 19 | 20 | 21 | 22 | 23 => {
+                config.ga_specific_config = Some(GASpecificConfig::parse_bits(
+                    bits,
+                    channel_configuration,
+                    audio_object_type,
+                )?);
+            }
+            // The other object types (like CELP, HVXC, ALS) are not supported,
+            // so the rest of config can't be located.
+            _ => return Ok(config),
+        }
+
+        if let 17 | 19..=27 | 39 = audio_object_type {
+            let ep_config = bits.read_u8(2)?;
+            if ep_config == 2 || ep_config == 3 {
+                // ErrorProtectionSpecificConfig is not supported.
+                return Ok(config);
+            }
+        }
+
+        // backward compatible explicit signalling of SBR/PS
+        if config.extension_audio_object_type != Some(AOT_SBR)
+            && bits.remaining() >= 16
+            && bits.read(11)? == SYNC_EXTENSION_TYPE_SBR
+        {
+            let extension_audio_object_type = self::audio_object_type(bits)?;
+            if extension_audio_object_type == AOT_SBR {
+                let sbr_present = bits.read_bool()?;
+                config.extension_audio_object_type = Some(AOT_SBR);
+                config.sbr_present = Some(sbr_present);
+                if sbr_present {
+                    config.extension_sampling_frequency = Some(sampling_frequency(bits)?.1);
+                    if bits.remaining() >= 12 && bits.read(11)? == SYNC_EXTENSION_TYPE_PS {
+                        config.ps_present = Some(bits.read_bool()?);
+                    }
+                }
+            } else if extension_audio_object_type == AOT_ER_BSAC {
+                let sbr_present = bits.read_bool()?;
+                config.extension_audio_object_type = Some(AOT_ER_BSAC);
+                config.sbr_present = Some(sbr_present);
+                if sbr_present {
+                    config.extension_sampling_frequency = Some(sampling_frequency(bits)?.1);
+                }
+                // extension channel configuration
+                bits.skip(4)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// The number of channels, the channels upmixed by PS are counted.
+    pub fn channels(&self) -> Option<u8> {
+        let channels = match self.channel_configuration {
+            0 => self
+                .ga_specific_config
+                .as_ref()
+                .and_then(|ga| ga.program_config_element)
+                .map(|pce| pce.channels())?,
+            1..=6 => self.channel_configuration,
+            7 | 12 | 14 => 8,
+            11 => 7,
+            13 => 24,
+            _ => return None,
+        };
+        if channels == 1 && self.ps_present == Some(true) {
+            Some(2)
+        } else {
+            Some(channels)
+        }
+    }
+
+    /// The output sampling frequency, the extension sampling frequency if SBR is
+    /// signalled explicitly, otherwise the sampling frequency of the core.
+    pub fn output_sampling_frequency(&self) -> u32 {
+        match (self.sbr_present, self.extension_sampling_frequency) {
+            (Some(true), Some(frequency)) => frequency,
+            _ => self.sampling_frequency,
+        }
+    }
+
+    /// The output sampling frequency guessed with implicit SBR signalling.
+    ///
+    /// Without explicit SBR signalling, SBR is assumed to be present
+    /// (doubling the sampling frequency) if the sampling frequency is at most 24000 Hz.
+    pub fn implicit_output_sampling_frequency(&self) -> u32 {
+        match self.sbr_present {
+            None if self.sampling_frequency <= 24000 => self.sampling_frequency * 2,
+            _ => self.output_sampling_frequency(),
+        }
+    }
+}
+
+impl GASpecificConfig {
+    fn parse_bits<'a>(
+        bits: &mut BitReader<'a>,
+        channel_configuration: u8,
+        audio_object_type: u8,
+    ) -> BitResult<'a, GASpecificConfig> {
+        let frame_length_flag = bits.read_bool()?;
+        let core_coder_delay = if bits.read_bool()? {
+            Some(bits.read(14)? as u16)
+        } else {
+            None
+        };
+        let extension_flag = bits.read_bool()?;
+        let program_config_element = if channel_configuration == 0 {
+            Some(ProgramConfigElement::parse_bits(bits)?)
+        } else {
+            None
+        };
+        let layer_nr = if audio_object_type == 6 || audio_object_type == 20 {
+            Some(bits.read_u8(3)?)
+        } else {
+            None
+        };
+        if extension_flag {
+            if audio_object_type == AOT_ER_BSAC {
+                // numOfSubFrame (5 bits) + layer_length (11 bits)
+                bits.skip(16)?;
+            }
+            if let 17 | 19 | 20 | 23 = audio_object_type {
+                // aacSectionDataResilienceFlag, aacScalefactorDataResilienceFlag,
+                // aacSpectralDataResilienceFlag
+                bits.skip(3)?;
+            }
+            // extensionFlag3
+            bits.skip(1)?;
+        }
+        Ok(GASpecificConfig {
+            frame_length_flag,
+            core_coder_delay,
+            program_config_element,
+            layer_nr,
+            extension_flag,
+        })
+    }
+}
+
+impl ProgramConfigElement {
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, ProgramConfigElement> {
+        let element_instance_tag = bits.read_u8(4)?;
+        let object_type = bits.read_u8(2)?;
+        let sampling_frequency_index = bits.read_u8(4)?;
+        let num_front_channel_elements = bits.read_u8(4)?;
+        let num_side_channel_elements = bits.read_u8(4)?;
+        let num_back_channel_elements = bits.read_u8(4)?;
+        let num_lfe_channel_elements = bits.read_u8(2)?;
+        let num_assoc_data_elements = bits.read_u8(3)?;
+        let num_valid_cc_elements = bits.read_u8(4)?;
+        // mono_mixdown_present + mono_mixdown_element_number
+        if bits.read_bool()? {
+            bits.skip(4)?;
+        }
+        // stereo_mixdown_present + stereo_mixdown_element_number
+        if bits.read_bool()? {
+            bits.skip(4)?;
+        }
+        // matrix_mixdown_idx_present + matrix_mixdown_idx + pseudo_surround_enable
+        if bits.read_bool()? {
+            bits.skip(3)?;
+        }
+
+        // Each channel pair element (is_cpe = 1) has 2 channels.
+        let mut channels = |count: u8| -> BitResult<'a, u8> {
+            let mut channels = 0;
+            for _ in 0..count {
+                let is_cpe = bits.read_bool()?;
+                // element tag select
+                bits.skip(4)?;
+                channels += if is_cpe { 2 } else { 1 };
+            }
+            Ok(channels)
+        };
+        let front_channels = channels(num_front_channel_elements)?;
+        let side_channels = channels(num_side_channel_elements)?;
+        let back_channels = channels(num_back_channel_elements)?;
+        // lfe_element_tag_select
+        bits.skip(4 * num_lfe_channel_elements as usize)?;
+        // assoc_data_element_tag_select
+        bits.skip(4 * num_assoc_data_elements as usize)?;
+        // cc_element_is_ind_sw + valid_cc_element_tag_select
+        bits.skip(5 * num_valid_cc_elements as usize)?;
+        bits.byte_align();
+        let comment_field_bytes = bits.read(8)? as usize;
+        bits.skip(8 * comment_field_bytes)?;
+
+        Ok(ProgramConfigElement {
+            element_instance_tag,
+            object_type,
+            sampling_frequency_index,
+            front_channels,
+            side_channels,
+            back_channels,
+            lfe_channels: num_lfe_channel_elements,
+        })
+    }
+}
+
+fn audio_object_type<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, u8> {
+    let audio_object_type = bits.read_u8(5)?;
+    if audio_object_type == 31 {
+        // audioObjectTypeExt
+        Ok(32 + bits.read_u8(6)?)
+    } else {
+        Ok(audio_object_type)
+    }
+}
+
+fn sampling_frequency<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, (u8, u32)> {
+    let index = bits.read_u8(4)?;
+    match index {
+        0..=12 => Ok((index, AAC_SAMPLING_FREQUENCIES[index as usize])),
+        15 => Ok((index, bits.read(24)?)),
+        _ => bits.error(ErrorKind::Switch),
+    }
+}
+
+impl<'a> AACAudioPacket<'a> {
+    /// Parse the AudioSpecificConfig of AAC sequence header,
+    /// `None` if the packet isn't a sequence header or the config is invalid.
+    pub fn audio_specific_config(&self) -> Option<AudioSpecificConfig> {
+        match self.packet_type {
+            AACPacketType::SequenceHeader => AudioSpecificConfig::parse(self.aac_data)
+                .ok()
+                .map(|(_, config)| config),
+            AACPacketType::Raw => None,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the AV1CodecConfigurationRecord carried by the AV1 sequence start.
+// [AV1 Codec ISO Media File Format Binding](https://aomediacodec.github.io/av1-isobmff/), 2.3 AV1 Codec Configuration Box
+
+use nom::{combinator::rest, number::streaming::be_u8, IResult};
+
+use super::VideoCodecConfiguration;
+use crate::parse::VideoTag;
+
+/// The AV1CodecConfigurationRecord (`av1C`) of AV1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Av1CodecConfigurationRecord<'a> {
+    /// The version, always 1, 7 bits.
+    pub version: u8,
+    /// The `seq_profile` of sequence header OBU, 3 bits.
+    pub seq_profile: u8,
+    /// The `seq_level_idx[0]` of sequence header OBU, 5 bits.
+    pub seq_level_idx_0: u8,
+    /// The `seq_tier[0]` of sequence header OBU.
+    pub seq_tier_0: bool,
+    /// The `high_bitdepth` of sequence header OBU.
+    pub high_bitdepth: bool,
+    /// The `twelve_bit` of sequence header OBU.
+    pub twelve_bit: bool,
+    /// The `mono_chrome` of sequence header OBU.
+    pub monochrome: bool,
+    /// The `subsampling_x` of sequence header OBU.
+    pub chroma_subsampling_x: bool,
+    /// The `subsampling_y` of sequence header OBU.
+    pub chroma_subsampling_y: bool,
+    /// The `chroma_sample_position` of sequence header OBU, 2 bits.
+    pub chroma_sample_position: u8,
+    /// The initial presentation delay minus one (in frames), 4 bits, if present.
+    pub initial_presentation_delay_minus_one: Option<u8>,
+    /// The configuration OBUs (like the sequence header OBU and metadata OBUs).
+    pub config_obus: &'a [u8],
+}
+
+impl<'a> Av1CodecConfigurationRecord<'a> {
+    /// Parse AV1CodecConfigurationRecord.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Av1CodecConfigurationRecord<'a>> {
+        do_parse!(
+            input,
+            // marker (1 bit) + version (7 bits)
+            version: map!(verify!(be_u8, |byte: &u8| *byte == 0x81), |byte| byte & 0x7f) >>
+            // seq_profile (3 bits) + seq_level_idx_0 (5 bits)
+            profile_level: be_u8 >>
+            // seq_tier_0, high_bitdepth, twelve_bit, monochrome, chroma_subsampling_x,
+            // chroma_subsampling_y, chroma_sample_position (2 bits)
+            flags: be_u8 >>
+            // reserved (3 bits) + initial_presentation_delay_present (1 bit)
+            // + initial_presentation_delay_minus_one (4 bits)
+            delay: be_u8 >>
+            config_obus: call!(rest) >>
+
+            (Av1CodecConfigurationRecord {
+                version,
+                seq_profile: profile_level >> 5,
+                seq_level_idx_0: profile_level & 0b1_1111,
+                seq_tier_0: flags & 0x80 != 0,
+                high_bitdepth: flags & 0x40 != 0,
+                twelve_bit: flags & 0x20 != 0,
+                monochrome: flags & 0x10 != 0,
+                chroma_subsampling_x: flags & 0x08 != 0,
+                chroma_subsampling_y: flags & 0x04 != 0,
+                chroma_sample_position: flags & 0b11,
+                initial_presentation_delay_minus_one: if delay & 0x10 != 0 {
+                    Some(delay & 0x0f)
+                } else {
+                    None
+                },
+                config_obus,
+            })
+        )
+    }
+
+    /// The bit depth (8, 10 or 12).
+    pub fn bit_depth(&self) -> u8 {
+        match (self.high_bitdepth, self.twelve_bit) {
+            (false, _) => 8,
+            (true, false) => 10,
+            (true, true) => 12,
+        }
+    }
+}
+
+impl<'a> VideoTag<'a> {
+    /// Parse the AV1CodecConfigurationRecord of AV1 sequence start (the `av01` FourCC of Enhanced RTMP),
+    /// `None` if the tag isn't an AV1 sequence start or the record is invalid.
+    pub fn av1_codec_configuration_record(&self) -> Option<Av1CodecConfigurationRecord<'a>> {
+        match self.codec_configuration()? {
+            VideoCodecConfiguration::Av1(record) => Some(record),
+            _ => None,
+        }
+    }
+}
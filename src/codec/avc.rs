@@ -0,0 +1,472 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the AVCDecoderConfigurationRecord carried by the AVC sequence header,
+// and the H.264 SPS in it.
+// [ISO/IEC 14496-15](https://www.iso.org/standard/74429.html), 5.3.3.1 AVC decoder configuration record
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use nom::{
+    error::ErrorKind,
+    number::streaming::{be_u16, be_u8},
+    IResult,
+};
+
+use super::bits::{parse_rbsp, BitReader, BitResult};
+use crate::parse::{AvcPacketType, AvcVideoPacket};
+
+/// The AVCDecoderConfigurationRecord (`avcC`) of AVC.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvcDecoderConfigurationRecord<'a> {
+    /// The configuration version, always 1.
+    pub configuration_version: u8,
+    /// The profile (`profile_idc`) of SPS.
+    pub profile_indication: u8,
+    /// The compatibility flags between `profile_idc` and `level_idc` of SPS.
+    pub profile_compatibility: u8,
+    /// The level (`level_idc`) of SPS.
+    pub level_indication: u8,
+    /// The length in bytes of the NAL unit length field minus one, 2 bits.
+    pub length_size_minus_one: u8,
+    /// The sequence parameter set NAL units.
+    pub sequence_parameter_sets: Vec<&'a [u8]>,
+    /// The picture parameter set NAL units.
+    pub picture_parameter_sets: Vec<&'a [u8]>,
+    /// The extension of the high profiles (100, 110, 122 and 144), if present.
+    pub high_profile_extension: Option<AvcHighProfileExtension<'a>>,
+}
+
+/// The extension of AVCDecoderConfigurationRecord for the high profiles.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvcHighProfileExtension<'a> {
+    /// The `chroma_format_idc` of SPS, 2 bits.
+    pub chroma_format: u8,
+    /// The `bit_depth_luma_minus8` of SPS, 3 bits.
+    pub bit_depth_luma_minus8: u8,
+    /// The `bit_depth_chroma_minus8` of SPS, 3 bits.
+    pub bit_depth_chroma_minus8: u8,
+    /// The sequence parameter set extension NAL units.
+    pub sequence_parameter_set_extensions: Vec<&'a [u8]>,
+}
+
+impl<'a> AvcDecoderConfigurationRecord<'a> {
+    /// Parse AVCDecoderConfigurationRecord.
+    ///
+    /// The high profile extension is optional, since many encoders omit it.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], AvcDecoderConfigurationRecord<'a>> {
+        let (remain, mut record) = do_parse!(
+            input,
+            configuration_version: verify!(be_u8, |version: &u8| *version == 1) >>
+            profile_indication: be_u8 >>
+            profile_compatibility: be_u8 >>
+            level_indication: be_u8 >>
+            // reserved (6 bits) + lengthSizeMinusOne (2 bits)
+            length_size_minus_one: map!(be_u8, |byte| byte & 0b11) >>
+            // reserved (3 bits) + numOfSequenceParameterSets (5 bits)
+            num_sps: map!(be_u8, |byte| byte & 0b1_1111) >>
+            sequence_parameter_sets: count!(length_data!(be_u16), num_sps as usize) >>
+            num_pps: be_u8 >>
+            picture_parameter_sets: count!(length_data!(be_u16), num_pps as usize) >>
+
+            (AvcDecoderConfigurationRecord {
+                configuration_version,
+                profile_indication,
+                profile_compatibility,
+                level_indication,
+                length_size_minus_one,
+                sequence_parameter_sets,
+                picture_parameter_sets,
+                high_profile_extension: None,
+            })
+        )?;
+
+        if let 100 | 110 | 122 | 144 = record.profile_indication {
+            if let Ok((remain, extension)) = AvcHighProfileExtension::parse(remain) {
+                record.high_profile_extension = Some(extension);
+                return Ok((remain, record));
+            }
+        }
+        Ok((remain, record))
+    }
+
+    /// The length in bytes of the NAL unit length field (1, 2 or 4).
+    pub fn nal_length_size(&self) -> u8 {
+        self.length_size_minus_one + 1
+    }
+
+    /// Parse the first SPS, `None` if there is no SPS or the SPS is invalid.
+    pub fn sequence_parameter_set(&self) -> Option<AvcSequenceParameterSet> {
+        let sps = self.sequence_parameter_sets.first()?;
+        AvcSequenceParameterSet::parse(sps).ok().map(|(_, sps)| sps)
+    }
+}
+
+impl<'a> AvcHighProfileExtension<'a> {
+    /// Parse the high profile extension of AVCDecoderConfigurationRecord.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], AvcHighProfileExtension<'a>> {
+        do_parse!(
+            input,
+            // reserved (6 bits) + chroma_format (2 bits)
+            chroma_format: map!(be_u8, |byte| byte & 0b11) >>
+            // reserved (5 bits) + bit_depth_luma_minus8 (3 bits)
+            bit_depth_luma_minus8: map!(be_u8, |byte| byte & 0b111) >>
+            // reserved (5 bits) + bit_depth_chroma_minus8 (3 bits)
+            bit_depth_chroma_minus8: map!(be_u8, |byte| byte & 0b111) >>
+            num_sps_ext: be_u8 >>
+            sequence_parameter_set_extensions: count!(length_data!(be_u16), num_sps_ext as usize) >>
+
+            (AvcHighProfileExtension {
+                chroma_format,
+                bit_depth_luma_minus8,
+                bit_depth_chroma_minus8,
+                sequence_parameter_set_extensions,
+            })
+        )
+    }
+}
+
+impl<'a> AvcVideoPacket<'a> {
+    /// Parse the AVCDecoderConfigurationRecord of AVC sequence header,
+    /// `None` if the packet isn't a sequence header or the record is invalid.
+    pub fn decoder_configuration_record(&self) -> Option<AvcDecoderConfigurationRecord<'a>> {
+        match self.packet_type {
+            AvcPacketType::SequenceHeader => AvcDecoderConfigurationRecord::parse(self.avc_data)
+                .ok()
+                .map(|(_, record)| record),
+            _ => None,
+        }
+    }
+}
+
+const NAL_UNIT_TYPE_SPS: u8 = 7;
+
+/// The sequence parameter set of H.264.
+/// [ITU-T H.264](https://www.itu.int/rec/T-REC-H.264), 7.3.2.1.1 Sequence parameter set data syntax
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvcSequenceParameterSet {
+    /// The profile.
+    pub profile_idc: u8,
+    /// The constraint_set0_flag ~ constraint_set5_flag and the reserved zero bits.
+    pub constraint_set_flags: u8,
+    /// The level.
+    pub level_idc: u8,
+    /// The id of SPS.
+    pub seq_parameter_set_id: u32,
+    /// The chroma sampling (0 = monochrome, 1 = 4:2:0, 2 = 4:2:2, 3 = 4:4:4).
+    pub chroma_format_idc: u32,
+    /// Whether the three colour components of 4:4:4 are coded separately.
+    pub separate_colour_plane_flag: bool,
+    /// The bit depth of luma minus 8.
+    pub bit_depth_luma_minus8: u32,
+    /// The bit depth of chroma minus 8.
+    pub bit_depth_chroma_minus8: u32,
+    /// The width of decoded picture in macroblocks minus 1.
+    pub pic_width_in_mbs_minus1: u32,
+    /// The height of decoded frame or field in slice group map units minus 1.
+    pub pic_height_in_map_units_minus1: u32,
+    /// Whether all the pictures are frames (no field).
+    pub frame_mbs_only_flag: bool,
+    /// The frame cropping offsets, if present.
+    pub frame_cropping: Option<AvcFrameCropping>,
+    /// The VUI parameters, if present.
+    pub vui_parameters: Option<AvcVuiParameters>,
+}
+
+/// The frame cropping offsets of H.264 SPS, in the units of chroma samples.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct AvcFrameCropping {
+    /// The left offset.
+    pub left: u32,
+    /// The right offset.
+    pub right: u32,
+    /// The top offset.
+    pub top: u32,
+    /// The bottom offset.
+    pub bottom: u32,
+}
+
+/// The VUI parameters of H.264 SPS, only the aspect ratio and timing info.
+/// [ITU-T H.264](https://www.itu.int/rec/T-REC-H.264), E.1.1 VUI parameters syntax
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct AvcVuiParameters {
+    /// The sample aspect ratio indicator (255 = Extended_SAR), if present.
+    pub aspect_ratio_idc: Option<u8>,
+    /// The horizontal size of sample aspect ratio, only for Extended_SAR.
+    pub sar_width: u16,
+    /// The vertical size of sample aspect ratio, only for Extended_SAR.
+    pub sar_height: u16,
+    /// The timing info, if present.
+    pub timing_info: Option<TimingInfo>,
+}
+
+/// The timing info of VUI parameters.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TimingInfo {
+    /// The number of time units of a clock tick.
+    pub num_units_in_tick: u32,
+    /// The number of time units that pass in one second.
+    pub time_scale: u32,
+    /// Whether the frame rate is fixed.
+    pub fixed_frame_rate_flag: bool,
+}
+
+// The sample aspect ratios indexed by `aspect_ratio_idc` (1 ~ 16), Table E-1,
+// shared by H.264 and H.265.
+pub(crate) const SAMPLE_ASPECT_RATIOS: [(u16, u16); 17] = [
+    (0, 0),
+    (1, 1),
+    (12, 11),
+    (10, 11),
+    (16, 11),
+    (40, 33),
+    (24, 11),
+    (20, 11),
+    (32, 11),
+    (80, 33),
+    (18, 11),
+    (15, 11),
+    (64, 33),
+    (160, 99),
+    (4, 3),
+    (3, 2),
+    (2, 1),
+];
+
+impl AvcSequenceParameterSet {
+    /// Parse the SPS NAL unit (including the NAL unit header).
+    pub fn parse(input: &[u8]) -> IResult<&[u8], AvcSequenceParameterSet> {
+        parse_rbsp(input, Self::parse_bits)
+    }
+
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, AvcSequenceParameterSet> {
+        // forbidden_zero_bit (1 bit) + nal_ref_idc (2 bits) + nal_unit_type (5 bits)
+        if bits.read_u8(8)? & 0x1f != NAL_UNIT_TYPE_SPS {
+            return bits.error(ErrorKind::Tag);
+        }
+        let profile_idc = bits.read_u8(8)?;
+        let constraint_set_flags = bits.read_u8(8)?;
+        let level_idc = bits.read_u8(8)?;
+        let seq_parameter_set_id = bits.read_ue_max(31)?;
+
+        let mut chroma_format_idc = 1;
+        let mut separate_colour_plane_flag = false;
+        let mut bit_depth_luma_minus8 = 0;
+        let mut bit_depth_chroma_minus8 = 0;
+        if let 100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135 =
+            profile_idc
+        {
+            chroma_format_idc = bits.read_ue_max(3)?;
+            if chroma_format_idc == 3 {
+                separate_colour_plane_flag = bits.read_bool()?;
+            }
+            bit_depth_luma_minus8 = bits.read_ue_max(6)?;
+            bit_depth_chroma_minus8 = bits.read_ue_max(6)?;
+            // qpprime_y_zero_transform_bypass_flag
+            bits.skip(1)?;
+            // seq_scaling_matrix_present_flag
+            if bits.read_bool()? {
+                let count = if chroma_format_idc == 3 { 12 } else { 8 };
+                for i in 0..count {
+                    // seq_scaling_list_present_flag
+                    if bits.read_bool()? {
+                        skip_scaling_list(bits, if i < 6 { 16 } else { 64 })?;
+                    }
+                }
+            }
+        }
+
+        // log2_max_frame_num_minus4
+        bits.read_ue_max(12)?;
+        let pic_order_cnt_type = bits.read_ue_max(2)?;
+        if pic_order_cnt_type == 0 {
+            // log2_max_pic_order_cnt_lsb_minus4
+            bits.read_ue_max(12)?;
+        } else if pic_order_cnt_type == 1 {
+            // delta_pic_order_always_zero_flag
+            bits.skip(1)?;
+            // offset_for_non_ref_pic
+            bits.read_se()?;
+            // offset_for_top_to_bottom_field
+            bits.read_se()?;
+            let num_ref_frames_in_pic_order_cnt_cycle = bits.read_ue_max(255)?;
+            for _ in 0..num_ref_frames_in_pic_order_cnt_cycle {
+                // offset_for_ref_frame
+                bits.read_se()?;
+            }
+        }
+        // max_num_ref_frames
+        bits.read_ue()?;
+        // gaps_in_frame_num_value_allowed_flag
+        bits.skip(1)?;
+        let pic_width_in_mbs_minus1 = bits.read_ue()?;
+        let pic_height_in_map_units_minus1 = bits.read_ue()?;
+        let frame_mbs_only_flag = bits.read_bool()?;
+        if !frame_mbs_only_flag {
+            // mb_adaptive_frame_field_flag
+            bits.skip(1)?;
+        }
+        // direct_8x8_inference_flag
+        bits.skip(1)?;
+        let frame_cropping = if bits.read_bool()? {
+            Some(AvcFrameCropping {
+                left: bits.read_ue()?,
+                right: bits.read_ue()?,
+                top: bits.read_ue()?,
+                bottom: bits.read_ue()?,
+            })
+        } else {
+            None
+        };
+        let vui_parameters = if bits.read_bool()? {
+            Some(AvcVuiParameters::parse_bits(bits)?)
+        } else {
+            None
+        };
+
+        Ok(AvcSequenceParameterSet {
+            profile_idc,
+            constraint_set_flags,
+            level_idc,
+            seq_parameter_set_id,
+            chroma_format_idc,
+            separate_colour_plane_flag,
+            bit_depth_luma_minus8,
+            bit_depth_chroma_minus8,
+            pic_width_in_mbs_minus1,
+            pic_height_in_map_units_minus1,
+            frame_mbs_only_flag,
+            frame_cropping,
+            vui_parameters,
+        })
+    }
+
+    // The crop units (CropUnitX, CropUnitY), (7-19) ~ (7-22).
+    fn crop_units(&self) -> (u32, u32) {
+        let field = 2 - u32::from(self.frame_mbs_only_flag);
+        // ChromaArrayType
+        match (self.separate_colour_plane_flag, self.chroma_format_idc) {
+            (true, _) | (false, 0) => (1, field),
+            (false, 1) => (2, 2 * field),
+            (false, 2) => (2, field),
+            (false, _) => (1, field),
+        }
+    }
+
+    /// The width of the output pictures, with the frame cropping applied,
+    /// `None` if it overflows or the cropping is wider than the picture.
+    pub fn width(&self) -> Option<u32> {
+        let width = self
+            .pic_width_in_mbs_minus1
+            .checked_add(1)?
+            .checked_mul(16)?;
+        let crop = self.frame_cropping.unwrap_or_default();
+        let crop = crop.left.checked_add(crop.right)?;
+        width.checked_sub(self.crop_units().0.checked_mul(crop)?)
+    }
+
+    /// The height of the output pictures, with the frame cropping applied,
+    /// `None` if it overflows or the cropping is higher than the picture.
+    pub fn height(&self) -> Option<u32> {
+        let field = 2 - u32::from(self.frame_mbs_only_flag);
+        let height = self
+            .pic_height_in_map_units_minus1
+            .checked_add(1)?
+            .checked_mul(16 * field)?;
+        let crop = self.frame_cropping.unwrap_or_default();
+        let crop = crop.top.checked_add(crop.bottom)?;
+        height.checked_sub(self.crop_units().1.checked_mul(crop)?)
+    }
+
+    /// The bit depth of luma.
+    pub fn bit_depth_luma(&self) -> u32 {
+        self.bit_depth_luma_minus8 + 8
+    }
+
+    /// The bit depth of chroma.
+    pub fn bit_depth_chroma(&self) -> u32 {
+        self.bit_depth_chroma_minus8 + 8
+    }
+
+    /// The frame rate from the VUI timing info, if present.
+    pub fn frame_rate(&self) -> Option<f64> {
+        let timing = self.vui_parameters?.timing_info?;
+        if timing.num_units_in_tick == 0 {
+            return None;
+        }
+        // A frame contains two fields, each field takes a clock tick.
+        Some(f64::from(timing.time_scale) / (2.0 * f64::from(timing.num_units_in_tick)))
+    }
+
+    /// The sample aspect ratio (width, height) from the VUI parameters, if present.
+    pub fn sample_aspect_ratio(&self) -> Option<(u16, u16)> {
+        let vui = self.vui_parameters?;
+        match vui.aspect_ratio_idc? {
+            idc @ 1..=16 => Some(SAMPLE_ASPECT_RATIOS[idc as usize]),
+            255 => Some((vui.sar_width, vui.sar_height)),
+            _ => None,
+        }
+    }
+}
+
+impl AvcVuiParameters {
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, AvcVuiParameters> {
+        let mut vui = AvcVuiParameters::default();
+        // aspect_ratio_info_present_flag
+        if bits.read_bool()? {
+            let aspect_ratio_idc = bits.read_u8(8)?;
+            if aspect_ratio_idc == 255 {
+                vui.sar_width = bits.read(16)? as u16;
+                vui.sar_height = bits.read(16)? as u16;
+            }
+            vui.aspect_ratio_idc = Some(aspect_ratio_idc);
+        }
+        // overscan_info_present_flag + overscan_appropriate_flag
+        if bits.read_bool()? {
+            bits.skip(1)?;
+        }
+        // video_signal_type_present_flag
+        if bits.read_bool()? {
+            // video_format (3 bits) + video_full_range_flag
+            bits.skip(4)?;
+            // colour_description_present_flag
+            if bits.read_bool()? {
+                // colour_primaries + transfer_characteristics + matrix_coefficients
+                bits.skip(24)?;
+            }
+        }
+        // chroma_loc_info_present_flag
+        if bits.read_bool()? {
+            // chroma_sample_loc_type_top_field + chroma_sample_loc_type_bottom_field
+            bits.read_ue()?;
+            bits.read_ue()?;
+        }
+        // timing_info_present_flag
+        if bits.read_bool()? {
+            vui.timing_info = Some(TimingInfo {
+                num_units_in_tick: bits.read(32)?,
+                time_scale: bits.read(32)?,
+                fixed_frame_rate_flag: bits.read_bool()?,
+            });
+        }
+        Ok(vui)
+    }
+}
+
+fn skip_scaling_list<'a>(bits: &mut BitReader<'a>, size: usize) -> BitResult<'a, ()> {
+    let mut last_scale = 8;
+    let mut next_scale = 8;
+    for _ in 0..size {
+        if next_scale != 0 {
+            let delta_scale = bits.read_se()?;
+            if !(-128..=127).contains(&delta_scale) {
+                return bits.error(ErrorKind::Verify);
+            }
+            next_scale = (last_scale + delta_scale + 256) % 256;
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+    Ok(())
+}
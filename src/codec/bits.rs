@@ -0,0 +1,141 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use nom::{
+    error::{Error, ErrorKind},
+    Err as NomErr, IResult, Needed,
+};
+
+pub(crate) type BitResult<'a, T> = Result<T, NomErr<Error<&'a [u8]>>>;
+
+/// A MSB-first bit reader over a byte slice.
+#[derive(Clone, Debug)]
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    // The position in bits.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// The number of bits that haven't been read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    /// The bytes after the last (partially) read byte.
+    pub(crate) fn remain(&self) -> &'a [u8] {
+        &self.data[self.pos.div_ceil(8)..]
+    }
+
+    /// Create an error of the invalid data at the current position.
+    pub(crate) fn error<T>(&self, kind: ErrorKind) -> BitResult<'a, T> {
+        Err(NomErr::Error(Error::new(&self.data[self.pos / 8..], kind)))
+    }
+
+    /// Read `n` (at most 32) bits.
+    pub(crate) fn read(&mut self, n: usize) -> BitResult<'a, u32> {
+        debug_assert!(n <= 32);
+        if self.remaining() < n {
+            return Err(NomErr::Incomplete(Needed::new(n - self.remaining())));
+        }
+        let mut value = 0u64;
+        for _ in 0..n {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | u64::from(bit);
+            self.pos += 1;
+        }
+        Ok(value as u32)
+    }
+
+    pub(crate) fn read_u8(&mut self, n: usize) -> BitResult<'a, u8> {
+        debug_assert!(n <= 8);
+        self.read(n).map(|value| value as u8)
+    }
+
+    pub(crate) fn read_bool(&mut self) -> BitResult<'a, bool> {
+        self.read(1).map(|value| value == 1)
+    }
+
+    pub(crate) fn skip(&mut self, n: usize) -> BitResult<'a, ()> {
+        if self.remaining() < n {
+            return Err(NomErr::Incomplete(Needed::new(n - self.remaining())));
+        }
+        self.pos += n;
+        Ok(())
+    }
+
+    /// Read an unsigned Exp-Golomb coded value, `ue(v)`.
+    pub(crate) fn read_ue(&mut self) -> BitResult<'a, u32> {
+        let mut leading_zeros = 0;
+        while !self.read_bool()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return self.error(ErrorKind::TooLarge);
+            }
+        }
+        let suffix = self.read(leading_zeros)?;
+        Ok(((1u64 << leading_zeros) - 1 + u64::from(suffix)) as u32)
+    }
+
+    /// Read an unsigned Exp-Golomb coded value, `ue(v)`, which must not exceed `max`.
+    pub(crate) fn read_ue_max(&mut self, max: u32) -> BitResult<'a, u32> {
+        match self.read_ue()? {
+            value if value <= max => Ok(value),
+            _ => self.error(ErrorKind::Verify),
+        }
+    }
+
+    /// Read a signed Exp-Golomb coded value, `se(v)`.
+    pub(crate) fn read_se(&mut self) -> BitResult<'a, i32> {
+        let value = i64::from(self.read_ue()?);
+        if value & 1 == 1 {
+            Ok(((value + 1) / 2) as i32)
+        } else {
+            Ok((-value / 2) as i32)
+        }
+    }
+
+    /// Skip to the next byte boundary.
+    pub(crate) fn byte_align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// Remove the emulation prevention bytes (`0x03` of `0x000003`) of NAL unit,
+/// resulting in the raw byte sequence payload.
+pub(crate) fn remove_emulation_prevention(nal_unit: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(nal_unit.len());
+    let mut zeros = 0;
+    for &byte in nal_unit {
+        if zeros >= 2 && byte == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    rbsp
+}
+
+/// Parse the raw byte sequence payload of NAL unit with the bit reader.
+///
+/// The errors refer to the start of NAL unit, since the payload is a copy.
+pub(crate) fn parse_rbsp<T>(
+    nal_unit: &[u8],
+    parse: impl for<'b> FnOnce(&mut BitReader<'b>) -> BitResult<'b, T>,
+) -> IResult<&[u8], T> {
+    let rbsp = remove_emulation_prevention(nal_unit);
+    let mut bits = BitReader::new(&rbsp);
+    match parse(&mut bits) {
+        Ok(value) => Ok((&nal_unit[nal_unit.len()..], value)),
+        Err(NomErr::Incomplete(needed)) => Err(NomErr::Incomplete(needed)),
+        Err(NomErr::Error(err)) => Err(NomErr::Error(Error::new(nal_unit, err.code))),
+        Err(NomErr::Failure(err)) => Err(NomErr::Failure(Error::new(nal_unit, err.code))),
+    }
+}
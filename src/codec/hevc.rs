@@ -0,0 +1,634 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the HEVCDecoderConfigurationRecord carried by the HEVC sequence header,
+// and the H.265 SPS in it.
+// [ISO/IEC 14496-15](https://www.iso.org/standard/74429.html), 8.3.3.1 HEVC decoder configuration record
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use nom::{
+    error::ErrorKind,
+    number::streaming::{be_u16, be_u32, be_u8},
+    IResult,
+};
+
+use super::{
+    avc::SAMPLE_ASPECT_RATIOS,
+    bits::{parse_rbsp, BitReader, BitResult},
+    VideoCodecConfiguration,
+};
+use crate::parse::VideoTag;
+
+/// The NAL unit type of VPS.
+pub const HEVC_NAL_UNIT_TYPE_VPS: u8 = 32;
+/// The NAL unit type of SPS.
+pub const HEVC_NAL_UNIT_TYPE_SPS: u8 = 33;
+/// The NAL unit type of PPS.
+pub const HEVC_NAL_UNIT_TYPE_PPS: u8 = 34;
+
+/// The HEVCDecoderConfigurationRecord (`hvcC`) of HEVC.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HevcDecoderConfigurationRecord<'a> {
+    /// The configuration version, always 1.
+    pub configuration_version: u8,
+    /// The `general_profile_space` of SPS, 2 bits.
+    pub general_profile_space: u8,
+    /// The `general_tier_flag` of SPS, 1 bit (0 = Main tier, 1 = High tier).
+    pub general_tier_flag: bool,
+    /// The `general_profile_idc` of SPS, 5 bits.
+    pub general_profile_idc: u8,
+    /// The `general_profile_compatibility_flags` of SPS.
+    pub general_profile_compatibility_flags: u32,
+    /// The `general_constraint_indicator_flags` of SPS, 48 bits.
+    pub general_constraint_indicator_flags: u64,
+    /// The `general_level_idc` of SPS, 30 times the level number.
+    pub general_level_idc: u8,
+    /// The `min_spatial_segmentation_idc` of VUI, 12 bits.
+    pub min_spatial_segmentation_idc: u16,
+    /// The type of parallelism (0 = unknown, 1 = slices, 2 = tiles, 3 = WPP), 2 bits.
+    pub parallelism_type: u8,
+    /// The `chroma_format_idc` of SPS, 2 bits.
+    pub chroma_format_idc: u8,
+    /// The `bit_depth_luma_minus8` of SPS, 3 bits.
+    pub bit_depth_luma_minus8: u8,
+    /// The `bit_depth_chroma_minus8` of SPS, 3 bits.
+    pub bit_depth_chroma_minus8: u8,
+    /// The average frame rate in units of frames / (256 seconds), 0 if unspecified.
+    pub avg_frame_rate: u16,
+    /// Whether the frame rate is constant, 2 bits.
+    pub constant_frame_rate: u8,
+    /// The number of temporal layers, 3 bits.
+    pub num_temporal_layers: u8,
+    /// Whether the temporal ids are nested.
+    pub temporal_id_nested: bool,
+    /// The length in bytes of the NAL unit length field minus one, 2 bits.
+    pub length_size_minus_one: u8,
+    /// The arrays of NAL units (like VPS, SPS, PPS and SEI).
+    pub arrays: Vec<HevcNalUnitArray<'a>>,
+}
+
+/// The array of NAL units of the same type in HEVCDecoderConfigurationRecord.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HevcNalUnitArray<'a> {
+    /// Whether all the NAL units of the type are in the array.
+    pub array_completeness: bool,
+    /// The type of NAL units, 6 bits.
+    pub nal_unit_type: u8,
+    /// The NAL units.
+    pub nal_units: Vec<&'a [u8]>,
+}
+
+impl<'a> HevcDecoderConfigurationRecord<'a> {
+    /// Parse HEVCDecoderConfigurationRecord.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], HevcDecoderConfigurationRecord<'a>> {
+        do_parse!(
+            input,
+            configuration_version: verify!(be_u8, |version: &u8| *version == 1) >>
+            // general_profile_space (2 bits) + general_tier_flag (1 bit)
+            // + general_profile_idc (5 bits)
+            profile: be_u8 >>
+            general_profile_compatibility_flags: be_u32 >>
+            general_constraint_indicator_flags: map!(take!(6), |bytes: &[u8]| {
+                bytes.iter().fold(0u64, |flags, byte| flags << 8 | u64::from(*byte))
+            }) >>
+            general_level_idc: be_u8 >>
+            // reserved (4 bits) + min_spatial_segmentation_idc (12 bits)
+            min_spatial_segmentation_idc: map!(be_u16, |value| value & 0x0fff) >>
+            // reserved (6 bits) + parallelismType (2 bits)
+            parallelism_type: map!(be_u8, |byte| byte & 0b11) >>
+            // reserved (6 bits) + chromaFormat (2 bits)
+            chroma_format_idc: map!(be_u8, |byte| byte & 0b11) >>
+            // reserved (5 bits) + bitDepthLumaMinus8 (3 bits)
+            bit_depth_luma_minus8: map!(be_u8, |byte| byte & 0b111) >>
+            // reserved (5 bits) + bitDepthChromaMinus8 (3 bits)
+            bit_depth_chroma_minus8: map!(be_u8, |byte| byte & 0b111) >>
+            avg_frame_rate: be_u16 >>
+            // constantFrameRate (2 bits) + numTemporalLayers (3 bits)
+            // + temporalIdNested (1 bit) + lengthSizeMinusOne (2 bits)
+            layers: be_u8 >>
+            num_of_arrays: be_u8 >>
+            arrays: count!(HevcNalUnitArray::parse, num_of_arrays as usize) >>
+
+            (HevcDecoderConfigurationRecord {
+                configuration_version,
+                general_profile_space: profile >> 6,
+                general_tier_flag: profile & 0b10_0000 != 0,
+                general_profile_idc: profile & 0b1_1111,
+                general_profile_compatibility_flags,
+                general_constraint_indicator_flags,
+                general_level_idc,
+                min_spatial_segmentation_idc,
+                parallelism_type,
+                chroma_format_idc,
+                bit_depth_luma_minus8,
+                bit_depth_chroma_minus8,
+                avg_frame_rate,
+                constant_frame_rate: layers >> 6,
+                num_temporal_layers: (layers >> 3) & 0b111,
+                temporal_id_nested: layers & 0b100 != 0,
+                length_size_minus_one: layers & 0b11,
+                arrays,
+            })
+        )
+    }
+
+    /// The length in bytes of the NAL unit length field (1, 2 or 4).
+    pub fn nal_length_size(&self) -> u8 {
+        self.length_size_minus_one + 1
+    }
+
+    /// The NAL units of the given type, in the order of the arrays.
+    pub fn nal_units(&self, nal_unit_type: u8) -> Vec<&'a [u8]> {
+        self.arrays
+            .iter()
+            .filter(|array| array.nal_unit_type == nal_unit_type)
+            .flat_map(|array| array.nal_units.iter().copied())
+            .collect()
+    }
+
+    /// The video parameter set NAL units.
+    pub fn video_parameter_sets(&self) -> Vec<&'a [u8]> {
+        self.nal_units(HEVC_NAL_UNIT_TYPE_VPS)
+    }
+
+    /// The sequence parameter set NAL units.
+    pub fn sequence_parameter_sets(&self) -> Vec<&'a [u8]> {
+        self.nal_units(HEVC_NAL_UNIT_TYPE_SPS)
+    }
+
+    /// The picture parameter set NAL units.
+    pub fn picture_parameter_sets(&self) -> Vec<&'a [u8]> {
+        self.nal_units(HEVC_NAL_UNIT_TYPE_PPS)
+    }
+
+    /// Parse the first SPS, `None` if there is no SPS or the SPS is invalid.
+    pub fn sequence_parameter_set(&self) -> Option<HevcSequenceParameterSet> {
+        let sps = self.sequence_parameter_sets().into_iter().next()?;
+        HevcSequenceParameterSet::parse(sps)
+            .ok()
+            .map(|(_, sps)| sps)
+    }
+}
+
+impl<'a> HevcNalUnitArray<'a> {
+    /// Parse the array of NAL units.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], HevcNalUnitArray<'a>> {
+        do_parse!(
+            input,
+            // array_completeness (1 bit) + reserved (1 bit) + NAL_unit_type (6 bits)
+            nal_unit_type: be_u8 >>
+            num_nalus: be_u16 >>
+            nal_units: count!(length_data!(be_u16), num_nalus as usize) >>
+
+            (HevcNalUnitArray {
+                array_completeness: nal_unit_type & 0x80 != 0,
+                nal_unit_type: nal_unit_type & 0b11_1111,
+                nal_units,
+            })
+        )
+    }
+}
+
+impl<'a> VideoTag<'a> {
+    /// Parse the HEVCDecoderConfigurationRecord of HEVC sequence header,
+    /// for both `CodecID` 12 and the `hvc1` FourCC of Enhanced RTMP,
+    /// `None` if the tag isn't a HEVC sequence header or the record is invalid.
+    pub fn hevc_decoder_configuration_record(&self) -> Option<HevcDecoderConfigurationRecord<'a>> {
+        match self.codec_configuration()? {
+            VideoCodecConfiguration::Hevc(record) => Some(record),
+            _ => None,
+        }
+    }
+}
+
+/// The sequence parameter set of H.265.
+/// [ITU-T H.265](https://www.itu.int/rec/T-REC-H.265), 7.3.2.2 Sequence parameter set RBSP syntax
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HevcSequenceParameterSet {
+    /// The id of the referred VPS.
+    pub sps_video_parameter_set_id: u8,
+    /// The maximum number of temporal sub-layers minus 1.
+    pub sps_max_sub_layers_minus1: u8,
+    /// Whether the inter prediction is additionally restricted for temporal sub-layers.
+    pub sps_temporal_id_nesting_flag: bool,
+    /// The general profile, tier and level.
+    pub profile_tier_level: HevcProfileTierLevel,
+    /// The id of SPS.
+    pub sps_seq_parameter_set_id: u32,
+    /// The chroma sampling (0 = monochrome, 1 = 4:2:0, 2 = 4:2:2, 3 = 4:4:4).
+    pub chroma_format_idc: u32,
+    /// Whether the three colour components of 4:4:4 are coded separately.
+    pub separate_colour_plane_flag: bool,
+    /// The width of decoded picture in luma samples.
+    pub pic_width_in_luma_samples: u32,
+    /// The height of decoded picture in luma samples.
+    pub pic_height_in_luma_samples: u32,
+    /// The conformance cropping window offsets, if present.
+    pub conformance_window: Option<HevcConformanceWindow>,
+    /// The bit depth of luma minus 8.
+    pub bit_depth_luma_minus8: u32,
+    /// The bit depth of chroma minus 8.
+    pub bit_depth_chroma_minus8: u32,
+    /// The VUI parameters, if present.
+    pub vui_parameters: Option<HevcVuiParameters>,
+}
+
+/// The general profile, tier and level of H.265.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct HevcProfileTierLevel {
+    /// The profile space, 2 bits.
+    pub general_profile_space: u8,
+    /// The tier (0 = Main tier, 1 = High tier).
+    pub general_tier_flag: bool,
+    /// The profile (1 = Main, 2 = Main 10, 3 = Main Still Picture, ...), 5 bits.
+    pub general_profile_idc: u8,
+    /// The profile compatibility flags.
+    pub general_profile_compatibility_flags: u32,
+    /// The progressive, interlaced, non-packed and frame-only source flags
+    /// and the other constraint flags, 48 bits.
+    pub general_constraint_indicator_flags: u64,
+    /// The level, 30 times the level number.
+    pub general_level_idc: u8,
+}
+
+/// The conformance cropping window offsets of H.265 SPS, in the units of chroma samples.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct HevcConformanceWindow {
+    /// The left offset.
+    pub left: u32,
+    /// The right offset.
+    pub right: u32,
+    /// The top offset.
+    pub top: u32,
+    /// The bottom offset.
+    pub bottom: u32,
+}
+
+/// The VUI parameters of H.265 SPS, only the aspect ratio and timing info.
+/// [ITU-T H.265](https://www.itu.int/rec/T-REC-H.265), E.2.1 VUI parameters syntax
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct HevcVuiParameters {
+    /// The sample aspect ratio indicator (255 = EXTENDED_SAR), if present.
+    pub aspect_ratio_idc: Option<u8>,
+    /// The horizontal size of sample aspect ratio, only for EXTENDED_SAR.
+    pub sar_width: u16,
+    /// The vertical size of sample aspect ratio, only for EXTENDED_SAR.
+    pub sar_height: u16,
+    /// Whether each coded picture is a field.
+    pub field_seq_flag: bool,
+    /// The timing info, if present.
+    pub timing_info: Option<HevcTimingInfo>,
+}
+
+/// The timing info of H.265 VUI parameters.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HevcTimingInfo {
+    /// The number of time units of a clock tick.
+    pub num_units_in_tick: u32,
+    /// The number of time units that pass in one second.
+    pub time_scale: u32,
+    /// The number of clock ticks of a picture order count difference of one, if present.
+    pub num_ticks_poc_diff_one: Option<u32>,
+}
+
+impl HevcSequenceParameterSet {
+    /// Parse the SPS NAL unit (including the NAL unit header).
+    pub fn parse(input: &[u8]) -> IResult<&[u8], HevcSequenceParameterSet> {
+        parse_rbsp(input, Self::parse_bits)
+    }
+
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, HevcSequenceParameterSet> {
+        // forbidden_zero_bit (1 bit) + nal_unit_type (6 bits)
+        // + nuh_layer_id (6 bits) + nuh_temporal_id_plus1 (3 bits)
+        if (bits.read(16)? >> 9) as u8 & 0b11_1111 != HEVC_NAL_UNIT_TYPE_SPS {
+            return bits.error(ErrorKind::Tag);
+        }
+        let sps_video_parameter_set_id = bits.read_u8(4)?;
+        let sps_max_sub_layers_minus1 = bits.read_u8(3)?;
+        let sps_temporal_id_nesting_flag = bits.read_bool()?;
+        let profile_tier_level = HevcProfileTierLevel::parse_bits(bits, sps_max_sub_layers_minus1)?;
+        let sps_seq_parameter_set_id = bits.read_ue_max(15)?;
+        let chroma_format_idc = bits.read_ue_max(3)?;
+        let separate_colour_plane_flag = chroma_format_idc == 3 && bits.read_bool()?;
+        let pic_width_in_luma_samples = bits.read_ue()?;
+        let pic_height_in_luma_samples = bits.read_ue()?;
+        let conformance_window = if bits.read_bool()? {
+            Some(HevcConformanceWindow {
+                left: bits.read_ue()?,
+                right: bits.read_ue()?,
+                top: bits.read_ue()?,
+                bottom: bits.read_ue()?,
+            })
+        } else {
+            None
+        };
+        let bit_depth_luma_minus8 = bits.read_ue_max(8)?;
+        let bit_depth_chroma_minus8 = bits.read_ue_max(8)?;
+        let log2_max_pic_order_cnt_lsb_minus4 = bits.read_ue_max(12)?;
+        let sps_sub_layer_ordering_info_present_flag = bits.read_bool()?;
+        let first = if sps_sub_layer_ordering_info_present_flag {
+            0
+        } else {
+            sps_max_sub_layers_minus1
+        };
+        for _ in first..=sps_max_sub_layers_minus1 {
+            // sps_max_dec_pic_buffering_minus1, sps_max_num_reorder_pics,
+            // sps_max_latency_increase_plus1
+            bits.read_ue()?;
+            bits.read_ue()?;
+            bits.read_ue()?;
+        }
+        // log2_min_luma_coding_block_size_minus3, log2_diff_max_min_luma_coding_block_size,
+        // log2_min_luma_transform_block_size_minus2, log2_diff_max_min_luma_transform_block_size,
+        // max_transform_hierarchy_depth_inter, max_transform_hierarchy_depth_intra
+        for _ in 0..6 {
+            bits.read_ue()?;
+        }
+        // scaling_list_enabled_flag + sps_scaling_list_data_present_flag
+        if bits.read_bool()? && bits.read_bool()? {
+            skip_scaling_list_data(bits)?;
+        }
+        // amp_enabled_flag + sample_adaptive_offset_enabled_flag
+        bits.skip(2)?;
+        // pcm_enabled_flag
+        if bits.read_bool()? {
+            // pcm_sample_bit_depth_luma_minus1 (4 bits) + pcm_sample_bit_depth_chroma_minus1 (4 bits)
+            bits.skip(8)?;
+            // log2_min_pcm_luma_coding_block_size_minus3, log2_diff_max_min_pcm_luma_coding_block_size
+            bits.read_ue()?;
+            bits.read_ue()?;
+            // pcm_loop_filter_disabled_flag
+            bits.skip(1)?;
+        }
+        let num_short_term_ref_pic_sets = bits.read_ue()?;
+        if num_short_term_ref_pic_sets > 64 {
+            return bits.error(ErrorKind::Verify);
+        }
+        let mut num_delta_pocs = Vec::with_capacity(num_short_term_ref_pic_sets as usize);
+        for index in 0..num_short_term_ref_pic_sets as usize {
+            let count = skip_st_ref_pic_set(bits, index, &num_delta_pocs)?;
+            num_delta_pocs.push(count);
+        }
+        // long_term_ref_pics_present_flag
+        if bits.read_bool()? {
+            let num_long_term_ref_pics_sps = bits.read_ue()?;
+            for _ in 0..num_long_term_ref_pics_sps {
+                // lt_ref_pic_poc_lsb_sps + used_by_curr_pic_lt_sps_flag
+                bits.skip(log2_max_pic_order_cnt_lsb_minus4 as usize + 4 + 1)?;
+            }
+        }
+        // sps_temporal_mvp_enabled_flag + strong_intra_smoothing_enabled_flag
+        bits.skip(2)?;
+        let vui_parameters = if bits.read_bool()? {
+            Some(HevcVuiParameters::parse_bits(bits)?)
+        } else {
+            None
+        };
+
+        Ok(HevcSequenceParameterSet {
+            sps_video_parameter_set_id,
+            sps_max_sub_layers_minus1,
+            sps_temporal_id_nesting_flag,
+            profile_tier_level,
+            sps_seq_parameter_set_id,
+            chroma_format_idc,
+            separate_colour_plane_flag,
+            pic_width_in_luma_samples,
+            pic_height_in_luma_samples,
+            conformance_window,
+            bit_depth_luma_minus8,
+            bit_depth_chroma_minus8,
+            vui_parameters,
+        })
+    }
+
+    // The chroma sample units (SubWidthC, SubHeightC) of conformance window, Table 6-1.
+    fn chroma_units(&self) -> (u32, u32) {
+        match (self.separate_colour_plane_flag, self.chroma_format_idc) {
+            (false, 1) => (2, 2),
+            (false, 2) => (2, 1),
+            _ => (1, 1),
+        }
+    }
+
+    /// The width of the output pictures, with the conformance window applied,
+    /// `None` if the window is wider than the picture.
+    pub fn width(&self) -> Option<u32> {
+        let window = self.conformance_window.unwrap_or_default();
+        let window = window.left.checked_add(window.right)?;
+        self.pic_width_in_luma_samples
+            .checked_sub(self.chroma_units().0.checked_mul(window)?)
+    }
+
+    /// The height of the output pictures, with the conformance window applied,
+    /// `None` if the window is higher than the picture.
+    pub fn height(&self) -> Option<u32> {
+        let window = self.conformance_window.unwrap_or_default();
+        let window = window.top.checked_add(window.bottom)?;
+        self.pic_height_in_luma_samples
+            .checked_sub(self.chroma_units().1.checked_mul(window)?)
+    }
+
+    /// The bit depth of luma.
+    pub fn bit_depth_luma(&self) -> u32 {
+        self.bit_depth_luma_minus8 + 8
+    }
+
+    /// The bit depth of chroma.
+    pub fn bit_depth_chroma(&self) -> u32 {
+        self.bit_depth_chroma_minus8 + 8
+    }
+
+    /// The frame rate (or the field rate of field coded video) from the VUI timing info,
+    /// if present.
+    pub fn frame_rate(&self) -> Option<f64> {
+        let timing = self.vui_parameters?.timing_info?;
+        if timing.num_units_in_tick == 0 {
+            return None;
+        }
+        Some(f64::from(timing.time_scale) / f64::from(timing.num_units_in_tick))
+    }
+
+    /// The sample aspect ratio (width, height) from the VUI parameters, if present.
+    pub fn sample_aspect_ratio(&self) -> Option<(u16, u16)> {
+        let vui = self.vui_parameters?;
+        match vui.aspect_ratio_idc? {
+            idc @ 1..=16 => Some(SAMPLE_ASPECT_RATIOS[idc as usize]),
+            255 => Some((vui.sar_width, vui.sar_height)),
+            _ => None,
+        }
+    }
+}
+
+impl HevcProfileTierLevel {
+    fn parse_bits<'a>(
+        bits: &mut BitReader<'a>,
+        max_sub_layers_minus1: u8,
+    ) -> BitResult<'a, HevcProfileTierLevel> {
+        let general_profile_space = bits.read_u8(2)?;
+        let general_tier_flag = bits.read_bool()?;
+        let general_profile_idc = bits.read_u8(5)?;
+        let general_profile_compatibility_flags = bits.read(32)?;
+        let general_constraint_indicator_flags =
+            u64::from(bits.read(16)?) << 32 | u64::from(bits.read(32)?);
+        let general_level_idc = bits.read_u8(8)?;
+
+        let mut sub_layer_flags = [(false, false); 8];
+        for flags in sub_layer_flags
+            .iter_mut()
+            .take(max_sub_layers_minus1 as usize)
+        {
+            // sub_layer_profile_present_flag + sub_layer_level_present_flag
+            *flags = (bits.read_bool()?, bits.read_bool()?);
+        }
+        if max_sub_layers_minus1 > 0 {
+            // reserved_zero_2bits
+            bits.skip(2 * (8 - max_sub_layers_minus1 as usize))?;
+        }
+        for &(profile_present, level_present) in &sub_layer_flags[..max_sub_layers_minus1 as usize]
+        {
+            if profile_present {
+                // sub_layer_profile_space ~ sub_layer_inbld_flag
+                bits.skip(88)?;
+            }
+            if level_present {
+                // sub_layer_level_idc
+                bits.skip(8)?;
+            }
+        }
+
+        Ok(HevcProfileTierLevel {
+            general_profile_space,
+            general_tier_flag,
+            general_profile_idc,
+            general_profile_compatibility_flags,
+            general_constraint_indicator_flags,
+            general_level_idc,
+        })
+    }
+}
+
+impl HevcVuiParameters {
+    fn parse_bits<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, HevcVuiParameters> {
+        let mut vui = HevcVuiParameters::default();
+        // aspect_ratio_info_present_flag
+        if bits.read_bool()? {
+            let aspect_ratio_idc = bits.read_u8(8)?;
+            if aspect_ratio_idc == 255 {
+                vui.sar_width = bits.read(16)? as u16;
+                vui.sar_height = bits.read(16)? as u16;
+            }
+            vui.aspect_ratio_idc = Some(aspect_ratio_idc);
+        }
+        // overscan_info_present_flag + overscan_appropriate_flag
+        if bits.read_bool()? {
+            bits.skip(1)?;
+        }
+        // video_signal_type_present_flag
+        if bits.read_bool()? {
+            // video_format (3 bits) + video_full_range_flag
+            bits.skip(4)?;
+            // colour_description_present_flag
+            if bits.read_bool()? {
+                // colour_primaries + transfer_characteristics + matrix_coeffs
+                bits.skip(24)?;
+            }
+        }
+        // chroma_loc_info_present_flag
+        if bits.read_bool()? {
+            // chroma_sample_loc_type_top_field + chroma_sample_loc_type_bottom_field
+            bits.read_ue()?;
+            bits.read_ue()?;
+        }
+        // neutral_chroma_indication_flag
+        bits.skip(1)?;
+        vui.field_seq_flag = bits.read_bool()?;
+        // frame_field_info_present_flag
+        bits.skip(1)?;
+        // default_display_window_flag
+        if bits.read_bool()? {
+            for _ in 0..4 {
+                bits.read_ue()?;
+            }
+        }
+        // vui_timing_info_present_flag
+        if bits.read_bool()? {
+            let num_units_in_tick = bits.read(32)?;
+            let time_scale = bits.read(32)?;
+            // vui_poc_proportional_to_timing_flag
+            let num_ticks_poc_diff_one = if bits.read_bool()? {
+                Some(bits.read_ue()?.saturating_add(1))
+            } else {
+                None
+            };
+            vui.timing_info = Some(HevcTimingInfo {
+                num_units_in_tick,
+                time_scale,
+                num_ticks_poc_diff_one,
+            });
+        }
+        Ok(vui)
+    }
+}
+
+fn skip_scaling_list_data<'a>(bits: &mut BitReader<'a>) -> BitResult<'a, ()> {
+    for size_id in 0..4 {
+        let step = if size_id == 3 { 3 } else { 1 };
+        for _ in (0..6).step_by(step) {
+            // scaling_list_pred_mode_flag
+            if !bits.read_bool()? {
+                // scaling_list_pred_matrix_id_delta
+                bits.read_ue()?;
+            } else {
+                let coef_num = 64.min(1 << (4 + (size_id << 1)));
+                if size_id > 1 {
+                    // scaling_list_dc_coef_minus8
+                    bits.read_se()?;
+                }
+                for _ in 0..coef_num {
+                    // scaling_list_delta_coef
+                    bits.read_se()?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Skip the short-term reference picture set, and return its number of delta POCs.
+// 7.3.7 Short-term reference picture set syntax
+fn skip_st_ref_pic_set<'a>(
+    bits: &mut BitReader<'a>,
+    index: usize,
+    num_delta_pocs: &[u32],
+) -> BitResult<'a, u32> {
+    // inter_ref_pic_set_prediction_flag
+    if index != 0 && bits.read_bool()? {
+        // delta_idx_minus1 is only present in slice header, the reference is the previous set.
+        let ref_num_delta_pocs = num_delta_pocs[index - 1];
+        // delta_rps_sign + abs_delta_rps_minus1
+        bits.skip(1)?;
+        bits.read_ue()?;
+        let mut count = 0;
+        for _ in 0..=ref_num_delta_pocs {
+            // used_by_curr_pic_flag, use_delta_flag (inferred to be 1)
+            if bits.read_bool()? || bits.read_bool()? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    } else {
+        let num_negative_pics = bits.read_ue()?;
+        let num_positive_pics = bits.read_ue()?;
+        if num_negative_pics > 16 || num_positive_pics > 16 {
+            return bits.error(ErrorKind::Verify);
+        }
+        for _ in 0..num_negative_pics + num_positive_pics {
+            // delta_poc_s0_minus1 / delta_poc_s1_minus1 + used_by_curr_pic_s0_flag / s1
+            bits.read_ue()?;
+            bits.skip(1)?;
+        }
+        Ok(num_negative_pics + num_positive_pics)
+    }
+}
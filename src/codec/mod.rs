@@ -0,0 +1,91 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the codec specific structures carried by FLV audio and video tags.
+
+mod aac;
+mod av1;
+mod avc;
+mod bits;
+mod hevc;
+mod mp3;
+mod vp9;
+
+pub use self::{aac::*, av1::*, avc::*, hevc::*, mp3::*, vp9::*};
+
+use crate::parse::{avc_video_packet, AvcPacketType, CodecID, FourCC, VideoPacketType, VideoTag};
+
+/// The codec configuration record of video sequence header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VideoCodecConfiguration<'a> {
+    /// AVCDecoderConfigurationRecord of `CodecID` 7 or `avc1`.
+    Avc(AvcDecoderConfigurationRecord<'a>),
+    /// HEVCDecoderConfigurationRecord of `CodecID` 12 or `hvc1`.
+    Hevc(HevcDecoderConfigurationRecord<'a>),
+    /// AV1CodecConfigurationRecord of `av01`.
+    Av1(Av1CodecConfigurationRecord<'a>),
+    /// VPCodecConfigurationRecord of `vp09`.
+    Vp9(VpCodecConfigurationRecord<'a>),
+}
+
+impl<'a> VideoTag<'a> {
+    /// Parse the codec configuration record of video sequence header,
+    /// for both the legacy `CodecID` and the FourCC of Enhanced RTMP,
+    /// `None` if the tag isn't a sequence header of AVC, HEVC, AV1 or VP9,
+    /// or the record is invalid.
+    pub fn codec_configuration(&self) -> Option<VideoCodecConfiguration<'a>> {
+        let fourcc = match &self.header.ex_header {
+            Some(ex_header) => ex_header.fourcc,
+            None => match self.header.codec_id {
+                CodecID::AVC => FourCC::AVC,
+                CodecID::HEVC => FourCC::HEVC,
+                _ => return None,
+            },
+        };
+        let data = self.sequence_header_data(fourcc)?;
+        match fourcc {
+            FourCC::AVC => AvcDecoderConfigurationRecord::parse(data)
+                .ok()
+                .map(|(_, record)| VideoCodecConfiguration::Avc(record)),
+            FourCC::HEVC => HevcDecoderConfigurationRecord::parse(data)
+                .ok()
+                .map(|(_, record)| VideoCodecConfiguration::Hevc(record)),
+            FourCC::AV1 => Av1CodecConfigurationRecord::parse(data)
+                .ok()
+                .map(|(_, record)| VideoCodecConfiguration::Av1(record)),
+            FourCC::VP9 => VpCodecConfigurationRecord::parse(data)
+                .ok()
+                .map(|(_, record)| VideoCodecConfiguration::Vp9(record)),
+            _ => None,
+        }
+    }
+
+    // The data of sequence header (the configuration record) of the codec,
+    // `None` if the tag isn't a sequence header of the codec.
+    fn sequence_header_data(&self, fourcc: FourCC) -> Option<&'a [u8]> {
+        match &self.header.ex_header {
+            Some(ex_header) => {
+                if ex_header.fourcc != fourcc
+                    || ex_header.packet_type != VideoPacketType::SequenceStart
+                {
+                    return None;
+                }
+                Some(self.body.data)
+            }
+            None => {
+                let codec_id = match fourcc {
+                    FourCC::AVC => CodecID::AVC,
+                    FourCC::HEVC => CodecID::HEVC,
+                    _ => return None,
+                };
+                if self.header.codec_id != codec_id {
+                    return None;
+                }
+                let (_, packet) = avc_video_packet(self.body.data, self.body.data.len()).ok()?;
+                if packet.packet_type != AvcPacketType::SequenceHeader {
+                    return None;
+                }
+                Some(packet.avc_data)
+            }
+        }
+    }
+}
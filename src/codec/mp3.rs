@@ -0,0 +1,306 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the MPEG audio frames carried by the MP3 audio tags.
+// [ISO/IEC 11172-3](https://www.iso.org/standard/22412.html), 2.4.1.3 Header
+// [ISO/IEC 13818-3](https://www.iso.org/standard/26797.html), 2.4.2.3 Header (and the unofficial MPEG 2.5)
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use nom::{
+    bytes::streaming::take,
+    error::{Error, ErrorKind},
+    number::streaming::be_u32,
+    Err as NomErr, IResult,
+};
+
+use crate::parse::{AudioTag, SoundFormat};
+
+/// The size of MPEG audio frame header (without CRC).
+pub const MPEG_AUDIO_FRAME_HEADER_SIZE: usize = 4;
+
+// The bitrates (kbit/s) indexed by `bitrate_index`, 0 for free format.
+const MPEG1_LAYER1_BITRATES: [u16; 15] = [
+    0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+];
+const MPEG1_LAYER2_BITRATES: [u16; 15] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+];
+const MPEG1_LAYER3_BITRATES: [u16; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_LAYER1_BITRATES: [u16; 15] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+];
+const MPEG2_LAYER2_LAYER3_BITRATES: [u16; 15] =
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// The version of MPEG audio.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpegVersion {
+    /// MPEG-1 (ISO/IEC 11172-3)
+    Mpeg1,
+    /// MPEG-2 (ISO/IEC 13818-3), the lower sampling frequencies
+    Mpeg2,
+    /// MPEG-2.5, the unofficial extension of MPEG-2 to the very low sampling frequencies
+    Mpeg25,
+}
+
+/// The layer of MPEG audio.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpegLayer {
+    /// Layer I
+    Layer1,
+    /// Layer II
+    Layer2,
+    /// Layer III (MP3)
+    Layer3,
+}
+
+/// The channel mode of MPEG audio.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpegChannelMode {
+    /// 0, Stereo
+    Stereo,
+    /// 1, Joint stereo
+    JointStereo,
+    /// 2, Dual channel
+    DualChannel,
+    /// 3, Single channel (mono)
+    Mono,
+}
+
+/// The header of MPEG audio frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MpegAudioFrameHeader {
+    /// The version.
+    pub version: MpegVersion,
+    /// The layer.
+    pub layer: MpegLayer,
+    /// Whether the header is followed by a 16 bits CRC.
+    pub crc_protected: bool,
+    /// The bitrate index, 4 bits.
+    pub bitrate_index: u8,
+    /// The bitrate in kbit/s, 0 for free format.
+    pub bitrate: u16,
+    /// The sampling frequency index, 2 bits.
+    pub sampling_frequency_index: u8,
+    /// The sampling frequency in Hz.
+    pub sampling_frequency: u32,
+    /// Whether the frame is padded with one slot.
+    pub padding: bool,
+    /// The private bit.
+    pub private: bool,
+    /// The channel mode.
+    pub channel_mode: MpegChannelMode,
+    /// The mode extension of joint stereo, 2 bits.
+    pub mode_extension: u8,
+    /// The copyright bit.
+    pub copyright: bool,
+    /// Whether the bitstream is original (rather than a copy).
+    pub original: bool,
+    /// The emphasis, 2 bits.
+    pub emphasis: u8,
+}
+
+impl MpegAudioFrameHeader {
+    /// Parse MPEG audio frame header.
+    ///
+    /// The reserved version, layer, bitrate index and sampling frequency index are invalid.
+    pub fn parse(input: &[u8]) -> IResult<&[u8], MpegAudioFrameHeader> {
+        map_opt!(input, be_u32, Self::from_u32)
+    }
+
+    fn from_u32(header: u32) -> Option<MpegAudioFrameHeader> {
+        // frame sync (11 bits)
+        if header >> 21 != 0x7ff {
+            return None;
+        }
+        let version = match (header >> 19) & 0b11 {
+            0b00 => MpegVersion::Mpeg25,
+            0b10 => MpegVersion::Mpeg2,
+            0b11 => MpegVersion::Mpeg1,
+            _ => return None,
+        };
+        let layer = match (header >> 17) & 0b11 {
+            0b01 => MpegLayer::Layer3,
+            0b10 => MpegLayer::Layer2,
+            0b11 => MpegLayer::Layer1,
+            _ => return None,
+        };
+        let bitrate_index = ((header >> 12) & 0b1111) as u8;
+        let bitrates = match (version, layer) {
+            (MpegVersion::Mpeg1, MpegLayer::Layer1) => &MPEG1_LAYER1_BITRATES,
+            (MpegVersion::Mpeg1, MpegLayer::Layer2) => &MPEG1_LAYER2_BITRATES,
+            (MpegVersion::Mpeg1, MpegLayer::Layer3) => &MPEG1_LAYER3_BITRATES,
+            (_, MpegLayer::Layer1) => &MPEG2_LAYER1_BITRATES,
+            (_, _) => &MPEG2_LAYER2_LAYER3_BITRATES,
+        };
+        let bitrate = *bitrates.get(usize::from(bitrate_index))?;
+        let sampling_frequency_index = ((header >> 10) & 0b11) as u8;
+        let sampling_frequency = match sampling_frequency_index {
+            0 => 44100,
+            1 => 48000,
+            2 => 32000,
+            _ => return None,
+        } >> match version {
+            MpegVersion::Mpeg1 => 0,
+            MpegVersion::Mpeg2 => 1,
+            MpegVersion::Mpeg25 => 2,
+        };
+        let channel_mode = match (header >> 6) & 0b11 {
+            0 => MpegChannelMode::Stereo,
+            1 => MpegChannelMode::JointStereo,
+            2 => MpegChannelMode::DualChannel,
+            _ => MpegChannelMode::Mono,
+        };
+        Some(MpegAudioFrameHeader {
+            version,
+            layer,
+            crc_protected: (header >> 16) & 1 == 0,
+            bitrate_index,
+            bitrate,
+            sampling_frequency_index,
+            sampling_frequency,
+            padding: (header >> 9) & 1 == 1,
+            private: (header >> 8) & 1 == 1,
+            channel_mode,
+            mode_extension: ((header >> 4) & 0b11) as u8,
+            copyright: (header >> 3) & 1 == 1,
+            original: (header >> 2) & 1 == 1,
+            emphasis: (header & 0b11) as u8,
+        })
+    }
+
+    /// The number of channels.
+    pub fn channels(&self) -> u8 {
+        match self.channel_mode {
+            MpegChannelMode::Mono => 1,
+            _ => 2,
+        }
+    }
+
+    /// The number of samples (per channel) of the frame.
+    pub fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (MpegLayer::Layer1, _) => 384,
+            (MpegLayer::Layer2, _) | (MpegLayer::Layer3, MpegVersion::Mpeg1) => 1152,
+            (MpegLayer::Layer3, _) => 576,
+        }
+    }
+
+    /// The size of the whole frame (including the header) in bytes,
+    /// `None` for free format, whose frame size isn't signalled.
+    pub fn frame_size(&self) -> Option<usize> {
+        if self.bitrate == 0 {
+            return None;
+        }
+        let bitrate = u32::from(self.bitrate) * 1000;
+        let padding = u32::from(self.padding);
+        let size = match self.layer {
+            // 4 bytes per slot
+            MpegLayer::Layer1 => (12 * bitrate / self.sampling_frequency + padding) * 4,
+            _ => self.samples_per_frame() / 8 * bitrate / self.sampling_frequency + padding,
+        };
+        Some(size as usize)
+    }
+}
+
+/// The MPEG audio frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MpegAudioFrame<'a> {
+    /// The frame header.
+    pub header: MpegAudioFrameHeader,
+    /// The whole frame, including the header.
+    pub data: &'a [u8],
+}
+
+impl<'a> MpegAudioFrame<'a> {
+    /// Parse MPEG audio frame.
+    ///
+    /// The frames of free format are invalid, since their sizes aren't signalled.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], MpegAudioFrame<'a>> {
+        let (_, header) = MpegAudioFrameHeader::parse(input)?;
+        let frame_size = match header.frame_size() {
+            Some(frame_size) if frame_size >= MPEG_AUDIO_FRAME_HEADER_SIZE => frame_size,
+            _ => return Err(NomErr::Error(Error::new(input, ErrorKind::LengthValue))),
+        };
+        let (remain, data) = take(frame_size)(input)?;
+        Ok((remain, MpegAudioFrame { header, data }))
+    }
+}
+
+/// The MPEG audio frames of an audio tag body.
+///
+/// The frames may be split across the audio tags, so the body may start with the rest
+/// of the frame begun in the previous tag, and end with the beginning of a frame
+/// continued in the next tag.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MpegAudioFrames<'a> {
+    /// The leading bytes before the first frame header.
+    pub leading: &'a [u8],
+    /// The complete frames.
+    pub frames: Vec<MpegAudioFrame<'a>>,
+    /// The trailing bytes of the incomplete frame at the end.
+    pub trailing: &'a [u8],
+}
+
+impl<'a> MpegAudioFrames<'a> {
+    /// Split the data (usually an audio tag body) into MPEG audio frames.
+    ///
+    /// The leading bytes are skipped until a frame header is found,
+    /// and the invalid frame headers are skipped.
+    pub fn split(data: &'a [u8]) -> MpegAudioFrames<'a> {
+        let start = find_frame(data);
+        let mut remain = &data[start..];
+        let mut frames = Vec::new();
+        while !remain.is_empty() {
+            match MpegAudioFrame::parse(remain) {
+                Ok((rest, frame)) => {
+                    frames.push(frame);
+                    remain = rest;
+                }
+                Err(NomErr::Incomplete(_)) => break,
+                Err(_) => remain = &remain[1 + find_frame(&remain[1..])..],
+            }
+        }
+        MpegAudioFrames {
+            leading: &data[..start],
+            frames,
+            trailing: remain,
+        }
+    }
+
+    /// Whether the data is split from the frames, i.e. it starts with the rest of
+    /// a frame or ends with the beginning of a frame.
+    pub fn is_split(&self) -> bool {
+        !self.leading.is_empty() || !self.trailing.is_empty()
+    }
+}
+
+impl<'a> AudioTag<'a> {
+    /// Split the body of MP3 audio tag into MPEG audio frames,
+    /// `None` if the sound format isn't MP3.
+    pub fn mpeg_audio_frames(&self) -> Option<MpegAudioFrames<'a>> {
+        match self.header.sound_format {
+            SoundFormat::MP3 | SoundFormat::MP3_8kHz => {
+                Some(MpegAudioFrames::split(self.body.data))
+            }
+            _ => None,
+        }
+    }
+}
+
+// The position of the first valid frame header (or a partial frame header at the end).
+fn find_frame(data: &[u8]) -> usize {
+    (0..data.len())
+        .find(|&pos| match MpegAudioFrameHeader::parse(&data[pos..]) {
+            Ok(_) => true,
+            // The frame sync of the partial frame header at the end.
+            Err(NomErr::Incomplete(_)) => {
+                data[pos] == 0xff && data.get(pos + 1).is_none_or(|byte| byte & 0xe0 == 0xe0)
+            }
+            Err(_) => false,
+        })
+        .unwrap_or(data.len())
+}
@@ -0,0 +1,85 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the VPCodecConfigurationRecord carried by the VP9 sequence start.
+// [VP Codec ISO Media File Format Binding](https://www.webmproject.org/vp9/mp4/), VP Codec Configuration Box
+
+use nom::{
+    number::streaming::{be_u16, be_u24, be_u8},
+    IResult,
+};
+
+use super::VideoCodecConfiguration;
+use crate::parse::VideoTag;
+
+/// The VPCodecConfigurationRecord (`vpcC`) of VP8 and VP9,
+/// prefixed with the version and flags of the `vpcC` box.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VpCodecConfigurationRecord<'a> {
+    /// The version of `vpcC` box, 1 for this layout.
+    pub version: u8,
+    /// The flags of `vpcC` box, 24 bits.
+    pub flags: u32,
+    /// The profile.
+    pub profile: u8,
+    /// The level, 10 times the level number.
+    pub level: u8,
+    /// The bit depth (8, 10 or 12), 4 bits.
+    pub bit_depth: u8,
+    /// The chroma subsampling (0 = 4:2:0 vertical, 1 = 4:2:0 colocated, 2 = 4:2:2,
+    /// 3 = 4:4:4), 3 bits.
+    pub chroma_subsampling: u8,
+    /// Whether the black level and color range are full (rather than limited) range.
+    pub video_full_range_flag: bool,
+    /// The color primaries, ISO/IEC 23001-8.
+    pub colour_primaries: u8,
+    /// The transfer characteristics, ISO/IEC 23001-8.
+    pub transfer_characteristics: u8,
+    /// The matrix coefficients, ISO/IEC 23001-8.
+    pub matrix_coefficients: u8,
+    /// The codec initialization data, always empty for VP8 and VP9.
+    pub codec_initialization_data: &'a [u8],
+}
+
+impl<'a> VpCodecConfigurationRecord<'a> {
+    /// Parse VPCodecConfigurationRecord.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], VpCodecConfigurationRecord<'a>> {
+        do_parse!(
+            input,
+            version: verify!(be_u8, |version: &u8| *version == 1) >>
+            flags: be_u24 >>
+            profile: be_u8 >>
+            level: be_u8 >>
+            // bitDepth (4 bits) + chromaSubsampling (3 bits) + videoFullRangeFlag (1 bit)
+            format: be_u8 >>
+            colour_primaries: be_u8 >>
+            transfer_characteristics: be_u8 >>
+            matrix_coefficients: be_u8 >>
+            codec_initialization_data: length_data!(be_u16) >>
+
+            (VpCodecConfigurationRecord {
+                version,
+                flags,
+                profile,
+                level,
+                bit_depth: format >> 4,
+                chroma_subsampling: (format >> 1) & 0b111,
+                video_full_range_flag: format & 1 != 0,
+                colour_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
+                codec_initialization_data,
+            })
+        )
+    }
+}
+
+impl<'a> VideoTag<'a> {
+    /// Parse the VPCodecConfigurationRecord of VP9 sequence start (the `vp09` FourCC of Enhanced RTMP),
+    /// `None` if the tag isn't a VP9 sequence start or the record is invalid.
+    pub fn vp9_codec_configuration_record(&self) -> Option<VpCodecConfigurationRecord<'a>> {
+        match self.codec_configuration()? {
+            VideoCodecConfiguration::Vp9(record) => Some(record),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Decrypt the filtered tags of `Encryption` and `SE` filters (AES-128-CBC) with a known key,
+// turning them back into plain tags.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use cbc::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+#[cfg(feature = "std")]
+use nom::number::streaming::be_u32;
+
+use crate::parse::{FilterParams, FilteredTag, FlvTag, FlvTagData, FlvTagHeader};
+#[cfg(feature = "std")]
+use crate::{
+    error::{FlvError, FlvStructure},
+    parse::{FlvFileHeader, FlvTagIter},
+    write::FlvWriter,
+};
+
+/// The size of AES-128 key.
+pub const AES_128_KEY_SIZE: usize = 16;
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// The error of decrypting filtered tag.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DecryptError {
+    /// The filter is neither `Encryption` nor `SE`.
+    UnsupportedFilter,
+    /// The encrypted data isn't a multiple of the block size or its padding is invalid,
+    /// usually because the key is wrong.
+    InvalidPadding,
+    /// The decrypted tag data cannot be parsed.
+    InvalidTagData,
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DecryptError::UnsupportedFilter => "unsupported filter",
+            DecryptError::InvalidPadding => "invalid padding of decrypted data",
+            DecryptError::InvalidTagData => "invalid decrypted tag data",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecryptError {}
+
+#[cfg(feature = "std")]
+impl From<DecryptError> for std::io::Error {
+    fn from(err: DecryptError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// The AES-128-CBC decryptor of filtered tags, with the key of the content.
+///
+/// The IV of each tag is taken from its filter params, and the decrypted data
+/// is unpadded as PKCS#7.
+#[derive(Clone)]
+pub struct FlvDecryptor {
+    key: [u8; AES_128_KEY_SIZE],
+}
+
+impl fmt::Debug for FlvDecryptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Don't leak the key.
+        f.debug_struct("FlvDecryptor").finish_non_exhaustive()
+    }
+}
+
+impl FlvDecryptor {
+    /// Create a decryptor with the AES-128 key.
+    pub fn new(key: [u8; AES_128_KEY_SIZE]) -> Self {
+        Self { key }
+    }
+
+    /// Decrypt filtered tag data, and append the plain tag data (the unencrypted
+    /// tag data header followed by the decrypted body) to `buf`.
+    ///
+    /// The data of `SE` filter whose packet isn't encrypted is appended as is.
+    pub fn decrypt_data(
+        &self,
+        filtered: &FilteredTag,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), DecryptError> {
+        let iv = match filtered.filter_params {
+            FilterParams::Encryption(params) => Some(params.iv),
+            FilterParams::SelectiveEncryption(params) => params.iv,
+            FilterParams::Unknown(_) => return Err(DecryptError::UnsupportedFilter),
+        };
+        let start = buf.len();
+        buf.extend_from_slice(filtered.header);
+        buf.extend_from_slice(filtered.data);
+        if let Some(iv) = iv {
+            let body = start + filtered.header.len();
+            let decrypted = Aes128CbcDec::new(&self.key.into(), &iv.into())
+                .decrypt_padded_mut::<Pkcs7>(&mut buf[body..])
+                .map(|decrypted| decrypted.len());
+            match decrypted {
+                Ok(size) => buf.truncate(body + size),
+                Err(_) => {
+                    buf.truncate(start);
+                    return Err(DecryptError::InvalidPadding);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt filtered tag into plain tag with the Filter bit cleared,
+    /// whose data refers to `buf`; the tag that isn't filtered is returned as is.
+    ///
+    /// The `data_size` of tag header is the size of the plain tag data.
+    pub fn decrypt_tag<'a>(
+        &self,
+        tag: &FlvTag<'a>,
+        buf: &'a mut Vec<u8>,
+    ) -> Result<FlvTag<'a>, DecryptError> {
+        let filtered = match &tag.data {
+            FlvTagData::Filtered(filtered) => filtered,
+            _ => return Ok(tag.clone()),
+        };
+        buf.clear();
+        self.decrypt_data(filtered, buf)?;
+        let buf: &'a [u8] = buf;
+        let (_, data) = FlvTagData::parse(buf, filtered.tag_type, buf.len())
+            .map_err(|_| DecryptError::InvalidTagData)?;
+        Ok(FlvTag {
+            header: FlvTagHeader {
+                filter: false,
+                data_size: buf.len() as u32,
+                ..tag.header
+            },
+            data,
+        })
+    }
+}
+
+/// Decrypt the filtered tags of FLV file with the AES-128 key, write the plain
+/// FLV file, and return the number of decrypted tags.
+///
+/// The other tags are written as is, the extension data of file header is dropped.
+#[cfg(feature = "std")]
+pub fn decrypt_flv<W: Write>(
+    input: &[u8],
+    key: [u8; AES_128_KEY_SIZE],
+    writer: &mut W,
+) -> io::Result<u64> {
+    let (body, (header, _)) = FlvFileHeader::parse_with_extension(input)
+        .map_err(|err| FlvError::from_nom(err, FlvStructure::FileHeader, input, 0, None))?;
+    let body_offset = input.len() - body.len();
+    let (tags, _) = be_u32(body).map_err(|err| {
+        FlvError::from_nom(err, FlvStructure::PreviousTagSize, body, body_offset, None)
+    })?;
+    // The 9-byte header without extension data.
+    let header = FlvFileHeader {
+        data_offset: 9,
+        ..header
+    };
+    let decryptor = FlvDecryptor::new(key);
+    let mut writer = FlvWriter::new(writer, &header)?;
+    let mut iter = FlvTagIter::with_offset(tags, input.len() - tags.len());
+    let mut buf = Vec::new();
+    let mut decrypted = 0;
+    for (tag, _) in iter.by_ref() {
+        if tag.header.filter {
+            decrypted += 1;
+        }
+        writer.write_tag(&decryptor.decrypt_tag(&tag, &mut buf)?)?;
+    }
+    if let Some(err) = iter.error() {
+        return Err((*err).into());
+    }
+    writer.into_inner()?;
+    Ok(decrypted)
+}
@@ -0,0 +1,161 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Incremental (push-based) demuxing of FLV streams, e.g. HTTP-FLV live streams,
+// whose bytes arrive in arbitrary chunks.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use nom::number::streaming::be_u32;
+
+use crate::{
+    error::{FlvError, FlvStructure},
+    parse::{flv_tag_with_size, FlvFileHeader, FlvTag},
+};
+
+const PREVIOUS_TAG_SIZE_SIZE: usize = 4;
+
+/// The item yielded by `FlvDemuxer`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlvDemuxEvent<'a> {
+    /// The header of FLV file, always yielded first.
+    Header(FlvFileHeader),
+    /// A complete FLV tag and the previous tag size that follows it.
+    Tag(FlvTag<'a>, u32),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum DemuxState {
+    Header,
+    FirstPreviousTagSize,
+    Tags,
+}
+
+/// A stateful FLV demuxer, which can be fed with arbitrary byte chunks.
+///
+/// Incomplete data (such as a tag header split across chunk boundaries)
+/// is buffered internally until enough bytes are pushed.
+///
+/// ```
+/// # use flvparse::{FlvDemuxer, FlvDemuxEvent};
+/// # fn handle(_: FlvDemuxEvent) {}
+/// # let chunks: Vec<&[u8]> = vec![];
+/// let mut demuxer = FlvDemuxer::new();
+/// for chunk in chunks {
+///     demuxer.push(chunk);
+///     while let Some(event) = demuxer.next_event().unwrap() {
+///         handle(event);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FlvDemuxer {
+    buffer: Vec<u8>,
+    // The number of bytes at the front of `buffer` that have been yielded already.
+    consumed: usize,
+    // The absolute offset of the front of `buffer`.
+    offset: usize,
+    // The index of the next tag.
+    tag_index: usize,
+    state: DemuxState,
+    first_previous_tag_size: Option<u32>,
+}
+
+impl Default for FlvDemuxer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlvDemuxer {
+    /// Create a new FLV demuxer that expects the FLV file header first.
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            consumed: 0,
+            offset: 0,
+            tag_index: 0,
+            state: DemuxState::Header,
+            first_previous_tag_size: None,
+        }
+    }
+
+    /// Push a chunk of bytes into the demuxer.
+    pub fn push(&mut self, data: &[u8]) {
+        // Drop the demuxed bytes once they are the most of buffer,
+        // so the bytes are moved only a few times.
+        if self.consumed > self.buffer.len() / 2 {
+            self.buffer.drain(..self.consumed);
+            self.offset += self.consumed;
+            self.consumed = 0;
+        }
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// The number of buffered bytes that haven't been demuxed yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len() - self.consumed
+    }
+
+    /// The size of the first previous tag (always 0), if it has been demuxed.
+    pub fn first_previous_tag_size(&self) -> Option<u32> {
+        self.first_previous_tag_size
+    }
+
+    /// Demux the next item from the buffered bytes.
+    ///
+    /// Returns `Ok(None)` if more bytes are needed to demux the next item,
+    /// or the error of the invalid structure, which is never truncated.
+    pub fn next_event(&mut self) -> Result<Option<FlvDemuxEvent<'_>>, FlvError> {
+        loop {
+            let input = &self.buffer[self.consumed..];
+            let offset = self.offset + self.consumed;
+            match self.state {
+                DemuxState::Header => {
+                    // The extension data of file header is skipped.
+                    return match FlvFileHeader::parse_with_extension(input) {
+                        Ok((remain, (header, _extension))) => {
+                            self.consumed += input.len() - remain.len();
+                            self.state = DemuxState::FirstPreviousTagSize;
+                            Ok(Some(FlvDemuxEvent::Header(header)))
+                        }
+                        Err(err) => not_truncated(FlvError::from_nom(
+                            err,
+                            FlvStructure::FileHeader,
+                            input,
+                            offset,
+                            None,
+                        )),
+                    };
+                }
+                DemuxState::FirstPreviousTagSize => match be_u32::<_, ()>(input) {
+                    Ok((_, size)) => {
+                        self.consumed += PREVIOUS_TAG_SIZE_SIZE;
+                        self.first_previous_tag_size = Some(size);
+                        self.state = DemuxState::Tags;
+                    }
+                    Err(_) => return Ok(None),
+                },
+                DemuxState::Tags => {
+                    return match flv_tag_with_size(input, offset, self.tag_index) {
+                        Ok((remain, tag, previous_tag_size)) => {
+                            self.consumed += input.len() - remain.len();
+                            self.tag_index += 1;
+                            Ok(Some(FlvDemuxEvent::Tag(tag, previous_tag_size)))
+                        }
+                        Err(err) => not_truncated(err),
+                    };
+                }
+            }
+        }
+    }
+}
+
+// More bytes are needed if the input is truncated.
+fn not_truncated<T>(err: FlvError) -> Result<Option<T>, FlvError> {
+    if err.is_truncated() {
+        Ok(None)
+    } else {
+        Err(err)
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// The error of parsing FLV file, with the structure that failed and its position.
+
+use core::fmt;
+
+use nom::{error::ErrorKind, Err as NomErr};
+
+/// The structure of FLV file that failed to be parsed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlvStructure {
+    /// The file header.
+    FileHeader,
+    /// The previous tag size (including the first one).
+    PreviousTagSize,
+    /// The tag header.
+    TagHeader,
+    /// The tag data as a whole (e.g. fewer bytes than the data size).
+    TagData,
+    /// The audio tag header.
+    AudioTagHeader,
+    /// The audio tag body.
+    AudioTagBody,
+    /// The video tag header.
+    VideoTagHeader,
+    /// The video tag body.
+    VideoTagBody,
+    /// The script tag data, the name and the AMF value.
+    ScriptData,
+    /// The encryption tag header and filter params of filtered tag data.
+    EncryptionHeader,
+}
+
+impl fmt::Display for FlvStructure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FlvStructure::FileHeader => "file header",
+            FlvStructure::PreviousTagSize => "previous tag size",
+            FlvStructure::TagHeader => "tag header",
+            FlvStructure::TagData => "tag data",
+            FlvStructure::AudioTagHeader => "audio tag header",
+            FlvStructure::AudioTagBody => "audio tag body",
+            FlvStructure::VideoTagHeader => "video tag header",
+            FlvStructure::VideoTagBody => "video tag body",
+            FlvStructure::ScriptData => "script data",
+            FlvStructure::EncryptionHeader => "encryption header",
+        })
+    }
+}
+
+/// The kind of `FlvError`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlvErrorKind {
+    /// The input ends before the structure is complete.
+    Truncated,
+    /// The structure is invalid, with the kind of the parser that failed.
+    Malformed(ErrorKind),
+}
+
+/// The error of parsing FLV file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FlvError {
+    /// The structure that failed to be parsed.
+    pub structure: FlvStructure,
+    /// Whether the input is truncated or malformed.
+    pub kind: FlvErrorKind,
+    /// The absolute byte offset (from the start of file) where the parsing failed:
+    /// the start of the structure if truncated, or the invalid part if malformed.
+    pub offset: u64,
+    /// The index of the tag that failed to be parsed, `None` for the file header
+    /// and the first previous tag size.
+    pub tag_index: Option<usize>,
+}
+
+impl FlvError {
+    /// Whether the input is truncated, i.e. more input may make the parsing succeed.
+    pub fn is_truncated(&self) -> bool {
+        self.kind == FlvErrorKind::Truncated
+    }
+
+    // Create the error from the error of nom parser, which parses `input` at `offset`.
+    pub(crate) fn from_nom(
+        err: NomErr<nom::error::Error<&[u8]>>,
+        structure: FlvStructure,
+        input: &[u8],
+        offset: usize,
+        tag_index: Option<usize>,
+    ) -> Self {
+        let (kind, offset) = match err {
+            NomErr::Incomplete(_) => (FlvErrorKind::Truncated, offset),
+            NomErr::Error(err) | NomErr::Failure(err) => {
+                // The error may refer to a copy of input (e.g. NAL unit payload).
+                let start = input.as_ptr() as usize;
+                let position = err.input.as_ptr() as usize;
+                let offset = if position >= start && position <= start + input.len() {
+                    offset + (position - start)
+                } else {
+                    offset
+                };
+                (FlvErrorKind::Malformed(err.code), offset)
+            }
+        };
+        FlvError {
+            structure,
+            kind,
+            offset: offset as u64,
+            tag_index,
+        }
+    }
+
+    // Treat the incomplete parsing as malformed, since the whole structure is available.
+    pub(crate) fn complete(mut self) -> Self {
+        if self.kind == FlvErrorKind::Truncated {
+            self.kind = FlvErrorKind::Malformed(ErrorKind::Eof);
+        }
+        self
+    }
+}
+
+impl fmt::Display for FlvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            FlvErrorKind::Truncated => write!(f, "truncated {}", self.structure)?,
+            FlvErrorKind::Malformed(kind) => {
+                write!(f, "malformed {} ({:?})", self.structure, kind)?
+            }
+        }
+        write!(f, " at offset {}", self.offset)?;
+        if let Some(index) = self.tag_index {
+            write!(f, " in tag {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FlvError {}
+
+#[cfg(feature = "std")]
+impl From<FlvError> for std::io::Error {
+    fn from(err: FlvError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Extract the AAC audio of FLV file as ADTS stream.
+// [ISO/IEC 13818-7](https://www.iso.org/standard/43345.html), 6.2 Audio Data Transport Stream, ADTS
+
+use std::io::{self, Write};
+
+use super::{flv_tags, invalid_data, tags_error};
+use crate::{
+    codec::AudioSpecificConfig,
+    parse::{aac_audio_packet, AACAudioPacket, AACPacketType, AudioTag, FlvTagData, SoundFormat},
+};
+
+/// The size of ADTS header without CRC.
+pub const ADTS_HEADER_SIZE: usize = 7;
+const MAX_ADTS_FRAME_LENGTH: usize = 0x1fff;
+
+impl AudioSpecificConfig {
+    /// Create the ADTS header (without CRC) of a raw AAC frame of `frame_size` bytes.
+    ///
+    /// Only the object types 1 ~ 4, the sampling frequency indexes 0 ~ 12
+    /// and the channel configurations 1 ~ 7 can be represented by ADTS header.
+    /// For HE-AAC, the header describes the core AAC stream.
+    pub fn adts_header(&self, frame_size: usize) -> io::Result<[u8; ADTS_HEADER_SIZE]> {
+        if !(1..=4).contains(&self.audio_object_type) {
+            return Err(invalid_data(
+                "audio object type cannot be represented by ADTS",
+            ));
+        }
+        if self.sampling_frequency_index > 12 {
+            return Err(invalid_data(
+                "explicit sampling frequency cannot be represented by ADTS",
+            ));
+        }
+        if !(1..=7).contains(&self.channel_configuration) {
+            return Err(invalid_data(
+                "channel configuration cannot be represented by ADTS",
+            ));
+        }
+        let frame_length = ADTS_HEADER_SIZE + frame_size;
+        if frame_length > MAX_ADTS_FRAME_LENGTH {
+            return Err(invalid_data("AAC frame is too large for ADTS"));
+        }
+
+        let profile = self.audio_object_type - 1;
+        let channels = self.channel_configuration;
+        let frame_length = frame_length as u16;
+        Ok([
+            // syncword (12 bits)
+            0xff,
+            // syncword, ID = 0 (MPEG-4), layer = 0, protection_absent = 1
+            0xf1,
+            profile << 6 | self.sampling_frequency_index << 2 | channels >> 2,
+            (channels & 0b11) << 6 | (frame_length >> 11) as u8,
+            (frame_length >> 3) as u8,
+            // adts_buffer_fullness = 0x7ff (VBR)
+            ((frame_length & 0b111) as u8) << 5 | 0x1f,
+            // number_of_raw_data_blocks_in_frame = 0
+            0xfc,
+        ])
+    }
+}
+
+/// A writer that writes the raw AAC frames as ADTS stream.
+///
+/// The AudioSpecificConfig is taken from the AAC sequence headers,
+/// the latest sequence header applies to the following raw frames.
+#[derive(Debug)]
+pub struct AdtsWriter<W: Write> {
+    writer: W,
+    config: Option<AudioSpecificConfig>,
+    frames: u64,
+}
+
+impl<W: Write> AdtsWriter<W> {
+    /// Create an ADTS writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            config: None,
+            frames: 0,
+        }
+    }
+
+    /// Write the AAC packet of audio tag, the audio tag must be AAC.
+    pub fn write_audio_tag(&mut self, audio: &AudioTag) -> io::Result<()> {
+        if audio.header.sound_format != SoundFormat::AAC {
+            return Err(invalid_data("audio tag is not AAC"));
+        }
+        let (_, packet) = aac_audio_packet(audio.body.data, audio.body.data.len())
+            .map_err(|_| invalid_data("invalid AAC audio packet"))?;
+        self.write_packet(&packet)
+    }
+
+    /// Write AAC packet.
+    ///
+    /// A sequence header updates the AudioSpecificConfig, a raw frame is written
+    /// with an ADTS header, raw frames before the first sequence header are invalid.
+    pub fn write_packet(&mut self, packet: &AACAudioPacket) -> io::Result<()> {
+        match packet.packet_type {
+            AACPacketType::SequenceHeader => {
+                let config = packet
+                    .audio_specific_config()
+                    .ok_or_else(|| invalid_data("invalid AudioSpecificConfig"))?;
+                self.config = Some(config);
+                Ok(())
+            }
+            AACPacketType::Raw => {
+                let config = self
+                    .config
+                    .as_ref()
+                    .ok_or_else(|| invalid_data("AAC raw frame before sequence header"))?;
+                let header = config.adts_header(packet.aac_data.len())?;
+                self.writer.write_all(&header)?;
+                self.writer.write_all(packet.aac_data)?;
+                self.frames += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the current AudioSpecificConfig.
+    pub fn config(&self) -> Option<&AudioSpecificConfig> {
+        self.config.as_ref()
+    }
+
+    /// Get the number of written ADTS frames.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush the underlying writer and unwrap it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Extract the AAC audio of FLV file as ADTS stream,
+/// and return the number of written ADTS frames.
+///
+/// The audio tags of other sound formats are ignored.
+/// Fails with the error of the first tag that cannot be parsed.
+pub fn extract_aac<W: Write>(input: &[u8], writer: &mut W) -> io::Result<u64> {
+    let mut adts = AdtsWriter::new(writer);
+    let mut tags = flv_tags(input)?;
+    for (tag, _) in tags.by_ref() {
+        match tag.data {
+            FlvTagData::Audio(audio) if audio.header.sound_format == SoundFormat::AAC => {
+                adts.write_audio_tag(&audio)?
+            }
+            _ => {}
+        }
+    }
+    tags_error(&tags)?;
+    let frames = adts.frames();
+    adts.into_inner()?;
+    Ok(frames)
+}
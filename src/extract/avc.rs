@@ -0,0 +1,173 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Extract the AVC video of FLV file as H.264 Annex B byte stream.
+// [ITU-T H.264](https://www.itu.int/rec/T-REC-H.264), Annex B Byte stream format
+
+use std::io::{self, Write};
+
+use super::{flv_tags, invalid_data, tags_error};
+use crate::{
+    codec::AvcDecoderConfigurationRecord,
+    parse::{avc_video_packet, AvcPacketType, AvcVideoPacket, CodecID, FlvTagData, VideoTag},
+};
+
+/// The start code prefixing each NAL unit of Annex B byte stream.
+pub const ANNEX_B_START_CODE: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
+
+const NAL_UNIT_TYPE_IDR: u8 = 5;
+const NAL_UNIT_TYPE_SPS: u8 = 7;
+const NAL_UNIT_TYPE_PPS: u8 = 8;
+
+// The parameter sets and NAL length size of the latest sequence header.
+#[derive(Debug)]
+struct AvcConfig {
+    nal_length_size: usize,
+    parameter_sets: Vec<Vec<u8>>,
+}
+
+/// A writer that writes the length prefixed NAL units of AVC video packets
+/// as H.264 Annex B byte stream.
+///
+/// The NAL length size and the SPS and PPS are taken from the AVC sequence headers,
+/// the SPS and PPS are inserted before every IDR picture.
+#[derive(Debug)]
+pub struct AnnexBWriter<W: Write> {
+    writer: W,
+    config: Option<AvcConfig>,
+    frames: u64,
+}
+
+impl<W: Write> AnnexBWriter<W> {
+    /// Create an Annex B writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            config: None,
+            frames: 0,
+        }
+    }
+
+    /// Write the AVC packet of video tag, the video tag must be AVC.
+    pub fn write_video_tag(&mut self, video: &VideoTag) -> io::Result<()> {
+        if video.header.codec_id != CodecID::AVC {
+            return Err(invalid_data("video tag is not AVC"));
+        }
+        let (_, packet) = avc_video_packet(video.body.data, video.body.data.len())
+            .map_err(|_| invalid_data("invalid AVC video packet"))?;
+        self.write_packet(&packet)
+    }
+
+    /// Write AVC packet.
+    ///
+    /// A sequence header updates the NAL length size and the parameter sets,
+    /// the NAL units are written with start codes, NAL units before the first
+    /// sequence header are invalid.
+    pub fn write_packet(&mut self, packet: &AvcVideoPacket) -> io::Result<()> {
+        match packet.packet_type {
+            AvcPacketType::SequenceHeader => {
+                let record = packet
+                    .decoder_configuration_record()
+                    .ok_or_else(|| invalid_data("invalid AVCDecoderConfigurationRecord"))?;
+                self.config = Some(AvcConfig::from(&record));
+                Ok(())
+            }
+            AvcPacketType::NALU => self.write_nal_units(packet.avc_data),
+            AvcPacketType::EndOfSequence | AvcPacketType::Unknown(_) => Ok(()),
+        }
+    }
+
+    fn write_nal_units(&mut self, mut data: &[u8]) -> io::Result<()> {
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| invalid_data("AVC NAL unit before sequence header"))?;
+        // Whether the SPS and PPS are present in the current access unit.
+        let mut has_parameter_sets = false;
+        while !data.is_empty() {
+            if data.len() < config.nal_length_size {
+                return Err(invalid_data("truncated NAL unit length"));
+            }
+            let (length, rest) = data.split_at(config.nal_length_size);
+            let length = length
+                .iter()
+                .fold(0usize, |length, byte| length << 8 | usize::from(*byte));
+            if rest.len() < length {
+                return Err(invalid_data("truncated NAL unit"));
+            }
+            let (nal_unit, rest) = rest.split_at(length);
+            data = rest;
+            if nal_unit.is_empty() {
+                continue;
+            }
+
+            match nal_unit[0] & 0x1f {
+                NAL_UNIT_TYPE_SPS | NAL_UNIT_TYPE_PPS => has_parameter_sets = true,
+                NAL_UNIT_TYPE_IDR if !has_parameter_sets => {
+                    for parameter_set in &config.parameter_sets {
+                        self.writer.write_all(&ANNEX_B_START_CODE)?;
+                        self.writer.write_all(parameter_set)?;
+                    }
+                    has_parameter_sets = true;
+                }
+                _ => {}
+            }
+            self.writer.write_all(&ANNEX_B_START_CODE)?;
+            self.writer.write_all(nal_unit)?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Get the number of written frames (AVC NALU packets).
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush the underlying writer and unwrap it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<'a> From<&AvcDecoderConfigurationRecord<'a>> for AvcConfig {
+    fn from(record: &AvcDecoderConfigurationRecord<'a>) -> Self {
+        let parameter_sets = record
+            .sequence_parameter_sets
+            .iter()
+            .chain(&record.picture_parameter_sets)
+            .map(|parameter_set| parameter_set.to_vec())
+            .collect();
+        Self {
+            nal_length_size: usize::from(record.nal_length_size()),
+            parameter_sets,
+        }
+    }
+}
+
+/// Extract the AVC video of FLV file as H.264 Annex B byte stream,
+/// and return the number of written frames.
+///
+/// The video tags of other codecs are ignored.
+/// Fails with the error of the first tag that cannot be parsed.
+pub fn extract_avc<W: Write>(input: &[u8], writer: &mut W) -> io::Result<u64> {
+    let mut annex_b = AnnexBWriter::new(writer);
+    let mut tags = flv_tags(input)?;
+    for (tag, _) in tags.by_ref() {
+        match tag.data {
+            FlvTagData::Video(video) if video.header.codec_id == CodecID::AVC => {
+                annex_b.write_video_tag(&video)?
+            }
+            _ => {}
+        }
+    }
+    tags_error(&tags)?;
+    let frames = annex_b.frames();
+    annex_b.into_inner()?;
+    Ok(frames)
+}
@@ -0,0 +1,35 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Extract the elementary streams of FLV file into standalone files.
+
+mod aac;
+mod avc;
+mod mp3;
+
+pub use self::{aac::*, avc::*, mp3::*};
+
+use std::io;
+
+use nom::number::streaming::be_u32;
+
+use crate::parse::{FlvFileHeader, FlvTagIter};
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Iterate the tags of the whole FLV file.
+fn flv_tags(input: &[u8]) -> io::Result<FlvTagIter<'_>> {
+    let (body, _) = FlvFileHeader::parse_with_extension(input)
+        .map_err(|_| invalid_data("invalid FLV file header"))?;
+    let (tags, _) = be_u32::<_, ()>(body).map_err(|_| invalid_data("invalid FLV file body"))?;
+    Ok(FlvTagIter::with_offset(tags, input.len() - tags.len()))
+}
+
+// The error of the tag that stopped the iteration, instead of ending with a truncated stream.
+fn tags_error(iter: &FlvTagIter) -> io::Result<()> {
+    match iter.error() {
+        Some(err) => Err((*err).into()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Extract the MP3 audio of FLV file as MPEG audio stream (.mp3).
+
+use std::io::{self, Write};
+
+use super::{flv_tags, invalid_data, tags_error};
+use crate::{
+    codec::MpegAudioFrames,
+    parse::{AudioTag, FlvTagData, SoundFormat},
+};
+
+/// A writer that writes the MPEG audio frames of MP3 audio tags as MPEG audio stream.
+///
+/// The frames split across the audio tags are reassembled, the bytes which don't
+/// belong to any frame (like the garbage before the first frame) are skipped.
+#[derive(Debug)]
+pub struct Mp3Writer<W: Write> {
+    writer: W,
+    // The beginning of the incomplete frame of the previous tags.
+    pending: Vec<u8>,
+    frames: u64,
+    split_frames: u64,
+    skipped_bytes: u64,
+}
+
+impl<W: Write> Mp3Writer<W> {
+    /// Create a MP3 writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            pending: Vec::new(),
+            frames: 0,
+            split_frames: 0,
+            skipped_bytes: 0,
+        }
+    }
+
+    /// Write the MPEG audio frames of audio tag, the audio tag must be MP3.
+    pub fn write_audio_tag(&mut self, audio: &AudioTag) -> io::Result<()> {
+        match audio.header.sound_format {
+            SoundFormat::MP3 | SoundFormat::MP3_8kHz => self.write_data(audio.body.data),
+            _ => Err(invalid_data("audio tag is not MP3")),
+        }
+    }
+
+    /// Write the MPEG audio data (usually an audio tag body),
+    /// the incomplete frame at the end is continued by the next data.
+    pub fn write_data(&mut self, data: &[u8]) -> io::Result<()> {
+        let carried = self.pending.len();
+        self.pending.extend_from_slice(data);
+        let split = MpegAudioFrames::split(&self.pending);
+        let mut written = 0;
+        for frame in &split.frames {
+            let offset = frame.data.as_ptr() as usize - self.pending.as_ptr() as usize;
+            if offset < carried {
+                self.split_frames += 1;
+            }
+            self.writer.write_all(frame.data)?;
+            written += frame.data.len();
+            self.frames += 1;
+        }
+        let trailing = split.trailing.len();
+        let consumed = self.pending.len() - trailing;
+        self.skipped_bytes += (consumed - written) as u64;
+        self.pending.drain(..consumed);
+        Ok(())
+    }
+
+    /// Get the number of written MPEG audio frames.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Get the number of written frames which were split across the data.
+    pub fn split_frames(&self) -> u64 {
+        self.split_frames
+    }
+
+    /// Get the number of skipped bytes which don't belong to any frame.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.skipped_bytes
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush the underlying writer and unwrap it,
+    /// the incomplete frame at the end is dropped.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Extract the MP3 audio of FLV file as MPEG audio stream,
+/// and return the number of written MPEG audio frames.
+///
+/// The audio tags of other sound formats are ignored.
+/// Fails with the error of the first tag that cannot be parsed.
+pub fn extract_mp3<W: Write>(input: &[u8], writer: &mut W) -> io::Result<u64> {
+    let mut mp3 = Mp3Writer::new(writer);
+    let mut tags = flv_tags(input)?;
+    for (tag, _) in tags.by_ref() {
+        match tag.data {
+            FlvTagData::Audio(audio)
+                if matches!(
+                    audio.header.sound_format,
+                    SoundFormat::MP3 | SoundFormat::MP3_8kHz
+                ) =>
+            {
+                mp3.write_audio_tag(&audio)?
+            }
+            _ => {}
+        }
+    }
+    tags_error(&tags)?;
+    let frames = mp3.frames();
+    mp3.into_inner()?;
+    Ok(frames)
+}
@@ -0,0 +1,281 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Inject (rewrite) the `onMetaData` script tag of FLV file, like yamdi.
+
+use std::io::{self, Write};
+
+use nom::number::streaming::be_u32;
+
+use crate::{
+    codec::VideoCodecConfiguration,
+    metadata::{is_video_frame, Keyframe, KeyframeIndex, OnMetaData, ON_META_DATA},
+    parse::{
+        FlvFileHeader, FlvTagData, FlvTagHeader, FlvTagIter, FlvTagType, FourCC, FrameType,
+        SoundRate, SoundSize, SoundType, VideoTag,
+    },
+};
+
+const FLV_FILE_HEADER_SIZE: u64 = 9;
+const FLV_TAG_HEADER_SIZE: u64 = 11;
+const PREVIOUS_TAG_SIZE_SIZE: u64 = 4;
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// The statistics of the audio and video tags.
+#[derive(Default)]
+struct TagStats {
+    has_video: bool,
+    has_audio: bool,
+    video_size: u64,
+    audio_size: u64,
+    video_frames: u64,
+    first_video_timestamp: Option<u32>,
+    last_video_timestamp: u32,
+    last_timestamp: u32,
+    last_keyframe_timestamp: Option<u32>,
+    last_video_frame_is_key: bool,
+    video_codec_id: Option<f64>,
+    audio_codec_id: Option<f64>,
+    audio_sample_rate: Option<f64>,
+    audio_sample_size: Option<f64>,
+    stereo: Option<bool>,
+    // The dimensions decoded from the SPS of the first AVC or HEVC sequence header.
+    dimensions: Option<(u32, u32)>,
+}
+
+/// Write a new FLV file with a correct `onMetaData` script tag first,
+/// and return the injected metadata.
+///
+/// The duration, file size, data rates, frame rate, codec ids, `lasttimestamp`,
+/// `lastkeyframetimestamp` and keyframe index are computed from the tags, the dimensions
+/// are decoded from the SPS of AVC or HEVC sequence header, the other properties are kept
+/// from the existing `onMetaData`.
+/// Any existing `onMetaData` script tags and the extension data of file header are dropped,
+/// all other tags are copied as is.
+///
+/// Fails with the error of the first tag that cannot be parsed, nothing is written then.
+pub fn inject_metadata<'a, W: Write>(
+    input: &'a [u8],
+    writer: &mut W,
+) -> io::Result<OnMetaData<'a>> {
+    let (body, (header, _extension)) = FlvFileHeader::parse_with_extension(input)
+        .map_err(|_| invalid_data("invalid FLV file header"))?;
+    let (tags, _) = be_u32::<_, ()>(body).map_err(|_| invalid_data("invalid FLV file body"))?;
+
+    // The kept tags (raw bytes, without the previous tag size), in order.
+    let mut kept = Vec::new();
+    // The key frames, whose file positions are relative to the first kept tag.
+    let mut keyframes = Vec::new();
+    let mut kept_size = 0u64;
+    let mut existing = None;
+    let mut stats = TagStats::default();
+
+    let mut iter = FlvTagIter::with_offset(tags, input.len() - tags.len());
+    loop {
+        let start = input.len() - iter.remain().len();
+        let tag = match iter.next() {
+            Some((tag, _)) => tag,
+            None => break,
+        };
+        let raw =
+            &input[start..start + FLV_TAG_HEADER_SIZE as usize + tag.header.data_size as usize];
+
+        match &tag.data {
+            FlvTagData::Script(script) if script.name == ON_META_DATA => {
+                if existing.is_none() {
+                    existing = OnMetaData::from_script_tag(script);
+                }
+                continue;
+            }
+            FlvTagData::Script(_) => {}
+            FlvTagData::Video(video) => {
+                let timestamp = tag.header.timestamp;
+                stats.has_video = true;
+                stats.video_size += u64::from(tag.header.data_size);
+                stats.video_codec_id = stats.video_codec_id.or_else(|| video_codec_id(video));
+                if stats.dimensions.is_none() {
+                    stats.dimensions = video_dimensions(video);
+                }
+                if is_video_frame(video) {
+                    stats.video_frames += 1;
+                    stats.first_video_timestamp.get_or_insert(timestamp);
+                    stats.last_video_timestamp = timestamp;
+                    stats.last_video_frame_is_key = video.header.frame_type == FrameType::Key;
+                    if video.header.frame_type == FrameType::Key {
+                        stats.last_keyframe_timestamp = Some(timestamp);
+                        keyframes.push(Keyframe {
+                            file_position: kept_size,
+                            timestamp,
+                        });
+                    }
+                }
+            }
+            FlvTagData::Audio(audio) => {
+                stats.has_audio = true;
+                stats.audio_size += u64::from(tag.header.data_size);
+                if let Some(ex_header) = &audio.header.ex_header {
+                    // The rate, size and type of sound are not present in the extended header.
+                    stats
+                        .audio_codec_id
+                        .get_or_insert(fourcc_codec_id(ex_header.fourcc));
+                } else if stats.audio_codec_id.is_none() {
+                    stats.audio_codec_id = Some(f64::from(u8::from(audio.header.sound_format)));
+                    stats.audio_sample_rate = Some(match audio.header.sound_rate {
+                        SoundRate::_5_5KHZ => 5512.0,
+                        SoundRate::_11KHZ => 11025.0,
+                        SoundRate::_22KHZ => 22050.0,
+                        SoundRate::_44KHZ => 44100.0,
+                    });
+                    stats.audio_sample_size = Some(match audio.header.sound_size {
+                        SoundSize::_8Bit => 8.0,
+                        SoundSize::_16Bit => 16.0,
+                    });
+                    stats.stereo = Some(audio.header.sound_type == SoundType::Stereo);
+                }
+            }
+            // The filtered (e.g. encrypted) tags are kept, only their sizes are counted.
+            FlvTagData::Filtered(filtered) => match filtered.tag_type {
+                FlvTagType::Audio => {
+                    stats.has_audio = true;
+                    stats.audio_size += u64::from(tag.header.data_size);
+                }
+                FlvTagType::Video => {
+                    stats.has_video = true;
+                    stats.video_size += u64::from(tag.header.data_size);
+                }
+                FlvTagType::Script => {}
+            },
+        }
+        stats.last_timestamp = stats.last_timestamp.max(tag.header.timestamp);
+        kept_size += raw.len() as u64 + PREVIOUS_TAG_SIZE_SIZE;
+        kept.push(raw);
+    }
+    // Don't write a truncated file silently.
+    if let Some(err) = iter.error() {
+        return Err((*err).into());
+    }
+
+    let mut meta = existing.unwrap_or_default();
+    let duration = f64::from(stats.last_timestamp) / 1000.0;
+    meta.duration = Some(duration);
+    meta.has_video = Some(stats.has_video);
+    meta.has_audio = Some(stats.has_audio);
+    meta.has_metadata = Some(true);
+    meta.has_keyframes = Some(!keyframes.is_empty());
+    meta.can_seek_to_end = Some(stats.last_video_frame_is_key);
+    meta.video_size = Some(stats.video_size as f64);
+    meta.audio_size = Some(stats.audio_size as f64);
+    meta.data_size = Some((stats.video_size + stats.audio_size) as f64);
+    meta.last_timestamp = Some(duration);
+    meta.last_keyframe_timestamp = stats
+        .last_keyframe_timestamp
+        .map(|timestamp| f64::from(timestamp) / 1000.0);
+    if duration > 0.0 {
+        // kilobits per second
+        meta.video_data_rate = Some(stats.video_size as f64 * 8.0 / 1000.0 / duration);
+        meta.audio_data_rate = Some(stats.audio_size as f64 * 8.0 / 1000.0 / duration);
+    }
+    if let Some(first) = stats.first_video_timestamp {
+        let span = stats.last_video_timestamp.saturating_sub(first);
+        if stats.video_frames > 1 && span > 0 {
+            meta.frame_rate = Some((stats.video_frames - 1) as f64 * 1000.0 / f64::from(span));
+        }
+    }
+    if stats.has_video {
+        meta.video_codec_id = stats.video_codec_id;
+    }
+    if let Some((width, height)) = stats.dimensions {
+        meta.width = Some(f64::from(width));
+        meta.height = Some(f64::from(height));
+    }
+    if stats.has_audio {
+        meta.audio_codec_id = stats.audio_codec_id;
+        meta.audio_sample_rate = stats.audio_sample_rate;
+        meta.audio_sample_size = stats.audio_sample_size;
+        meta.stereo = stats.stereo;
+    }
+
+    // The size of `onMetaData` tag doesn't depend on the values of the file positions
+    // and the file size (all numbers are encoded as 8 bytes), so encode it with the
+    // relative positions first to know where the kept tags start.
+    meta.keyframes = Some(KeyframeIndex {
+        keyframes: keyframes.clone(),
+    });
+    meta.file_size = Some(0.0);
+    let meta_size = encode_script_tag_data(&meta)?.len() as u64;
+    let tags_start = FLV_FILE_HEADER_SIZE
+        + PREVIOUS_TAG_SIZE_SIZE
+        + FLV_TAG_HEADER_SIZE
+        + meta_size
+        + PREVIOUS_TAG_SIZE_SIZE;
+    for keyframe in &mut keyframes {
+        keyframe.file_position += tags_start;
+    }
+    meta.keyframes = Some(KeyframeIndex { keyframes });
+    meta.file_size = Some((tags_start + kept_size) as f64);
+    // The existing properties replaced by the computed ones aren't written.
+    meta.remove_shadowed_extra();
+    let meta_data = encode_script_tag_data(&meta)?;
+    debug_assert_eq!(meta_data.len() as u64, meta_size);
+
+    let header = FlvFileHeader {
+        has_audio: stats.has_audio,
+        has_video: stats.has_video,
+        data_offset: FLV_FILE_HEADER_SIZE as u32,
+        ..header
+    };
+    header.write(writer)?;
+    writer.write_all(&0u32.to_be_bytes())?;
+    let meta_header = FlvTagHeader {
+        reserved: 0,
+        filter: false,
+        tag_type: FlvTagType::Script,
+        data_size: meta_data.len() as u32,
+        timestamp: 0,
+        stream_id: 0,
+    };
+    meta_header.write(writer)?;
+    writer.write_all(&meta_data)?;
+    writer.write_all(&((FLV_TAG_HEADER_SIZE + meta_size) as u32).to_be_bytes())?;
+    for raw in kept {
+        writer.write_all(raw)?;
+        writer.write_all(&(raw.len() as u32).to_be_bytes())?;
+    }
+    Ok(meta)
+}
+
+// The codec id of Enhanced RTMP, the FourCC as a big-endian number.
+fn fourcc_codec_id(fourcc: FourCC) -> f64 {
+    f64::from(u32::from_be_bytes(fourcc.0))
+}
+
+// The codec id of video, or the FourCC of Enhanced RTMP.
+fn video_codec_id(video: &VideoTag) -> Option<f64> {
+    match &video.header.ex_header {
+        Some(ex_header) => Some(fourcc_codec_id(ex_header.fourcc)),
+        None => video.header.codec_id.value().map(f64::from),
+    }
+}
+
+// The dimensions of AVC or HEVC sequence header.
+fn video_dimensions(video: &VideoTag) -> Option<(u32, u32)> {
+    match video.codec_configuration()? {
+        VideoCodecConfiguration::Avc(record) => {
+            let sps = record.sequence_parameter_set()?;
+            Some((sps.width()?, sps.height()?))
+        }
+        VideoCodecConfiguration::Hevc(record) => {
+            let sps = record.sequence_parameter_set()?;
+            Some((sps.width()?, sps.height()?))
+        }
+        _ => None,
+    }
+}
+
+fn encode_script_tag_data(meta: &OnMetaData) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    meta.to_script_tag().write(&mut data)?;
+    Ok(data)
+}
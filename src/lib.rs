@@ -13,9 +13,27 @@ extern crate alloc;
 #[macro_use]
 extern crate nom;
 
+mod codec;
+#[cfg(feature = "decrypt")]
+mod decrypt;
+mod demux;
+mod error;
+#[cfg(feature = "std")]
+mod extract;
+#[cfg(feature = "std")]
+mod inject;
+mod metadata;
 mod parse;
+mod recover;
+mod validate;
+#[cfg(feature = "std")]
+mod write;
 
-pub use self::parse::*;
+#[cfg(feature = "decrypt")]
+pub use self::decrypt::*;
+pub use self::{codec::*, demux::*, error::*, metadata::*, parse::*, recover::*, validate::*};
+#[cfg(feature = "std")]
+pub use self::{extract::*, inject::*, write::*};
 
 pub use nom::{
     error::{Error, ErrorKind},
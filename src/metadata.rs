@@ -0,0 +1,390 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// The typed view of `onMetaData` script tag, and the keyframe index used for seeking.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::{vec, vec::Vec};
+
+use nom::{number::streaming::be_u32, IResult};
+
+use crate::parse::{
+    avc_video_packet, AvcPacketType, CodecID, FlvFileHeader, FlvTagData, FlvTagIter, FrameType,
+    ScriptDataObjectProperty, ScriptDataValue, ScriptTag, VideoPacketType, VideoTag,
+};
+
+/// The name of the script tag that carries the metadata of FLV file.
+pub const ON_META_DATA: &str = "onMetaData";
+
+/// The typed view of `onMetaData` script tag, and the keyframe index used for seeking.
+///
+/// The well-known properties are typed, the other properties (and the well-known properties
+/// whose values have unexpected types) are kept in `extra`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OnMetaData<'a> {
+    /// `duration`, total duration of the file in seconds.
+    pub duration: Option<f64>,
+    /// `filesize`, total size of the file in bytes.
+    pub file_size: Option<f64>,
+    /// `width`, width of the video in pixels.
+    pub width: Option<f64>,
+    /// `height`, height of the video in pixels.
+    pub height: Option<f64>,
+    /// `videodatarate`, video bit rate in kilobits per second.
+    pub video_data_rate: Option<f64>,
+    /// `framerate`, number of frames per second.
+    pub frame_rate: Option<f64>,
+    /// `videocodecid`, video codec ID used in the file.
+    pub video_codec_id: Option<f64>,
+    /// `audiodatarate`, audio bit rate in kilobits per second.
+    pub audio_data_rate: Option<f64>,
+    /// `audiosamplerate`, frequency at which the audio stream is replayed.
+    pub audio_sample_rate: Option<f64>,
+    /// `audiosamplesize`, resolution of a single audio sample.
+    pub audio_sample_size: Option<f64>,
+    /// `stereo`, indicating stereo audio.
+    pub stereo: Option<bool>,
+    /// `audiocodecid`, audio codec ID used in the file.
+    pub audio_codec_id: Option<f64>,
+    /// `encoder`, name of the encoder used to create the file.
+    pub encoder: Option<&'a str>,
+    /// `metadatacreator`, name of the tool that wrote the metadata.
+    pub metadata_creator: Option<&'a str>,
+    /// `hasVideo`, whether the file contains video.
+    pub has_video: Option<bool>,
+    /// `hasAudio`, whether the file contains audio.
+    pub has_audio: Option<bool>,
+    /// `hasMetadata`, whether the file contains metadata.
+    pub has_metadata: Option<bool>,
+    /// `hasKeyframes`, whether the file contains a keyframe index.
+    pub has_keyframes: Option<bool>,
+    /// `canSeekToEnd`, whether the last video frame is a key frame.
+    pub can_seek_to_end: Option<bool>,
+    /// `datasize`, total size of the audio and video data in bytes.
+    pub data_size: Option<f64>,
+    /// `videosize`, total size of the video data in bytes.
+    pub video_size: Option<f64>,
+    /// `audiosize`, total size of the audio data in bytes.
+    pub audio_size: Option<f64>,
+    /// `lasttimestamp`, timestamp of the last tag in seconds.
+    pub last_timestamp: Option<f64>,
+    /// `lastkeyframetimestamp`, timestamp of the last video key frame in seconds.
+    pub last_keyframe_timestamp: Option<f64>,
+    /// `keyframes`, the keyframe index.
+    pub keyframes: Option<KeyframeIndex>,
+    /// The other properties.
+    pub extra: Vec<ScriptDataObjectProperty<'a>>,
+}
+
+impl<'a> OnMetaData<'a> {
+    /// Build the typed metadata from the script tag named `onMetaData`,
+    /// whose value is an `ECMAArray` (or an `Object`).
+    pub fn from_script_tag(tag: &ScriptTag<'a>) -> Option<Self> {
+        if tag.name != ON_META_DATA {
+            return None;
+        }
+        match &tag.value {
+            ScriptDataValue::ECMAArray(properties) | ScriptDataValue::Object(properties) => {
+                Some(Self::from_properties(properties))
+            }
+            _ => None,
+        }
+    }
+
+    /// Build the typed metadata from the properties of `onMetaData`.
+    pub fn from_properties(properties: &[ScriptDataObjectProperty<'a>]) -> Self {
+        let mut meta = Self::default();
+        for property in properties {
+            match (property.name, &property.value) {
+                ("duration", ScriptDataValue::Number(v)) => meta.duration = Some(*v),
+                ("filesize", ScriptDataValue::Number(v)) => meta.file_size = Some(*v),
+                ("width", ScriptDataValue::Number(v)) => meta.width = Some(*v),
+                ("height", ScriptDataValue::Number(v)) => meta.height = Some(*v),
+                ("videodatarate", ScriptDataValue::Number(v)) => meta.video_data_rate = Some(*v),
+                ("framerate", ScriptDataValue::Number(v)) => meta.frame_rate = Some(*v),
+                ("videocodecid", ScriptDataValue::Number(v)) => meta.video_codec_id = Some(*v),
+                ("audiodatarate", ScriptDataValue::Number(v)) => meta.audio_data_rate = Some(*v),
+                ("audiosamplerate", ScriptDataValue::Number(v)) => {
+                    meta.audio_sample_rate = Some(*v)
+                }
+                ("audiosamplesize", ScriptDataValue::Number(v)) => {
+                    meta.audio_sample_size = Some(*v)
+                }
+                ("stereo", ScriptDataValue::Boolean(v)) => meta.stereo = Some(*v),
+                ("audiocodecid", ScriptDataValue::Number(v)) => meta.audio_codec_id = Some(*v),
+                ("encoder", ScriptDataValue::String(v)) => meta.encoder = Some(v),
+                ("metadatacreator", ScriptDataValue::String(v)) => meta.metadata_creator = Some(v),
+                ("hasVideo", ScriptDataValue::Boolean(v)) => meta.has_video = Some(*v),
+                ("hasAudio", ScriptDataValue::Boolean(v)) => meta.has_audio = Some(*v),
+                ("hasMetadata", ScriptDataValue::Boolean(v)) => meta.has_metadata = Some(*v),
+                ("hasKeyframes", ScriptDataValue::Boolean(v)) => meta.has_keyframes = Some(*v),
+                ("canSeekToEnd", ScriptDataValue::Boolean(v)) => meta.can_seek_to_end = Some(*v),
+                ("datasize", ScriptDataValue::Number(v)) => meta.data_size = Some(*v),
+                ("videosize", ScriptDataValue::Number(v)) => meta.video_size = Some(*v),
+                ("audiosize", ScriptDataValue::Number(v)) => meta.audio_size = Some(*v),
+                ("lasttimestamp", ScriptDataValue::Number(v)) => meta.last_timestamp = Some(*v),
+                ("lastkeyframetimestamp", ScriptDataValue::Number(v)) => {
+                    meta.last_keyframe_timestamp = Some(*v)
+                }
+                ("keyframes", value) => match KeyframeIndex::from_script_data_value(value) {
+                    Some(keyframes) => meta.keyframes = Some(keyframes),
+                    None => meta.extra.push(property.clone()),
+                },
+                _ => meta.extra.push(property.clone()),
+            }
+        }
+        meta
+    }
+
+    /// Convert the typed metadata into the properties of `onMetaData`,
+    /// the well-known properties come first, followed by the other properties.
+    ///
+    /// The other properties named the same as a well-known property that is set
+    /// (e.g. a `keyframes` that cannot be parsed) are left out.
+    pub fn to_properties(&self) -> Vec<ScriptDataObjectProperty<'a>> {
+        let mut properties = self.known_properties();
+        let extra = self
+            .extra
+            .iter()
+            .filter(|extra| !properties.iter().any(|known| known.name == extra.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        properties.extend(extra);
+        properties
+    }
+
+    /// Remove the other properties named the same as a well-known property that is set,
+    /// which are left out by `to_properties`.
+    pub fn remove_shadowed_extra(&mut self) {
+        let known = self.known_properties();
+        self.extra
+            .retain(|extra| !known.iter().any(|known| known.name == extra.name));
+    }
+
+    // The well-known properties that are set.
+    fn known_properties(&self) -> Vec<ScriptDataObjectProperty<'a>> {
+        let known = [
+            ("duration", self.duration.map(ScriptDataValue::Number)),
+            ("filesize", self.file_size.map(ScriptDataValue::Number)),
+            ("width", self.width.map(ScriptDataValue::Number)),
+            ("height", self.height.map(ScriptDataValue::Number)),
+            (
+                "videodatarate",
+                self.video_data_rate.map(ScriptDataValue::Number),
+            ),
+            ("framerate", self.frame_rate.map(ScriptDataValue::Number)),
+            (
+                "videocodecid",
+                self.video_codec_id.map(ScriptDataValue::Number),
+            ),
+            (
+                "audiodatarate",
+                self.audio_data_rate.map(ScriptDataValue::Number),
+            ),
+            (
+                "audiosamplerate",
+                self.audio_sample_rate.map(ScriptDataValue::Number),
+            ),
+            (
+                "audiosamplesize",
+                self.audio_sample_size.map(ScriptDataValue::Number),
+            ),
+            ("stereo", self.stereo.map(ScriptDataValue::Boolean)),
+            (
+                "audiocodecid",
+                self.audio_codec_id.map(ScriptDataValue::Number),
+            ),
+            ("encoder", self.encoder.map(ScriptDataValue::String)),
+            (
+                "metadatacreator",
+                self.metadata_creator.map(ScriptDataValue::String),
+            ),
+            ("hasVideo", self.has_video.map(ScriptDataValue::Boolean)),
+            ("hasAudio", self.has_audio.map(ScriptDataValue::Boolean)),
+            (
+                "hasMetadata",
+                self.has_metadata.map(ScriptDataValue::Boolean),
+            ),
+            (
+                "hasKeyframes",
+                self.has_keyframes.map(ScriptDataValue::Boolean),
+            ),
+            (
+                "canSeekToEnd",
+                self.can_seek_to_end.map(ScriptDataValue::Boolean),
+            ),
+            ("datasize", self.data_size.map(ScriptDataValue::Number)),
+            ("videosize", self.video_size.map(ScriptDataValue::Number)),
+            ("audiosize", self.audio_size.map(ScriptDataValue::Number)),
+            (
+                "lasttimestamp",
+                self.last_timestamp.map(ScriptDataValue::Number),
+            ),
+            (
+                "lastkeyframetimestamp",
+                self.last_keyframe_timestamp.map(ScriptDataValue::Number),
+            ),
+            (
+                "keyframes",
+                self.keyframes
+                    .as_ref()
+                    .map(KeyframeIndex::to_script_data_value),
+            ),
+        ];
+
+        known
+            .iter()
+            .cloned()
+            .filter_map(|(name, value)| value.map(|value| ScriptDataObjectProperty { name, value }))
+            .collect()
+    }
+
+    /// Convert the typed metadata into the script tag named `onMetaData`.
+    pub fn to_script_tag(&self) -> ScriptTag<'a> {
+        ScriptTag {
+            name: ON_META_DATA,
+            value: self.into(),
+            raw: None,
+        }
+    }
+}
+
+impl<'a> From<&OnMetaData<'a>> for ScriptDataValue<'a> {
+    fn from(meta: &OnMetaData<'a>) -> Self {
+        ScriptDataValue::ECMAArray(meta.to_properties())
+    }
+}
+
+impl<'a> From<OnMetaData<'a>> for ScriptDataValue<'a> {
+    fn from(meta: OnMetaData<'a>) -> Self {
+        (&meta).into()
+    }
+}
+
+/// Whether the video tag carries a video frame, rather than a sequence header,
+/// end of sequence or metadata.
+pub(crate) fn is_video_frame(video: &VideoTag) -> bool {
+    if let Some(ex_header) = &video.header.ex_header {
+        return matches!(
+            ex_header.packet_type,
+            VideoPacketType::CodedFrames | VideoPacketType::CodedFramesX
+        );
+    }
+    !matches!(video.header.codec_id, CodecID::AVC | CodecID::HEVC)
+        || matches!(
+            avc_video_packet(video.body.data, video.body.data.len()),
+            Ok((_, packet)) if packet.packet_type == AvcPacketType::NALU
+        )
+}
+
+/// The keyframe index, used by players to seek in FLV file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeyframeIndex {
+    /// The keyframes, in the order of the file.
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// The entry of keyframe index.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Keyframe {
+    /// The byte offset of the key frame tag (the start of its tag header) in FLV file.
+    pub file_position: u64,
+    /// The timestamp (in milliseconds) of the key frame tag.
+    pub timestamp: u32,
+}
+
+impl KeyframeIndex {
+    /// Build the keyframe index by scanning the video tags of FLV file,
+    /// whose frame type is key frame (AVC sequence headers and end of sequence are skipped).
+    pub fn scan(input: &[u8]) -> IResult<&[u8], KeyframeIndex> {
+        let (body, _header) = FlvFileHeader::parse_with_extension(input)?;
+        let (tags, _first_previous_tag_size) = be_u32(body)?;
+
+        let mut index = KeyframeIndex::default();
+        let mut iter = FlvTagIter::with_offset(tags, input.len() - tags.len());
+        loop {
+            let file_position = (input.len() - iter.remain().len()) as u64;
+            let tag = match iter.next() {
+                Some((tag, _)) => tag,
+                None => break,
+            };
+            if let FlvTagData::Video(video) = &tag.data {
+                if video.header.frame_type == FrameType::Key && is_video_frame(video) {
+                    index.keyframes.push(Keyframe {
+                        file_position,
+                        timestamp: tag.header.timestamp,
+                    });
+                }
+            }
+        }
+        Ok((iter.remain(), index))
+    }
+
+    /// Parse the keyframe index from the `keyframes` object of `onMetaData`,
+    /// which holds parallel `filepositions` and `times` (in seconds) arrays.
+    pub fn from_script_data_value(value: &ScriptDataValue) -> Option<Self> {
+        let properties = match value {
+            ScriptDataValue::Object(properties) | ScriptDataValue::ECMAArray(properties) => {
+                properties
+            }
+            _ => return None,
+        };
+        let numbers = |name: &str| {
+            properties
+                .iter()
+                .find(|property| property.name == name)
+                .and_then(|property| match &property.value {
+                    ScriptDataValue::StrictArray(values) => Some(values),
+                    _ => None,
+                })
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|value| match value {
+                            ScriptDataValue::Number(number) if *number >= 0.0 => Some(*number),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                })
+        };
+
+        let file_positions = numbers("filepositions")?;
+        let times = numbers("times")?;
+        if file_positions.len() != times.len() {
+            return None;
+        }
+        let keyframes = file_positions
+            .into_iter()
+            .zip(times)
+            .map(|(file_position, time)| Keyframe {
+                file_position: file_position as u64,
+                // seconds to milliseconds, rounded
+                timestamp: (time * 1000.0 + 0.5) as u32,
+            })
+            .collect();
+        Some(Self { keyframes })
+    }
+
+    /// Convert the keyframe index into the `keyframes` object of `onMetaData`.
+    pub fn to_script_data_value<'a>(&self) -> ScriptDataValue<'a> {
+        let file_positions = self
+            .keyframes
+            .iter()
+            .map(|keyframe| ScriptDataValue::Number(keyframe.file_position as f64))
+            .collect();
+        let times = self
+            .keyframes
+            .iter()
+            .map(|keyframe| ScriptDataValue::Number(f64::from(keyframe.timestamp) / 1000.0))
+            .collect();
+        ScriptDataValue::Object(vec![
+            ScriptDataObjectProperty {
+                name: "filepositions",
+                value: ScriptDataValue::StrictArray(file_positions),
+            },
+            ScriptDataObjectProperty {
+                name: "times",
+                value: ScriptDataValue::StrictArray(times),
+            },
+        ])
+    }
+}
@@ -0,0 +1,449 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the AMF3 values that follow the AVM+ type marker (0x11) of AMF0 script data.
+// [Action Message Format -- AMF 3](https://www.adobe.com/content/dam/acom/en/devnet/pdf/amf-file-format-spec.pdf)
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+use core::str;
+
+use nom::{
+    bytes::streaming::take,
+    error::{Error, ErrorKind},
+    number::streaming::{be_f64, be_i32, be_u32, be_u8},
+    Err as NomErr, IResult,
+};
+
+/// The AMF3 value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Amf3Value<'a> {
+    /// 0x00, Undefined value.
+    Undefined,
+    /// 0x01, Null value.
+    Null,
+    /// 0x02 (false) or 0x03 (true), Boolean value.
+    Boolean(bool),
+    /// 0x04, Integer value (29-bit signed integer).
+    Integer(i32),
+    /// 0x05, Double value.
+    Double(f64),
+    /// 0x06, String value.
+    String(&'a str),
+    /// 0x07, XML document value.
+    XmlDocument(&'a str),
+    /// 0x08, Date value, the number of milliseconds since UNIX_EPOCH (in UTC).
+    Date(f64),
+    /// 0x09, Array value.
+    Array(Amf3Array<'a>),
+    /// 0x0A, Object value.
+    Object(Amf3Object<'a>),
+    /// 0x0B, XML value.
+    Xml(&'a str),
+    /// 0x0C, ByteArray value.
+    ByteArray(&'a [u8]),
+    /// 0x0D, Vector of int value.
+    VectorInt(Amf3Vector<i32>),
+    /// 0x0E, Vector of uint value.
+    VectorUInt(Amf3Vector<u32>),
+    /// 0x0F, Vector of double value.
+    VectorDouble(Amf3Vector<f64>),
+    /// 0x10, Vector of object value.
+    VectorObject(Amf3ObjectVector<'a>),
+    /// 0x11, Dictionary value.
+    Dictionary(Amf3Dictionary<'a>),
+    /// The reference to a complex value (0x07 - 0x11), by its index in the object
+    /// reference table, i.e. the order in which the complex values start.
+    Reference(u32),
+}
+
+/// The `Array` AMF3 value, which has an associative part and a dense part.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Amf3Array<'a> {
+    /// The associative part (name-value pairs).
+    pub assoc: Vec<Amf3Property<'a>>,
+    /// The dense part (ordinal indices).
+    pub dense: Vec<Amf3Value<'a>>,
+}
+
+/// The `Object` AMF3 value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Amf3Object<'a> {
+    /// The traits of object.
+    pub traits: Amf3Traits<'a>,
+    /// The sealed members of object, named by the sealed member names of traits.
+    pub sealed: Vec<Amf3Property<'a>>,
+    /// The dynamic members of object, only exists when the traits is dynamic.
+    pub dynamic: Vec<Amf3Property<'a>>,
+}
+
+/// The traits of `Object` AMF3 value.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Amf3Traits<'a> {
+    /// The class name, empty for anonymous objects.
+    pub class_name: &'a str,
+    /// Whether the object has dynamic members.
+    pub dynamic: bool,
+    /// The sealed member names.
+    pub sealed_names: Vec<&'a str>,
+}
+
+/// The name-value pair of `Array` and `Object` AMF3 values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Amf3Property<'a> {
+    /// Property name.
+    pub name: &'a str,
+    /// Property value.
+    pub value: Amf3Value<'a>,
+}
+
+/// The `Vector` AMF3 value of int, uint or double.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Amf3Vector<T> {
+    /// Whether the vector has a fixed length.
+    pub fixed: bool,
+    /// The items of vector.
+    pub items: Vec<T>,
+}
+
+/// The `Vector` AMF3 value of object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Amf3ObjectVector<'a> {
+    /// Whether the vector has a fixed length.
+    pub fixed: bool,
+    /// The type name of items, `*` for any type.
+    pub type_name: &'a str,
+    /// The items of vector.
+    pub items: Vec<Amf3Value<'a>>,
+}
+
+/// The `Dictionary` AMF3 value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Amf3Dictionary<'a> {
+    /// Whether the keys are weakly referenced.
+    pub weak_keys: bool,
+    /// The key-value pairs of dictionary.
+    pub entries: Vec<(Amf3Value<'a>, Amf3Value<'a>)>,
+}
+
+impl<'a> Amf3Value<'a> {
+    /// Parse AMF3 value, with new (empty) reference tables.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], Amf3Value<'a>> {
+        Amf3Decoder::new().decode(input)
+    }
+}
+
+/// The maximum nesting depth of AMF3 values.
+pub const AMF3_MAX_DEPTH: usize = 64;
+
+/// Parse U29 (variable length unsigned 29-bit integer).
+pub fn amf3_u29(input: &[u8]) -> IResult<&[u8], u32> {
+    let mut value = 0u32;
+    let mut remain = input;
+    // The first 3 bytes use 7 bits with a continuation bit, the 4th byte uses all 8 bits.
+    for _ in 0..3 {
+        let (rest, byte) = be_u8(remain)?;
+        remain = rest;
+        if byte & 0x80 == 0 {
+            return Ok((remain, (value << 7) | u32::from(byte)));
+        }
+        value = (value << 7) | u32::from(byte & 0x7f);
+    }
+    let (remain, byte) = be_u8(remain)?;
+    Ok((remain, (value << 8) | u32::from(byte)))
+}
+
+fn error<T>(input: &[u8], kind: ErrorKind) -> IResult<&[u8], T> {
+    Err(NomErr::Error(Error::new(input, kind)))
+}
+
+/// The AMF3 decoder, which keeps the string, object and traits reference tables.
+///
+/// The object references are decoded as `Amf3Value::Reference`, not resolved.
+#[derive(Clone, Debug, Default)]
+pub struct Amf3Decoder<'a> {
+    strings: Vec<&'a str>,
+    // The number of complex values in the object reference table.
+    objects: u32,
+    traits: Vec<Amf3Traits<'a>>,
+    depth: usize,
+}
+
+impl<'a> Amf3Decoder<'a> {
+    /// Create an AMF3 decoder with empty reference tables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode an AMF3 value, the reference tables are shared with the previous decoded values.
+    ///
+    /// Fails if the values are nested deeper than `AMF3_MAX_DEPTH`.
+    pub fn decode(&mut self, input: &'a [u8]) -> IResult<&'a [u8], Amf3Value<'a>> {
+        if self.depth >= AMF3_MAX_DEPTH {
+            return error(input, ErrorKind::TooLarge);
+        }
+        self.depth += 1;
+        let result = self.decode_value(input);
+        self.depth -= 1;
+        result
+    }
+
+    fn decode_value(&mut self, input: &'a [u8]) -> IResult<&'a [u8], Amf3Value<'a>> {
+        let (input, marker) = be_u8(input)?;
+        match marker {
+            0x00 => Ok((input, Amf3Value::Undefined)),
+            0x01 => Ok((input, Amf3Value::Null)),
+            0x02 => Ok((input, Amf3Value::Boolean(false))),
+            0x03 => Ok((input, Amf3Value::Boolean(true))),
+            0x04 => {
+                let (input, value) = amf3_u29(input)?;
+                // Sign-extend the 29-bit integer.
+                Ok((input, Amf3Value::Integer(((value << 3) as i32) >> 3)))
+            }
+            0x05 => {
+                let (input, value) = be_f64(input)?;
+                Ok((input, Amf3Value::Double(value)))
+            }
+            0x06 => {
+                let (input, value) = self.decode_string(input)?;
+                Ok((input, Amf3Value::String(value)))
+            }
+            0x07 => self.decode_complex(input, |_, input, header| {
+                let (input, value) = utf8(input, header as usize)?;
+                Ok((input, Amf3Value::XmlDocument(value)))
+            }),
+            0x08 => self.decode_complex(input, |_, input, _| {
+                let (input, value) = be_f64(input)?;
+                Ok((input, Amf3Value::Date(value)))
+            }),
+            0x09 => self.decode_complex(input, Self::decode_array),
+            0x0a => self.decode_object(input),
+            0x0b => self.decode_complex(input, |_, input, header| {
+                let (input, value) = utf8(input, header as usize)?;
+                Ok((input, Amf3Value::Xml(value)))
+            }),
+            0x0c => self.decode_complex(input, |_, input, header| {
+                let (input, value) = take(header as usize)(input)?;
+                Ok((input, Amf3Value::ByteArray(value)))
+            }),
+            0x0d => self.decode_complex(input, |_, input, header| {
+                let (input, vector) = vector(input, header, be_i32)?;
+                Ok((input, Amf3Value::VectorInt(vector)))
+            }),
+            0x0e => self.decode_complex(input, |_, input, header| {
+                let (input, vector) = vector(input, header, be_u32)?;
+                Ok((input, Amf3Value::VectorUInt(vector)))
+            }),
+            0x0f => self.decode_complex(input, |_, input, header| {
+                let (input, vector) = vector(input, header, be_f64)?;
+                Ok((input, Amf3Value::VectorDouble(vector)))
+            }),
+            0x10 => self.decode_complex(input, Self::decode_object_vector),
+            0x11 => self.decode_complex(input, Self::decode_dictionary),
+            _ => error(input, ErrorKind::Switch),
+        }
+    }
+
+    /// Decode UTF-8-vr (the string without type marker), which may be a string reference.
+    fn decode_string(&mut self, input: &'a [u8]) -> IResult<&'a [u8], &'a str> {
+        let (remain, header) = amf3_u29(input)?;
+        if header & 1 == 0 {
+            return match self.strings.get((header >> 1) as usize) {
+                Some(value) => Ok((remain, *value)),
+                None => error(input, ErrorKind::Verify),
+            };
+        }
+        let (remain, value) = utf8(remain, (header >> 1) as usize)?;
+        // The empty string is never sent by reference.
+        if !value.is_empty() {
+            self.strings.push(value);
+        }
+        Ok((remain, value))
+    }
+
+    /// Decode the complex value (added into the object reference table),
+    /// which may be an object reference.
+    fn decode_complex<F>(&mut self, input: &'a [u8], decode: F) -> IResult<&'a [u8], Amf3Value<'a>>
+    where
+        F: FnOnce(&mut Self, &'a [u8], u32) -> IResult<&'a [u8], Amf3Value<'a>>,
+    {
+        let (remain, header) = amf3_u29(input)?;
+        if header & 1 == 0 {
+            return self.object_reference(input, remain, header);
+        }
+        self.objects += 1;
+        decode(self, remain, header >> 1)
+    }
+
+    fn object_reference(
+        &self,
+        input: &'a [u8],
+        remain: &'a [u8],
+        header: u32,
+    ) -> IResult<&'a [u8], Amf3Value<'a>> {
+        match header >> 1 {
+            index if index < self.objects => Ok((remain, Amf3Value::Reference(index))),
+            _ => error(input, ErrorKind::Verify),
+        }
+    }
+
+    fn decode_array(&mut self, input: &'a [u8], length: u32) -> IResult<&'a [u8], Amf3Value<'a>> {
+        let (input, assoc) = self.decode_properties(input)?;
+        let (input, dense) = self.decode_values(input, length)?;
+        Ok((input, Amf3Value::Array(Amf3Array { assoc, dense })))
+    }
+
+    fn decode_object(&mut self, input: &'a [u8]) -> IResult<&'a [u8], Amf3Value<'a>> {
+        let (remain, header) = amf3_u29(input)?;
+        if header & 1 == 0 {
+            return self.object_reference(input, remain, header);
+        }
+        self.objects += 1;
+
+        let (mut remain, traits) = if header & 0b10 == 0 {
+            // traits reference
+            match self.traits.get((header >> 2) as usize) {
+                Some(traits) => (remain, traits.clone()),
+                None => return error(input, ErrorKind::Verify),
+            }
+        } else if header & 0b100 != 0 {
+            // The externalizable object can't be decoded without knowing its class.
+            return error(input, ErrorKind::Verify);
+        } else {
+            let dynamic = header & 0b1000 != 0;
+            let (mut remain, class_name) = self.decode_string(remain)?;
+            let mut sealed_names = Vec::new();
+            for _ in 0..header >> 4 {
+                let (rest, name) = self.decode_string(remain)?;
+                remain = rest;
+                sealed_names.push(name);
+            }
+            let traits = Amf3Traits {
+                class_name,
+                dynamic,
+                sealed_names,
+            };
+            self.traits.push(traits.clone());
+            (remain, traits)
+        };
+
+        let mut sealed = Vec::with_capacity(traits.sealed_names.len());
+        for &name in &traits.sealed_names {
+            let (rest, value) = self.decode(remain)?;
+            remain = rest;
+            sealed.push(Amf3Property { name, value });
+        }
+        let (remain, dynamic) = if traits.dynamic {
+            self.decode_properties(remain)?
+        } else {
+            (remain, Vec::new())
+        };
+
+        Ok((
+            remain,
+            Amf3Value::Object(Amf3Object {
+                traits,
+                sealed,
+                dynamic,
+            }),
+        ))
+    }
+
+    fn decode_object_vector(
+        &mut self,
+        input: &'a [u8],
+        length: u32,
+    ) -> IResult<&'a [u8], Amf3Value<'a>> {
+        let (input, fixed) = be_u8(input)?;
+        let (input, type_name) = self.decode_string(input)?;
+        let (input, items) = self.decode_values(input, length)?;
+        Ok((
+            input,
+            Amf3Value::VectorObject(Amf3ObjectVector {
+                fixed: fixed != 0,
+                type_name,
+                items,
+            }),
+        ))
+    }
+
+    fn decode_dictionary(
+        &mut self,
+        input: &'a [u8],
+        length: u32,
+    ) -> IResult<&'a [u8], Amf3Value<'a>> {
+        let (mut input, weak_keys) = be_u8(input)?;
+        let mut entries = Vec::new();
+        for _ in 0..length {
+            let (remain, key) = self.decode(input)?;
+            let (remain, value) = self.decode(remain)?;
+            input = remain;
+            entries.push((key, value));
+        }
+        Ok((
+            input,
+            Amf3Value::Dictionary(Amf3Dictionary {
+                weak_keys: weak_keys != 0,
+                entries,
+            }),
+        ))
+    }
+
+    /// Decode name-value pairs, terminated by an empty name.
+    fn decode_properties(
+        &mut self,
+        mut input: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<Amf3Property<'a>>> {
+        let mut properties = Vec::new();
+        loop {
+            let (remain, name) = self.decode_string(input)?;
+            if name.is_empty() {
+                return Ok((remain, properties));
+            }
+            let (remain, value) = self.decode(remain)?;
+            input = remain;
+            properties.push(Amf3Property { name, value });
+        }
+    }
+
+    fn decode_values(
+        &mut self,
+        mut input: &'a [u8],
+        count: u32,
+    ) -> IResult<&'a [u8], Vec<Amf3Value<'a>>> {
+        let mut values = Vec::new();
+        for _ in 0..count {
+            let (remain, value) = self.decode(input)?;
+            input = remain;
+            values.push(value);
+        }
+        Ok((input, values))
+    }
+}
+
+fn utf8(input: &[u8], length: usize) -> IResult<&[u8], &str> {
+    let (remain, bytes) = take(length)(input)?;
+    match str::from_utf8(bytes) {
+        Ok(value) => Ok((remain, value)),
+        Err(_) => error(input, ErrorKind::MapRes),
+    }
+}
+
+fn vector<'a, T, F>(input: &'a [u8], length: u32, item: F) -> IResult<&'a [u8], Amf3Vector<T>>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], T>,
+{
+    let (mut input, fixed) = be_u8(input)?;
+    let mut items = Vec::new();
+    for _ in 0..length {
+        let (remain, value) = item(input)?;
+        input = remain;
+        items.push(value);
+    }
+    Ok((
+        input,
+        Amf3Vector {
+            fixed: fixed != 0,
+            items,
+        },
+    ))
+}
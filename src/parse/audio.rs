@@ -1,6 +1,11 @@
 // Copyright 2019-2021 koushiro. Licensed under MIT.
 
-use nom::{number::streaming::be_u8, Err as NomErr, IResult, Needed};
+use nom::{
+    number::streaming::{be_u32, be_u8},
+    Err as NomErr, IResult, Needed,
+};
+
+use super::FourCC;
 
 /// The tag data part of `audio` FLV tag, including `tag data header` and `tag data body`.
 #[derive(Clone, Debug, PartialEq)]
@@ -19,7 +24,7 @@ impl<'a> AudioTag<'a> {
             // parse audio tag header
             header: call!(AudioTagHeader::parse, size) >>
             // parse audio tag body
-            body: call!(AudioTagBody::parse, size - 1) >>
+            body: call!(AudioTagBody::parse, size - header.size()) >>
 
            (AudioTag { header, body })
         )
@@ -27,6 +32,9 @@ impl<'a> AudioTag<'a> {
 }
 
 /// The `tag data header` part of `audio` FLV tag data.
+///
+/// For the extended header of Enhanced RTMP, the rate, size and type of sound
+/// are not present, and are set to 44 KHz, 16 bit and stereo.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct AudioTagHeader {
     /// The format of sound, 4 bits.
@@ -37,6 +45,47 @@ pub struct AudioTagHeader {
     pub sound_size: SoundSize,
     /// The type of sound, 1 bit.
     pub sound_type: SoundType,
+    /// The extended header of Enhanced RTMP, only if the sound format is `ExHeader`.
+    pub ex_header: Option<ExAudioTagHeader>,
+}
+
+/// The extended audio tag header of Enhanced RTMP,
+/// the rate, size and type of sound are replaced by the packet type.
+/// [Enhanced RTMP](https://github.com/veovera/enhanced-rtmp)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExAudioTagHeader {
+    /// The type of audio packet, 4 bits.
+    pub packet_type: AudioPacketType,
+    /// The FourCC of audio codec, 4 bytes.
+    pub fourcc: FourCC,
+}
+
+/// The type of audio packet of Enhanced RTMP.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AudioPacketType {
+    /// 0, SequenceStart, the codec configuration record.
+    SequenceStart,
+    /// 1, CodedFrames.
+    CodedFrames,
+    /// 2, SequenceEnd.
+    SequenceEnd,
+    /// 4, MultichannelConfig, the channel layout.
+    MultichannelConfig,
+    /// Unknown with the raw value, including the unsupported Multitrack and ModEx.
+    Unknown(u8),
+}
+
+impl AudioPacketType {
+    /// The value of packet type.
+    pub fn value(self) -> u8 {
+        match self {
+            AudioPacketType::SequenceStart => 0,
+            AudioPacketType::CodedFrames => 1,
+            AudioPacketType::SequenceEnd => 2,
+            AudioPacketType::MultichannelConfig => 4,
+            AudioPacketType::Unknown(value) => value,
+        }
+    }
 }
 
 /// The audio format.
@@ -61,8 +110,8 @@ pub enum SoundFormat {
     PcmALaw,
     /// 8, PcmMuLaw
     PcmMuLaw,
-    /// 9, Reserved
-    Reserved,
+    /// 9, the extended header of Enhanced RTMP, the codec is given by the FourCC.
+    ExHeader,
     /// 10, MPEG-4 Part3 AAC
     AAC,
     /// 11, Speex
@@ -71,6 +120,30 @@ pub enum SoundFormat {
     MP3_8kHz,
     /// 15, DeviceSpecific
     DeviceSpecific,
+    /// Unknown (the reserved 12 and 13), with the raw value.
+    Unknown(u8),
+}
+
+impl From<SoundFormat> for u8 {
+    fn from(format: SoundFormat) -> u8 {
+        match format {
+            SoundFormat::PcmPlatformEndian => 0,
+            SoundFormat::ADPCM => 1,
+            SoundFormat::MP3 => 2,
+            SoundFormat::PcmLittleEndian => 3,
+            SoundFormat::Nellymoser16kHzMono => 4,
+            SoundFormat::Nellymoser8kHzMono => 5,
+            SoundFormat::Nellymoser => 6,
+            SoundFormat::PcmALaw => 7,
+            SoundFormat::PcmMuLaw => 8,
+            SoundFormat::ExHeader => 9,
+            SoundFormat::AAC => 10,
+            SoundFormat::Speex => 11,
+            SoundFormat::MP3_8kHz => 14,
+            SoundFormat::DeviceSpecific => 15,
+            SoundFormat::Unknown(value) => value,
+        }
+    }
 }
 
 /// The audio sampling rate.
@@ -111,6 +184,11 @@ impl AudioTagHeader {
             return Err(NomErr::Incomplete(Needed::new(1)));
         }
 
+        // SoundFormat 9, ExHeader
+        if matches!(input.first(), Some(byte) if byte >> 4 == 9) {
+            return Self::parse_ex(input, size);
+        }
+
         let (remain, (sound_format, sound_rate, sound_size, sound_type)) = try_parse!(
             input,
             bits!(tuple!(
@@ -125,11 +203,12 @@ impl AudioTagHeader {
                     6  => value!(SoundFormat::Nellymoser)           |
                     7  => value!(SoundFormat::PcmALaw)              |
                     8  => value!(SoundFormat::PcmMuLaw)             |
-                    9  => value!(SoundFormat::Reserved)             |
+                    9  => value!(SoundFormat::ExHeader)             |
                     10 => value!(SoundFormat::AAC)                  |
                     11 => value!(SoundFormat::Speex)                |
                     14 => value!(SoundFormat::MP3_8kHz)             |
-                    15 => value!(SoundFormat::DeviceSpecific)
+                    15 => value!(SoundFormat::DeviceSpecific)       |
+                    other => value!(SoundFormat::Unknown(other))
                 ),
                 // parse sound rate
                 switch!(take_bits!(2u8),
@@ -158,9 +237,57 @@ impl AudioTagHeader {
                 sound_rate,
                 sound_size,
                 sound_type,
+                ex_header: None,
+            },
+        ))
+    }
+
+    fn parse_ex(input: &[u8], size: usize) -> IResult<&[u8], AudioTagHeader> {
+        if size < 5 {
+            return Err(NomErr::Incomplete(Needed::new(5)));
+        }
+
+        let (remain, (packet_type, fourcc)) = try_parse!(
+            input,
+            tuple!(
+                // skip sound format, parse packet type
+                switch!(map!(be_u8, |byte| byte & 0x0f),
+                    0 => value!(AudioPacketType::SequenceStart)      |
+                    1 => value!(AudioPacketType::CodedFrames)        |
+                    2 => value!(AudioPacketType::SequenceEnd)        |
+                    4 => value!(AudioPacketType::MultichannelConfig) |
+                    other => value!(AudioPacketType::Unknown(other))
+                ),
+                // parse FourCC
+                map!(take!(4), |fourcc| FourCC([
+                    fourcc[0], fourcc[1], fourcc[2], fourcc[3]
+                ]))
+            )
+        );
+
+        Ok((
+            remain,
+            AudioTagHeader {
+                sound_format: SoundFormat::ExHeader,
+                sound_rate: SoundRate::_44KHZ,
+                sound_size: SoundSize::_16Bit,
+                sound_type: SoundType::Stereo,
+                ex_header: Some(ExAudioTagHeader {
+                    packet_type,
+                    fourcc,
+                }),
             },
         ))
     }
+
+    /// The size of audio tag data header, 1 byte, or 5 bytes for the extended header.
+    pub fn size(&self) -> usize {
+        if self.ex_header.is_some() {
+            5
+        } else {
+            1
+        }
+    }
 }
 
 /// The `tag data body` part of `audio` FLV tag data.
@@ -205,7 +332,7 @@ pub enum AACPacketType {
 }
 
 /// Parse AAC audio packet.
-pub fn aac_audio_packet(input: &[u8], size: usize) -> IResult<&[u8], AACAudioPacket> {
+pub fn aac_audio_packet(input: &[u8], size: usize) -> IResult<&[u8], AACAudioPacket<'_>> {
     if input.len() < size {
         return Err(NomErr::Incomplete(Needed::new(size)));
     }
@@ -230,3 +357,44 @@ pub fn aac_audio_packet(input: &[u8], size: usize) -> IResult<&[u8], AACAudioPac
         },
     ))
 }
+
+/// The multichannel config of Enhanced RTMP audio, the `tag data body` part of
+/// `MultichannelConfig` audio packet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AudioMultichannelConfig<'a> {
+    /// The number of channels.
+    pub channel_count: u8,
+    /// The order of channels.
+    pub channel_order: AudioChannelOrder<'a>,
+}
+
+/// The order of audio channels.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AudioChannelOrder<'a> {
+    /// 0, Unspecified, only the number of channels is known.
+    Unspecified,
+    /// 1, Native, the channels present are flagged in the bit mask
+    /// (bit 0 = front left, bit 1 = front right, ...) in the native order.
+    Native(u32),
+    /// 2, Custom, the channel (speaker) of each audio channel.
+    Custom(&'a [u8]),
+}
+
+impl<'a> AudioMultichannelConfig<'a> {
+    /// Parse multichannel config.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], AudioMultichannelConfig<'a>> {
+        let (input, (order, channel_count)) = tuple!(input, be_u8, be_u8)?;
+        let (remain, channel_order) = switch!(input, value!(order),
+            0 => value!(AudioChannelOrder::Unspecified)              |
+            1 => map!(be_u32, AudioChannelOrder::Native)             |
+            2 => map!(take!(channel_count), AudioChannelOrder::Custom)
+        )?;
+        Ok((
+            remain,
+            AudioMultichannelConfig {
+                channel_count,
+                channel_order,
+            },
+        ))
+    }
+}
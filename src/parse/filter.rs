@@ -0,0 +1,215 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the filtered (e.g. encrypted) tag data, whose Filter bit of tag header is set.
+// [The FLV File Format Spec](https://www.adobe.com/content/dam/acom/en/devnet/flv/video_file_format_spec_v10_1.pdf), E.4.1 FLV Tag
+
+use nom::{
+    number::streaming::{be_u24, be_u8},
+    Err as NomErr, IResult, Needed,
+};
+
+use super::{AudioTagHeader, CodecID, FlvTagType, ScriptDataValue, SoundFormat, VideoTagHeader};
+
+/// The filter name of encryption filter.
+pub const ENCRYPTION_FILTER_NAME: &str = "Encryption";
+/// The filter name of selective encryption filter.
+pub const SELECTIVE_ENCRYPTION_FILTER_NAME: &str = "SE";
+
+/// The size of the initialization vector of filter params.
+pub const FILTER_IV_SIZE: usize = 16;
+
+/// The tag data part of FLV tag whose Filter bit is set,
+/// the data requires pre-processing (such as decryption) before it can be rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilteredTag<'a> {
+    /// The type of the tag.
+    pub tag_type: FlvTagType,
+    /// The unencrypted tag data header, i.e. the audio tag header (with the AACPacketType
+    /// for AAC), or the video tag header (with the AVCPacketType and CompositionTime
+    /// for AVC and HEVC), empty for script tag.
+    pub header: &'a [u8],
+    /// The encryption tag header.
+    pub encryption_header: EncryptionTagHeader<'a>,
+    /// The params of the filter.
+    pub filter_params: FilterParams<'a>,
+    /// The filtered (e.g. encrypted) tag data body.
+    pub data: &'a [u8],
+}
+
+impl<'a> FilteredTag<'a> {
+    /// Parse filtered tag data.
+    pub fn parse(
+        input: &'a [u8],
+        tag_type: FlvTagType,
+        size: usize,
+    ) -> IResult<&'a [u8], FilteredTag<'a>> {
+        let header_size = match tag_type {
+            FlvTagType::Audio => {
+                let (_, header) = AudioTagHeader::parse(input, size)?;
+                match header.sound_format {
+                    // AACPacketType
+                    SoundFormat::AAC => header.size() + 1,
+                    _ => header.size(),
+                }
+            }
+            FlvTagType::Video => {
+                let (_, header) = VideoTagHeader::parse(input, size)?;
+                match header.codec_id {
+                    // AVCPacketType + CompositionTime
+                    CodecID::AVC | CodecID::HEVC => header.size() + 4,
+                    _ => header.size(),
+                }
+            }
+            FlvTagType::Script => 0,
+        };
+        if input.len() < size.max(header_size) {
+            return Err(NomErr::Incomplete(Needed::new(size.max(header_size))));
+        }
+
+        let (data, remain) = input.split_at(size);
+        let (header, rest) = data.split_at(header_size.min(size));
+        let (rest, encryption_header) = EncryptionTagHeader::parse(rest)?;
+        let (data, filter_params) = FilterParams::parse(
+            rest,
+            encryption_header.filter_name,
+            encryption_header.length as usize,
+        )?;
+
+        Ok((
+            remain,
+            FilteredTag {
+                tag_type,
+                header,
+                encryption_header,
+                filter_params,
+                data,
+            },
+        ))
+    }
+
+    /// Parse the unencrypted audio tag header, `None` if the tag isn't audio.
+    pub fn audio_header(&self) -> Option<AudioTagHeader> {
+        match self.tag_type {
+            FlvTagType::Audio => AudioTagHeader::parse(self.header, self.header.len())
+                .ok()
+                .map(|(_, header)| header),
+            _ => None,
+        }
+    }
+
+    /// Parse the unencrypted video tag header, `None` if the tag isn't video.
+    pub fn video_header(&self) -> Option<VideoTagHeader> {
+        match self.tag_type {
+            FlvTagType::Video => VideoTagHeader::parse(self.header, self.header.len())
+                .ok()
+                .map(|(_, header)| header),
+            _ => None,
+        }
+    }
+}
+
+/// The encryption tag header of filtered tag data.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EncryptionTagHeader<'a> {
+    /// The number of filters applied to the packet, shall be 1.
+    pub num_filters: u8,
+    /// The name of the filter, `Encryption` or `SE`.
+    pub filter_name: &'a str,
+    /// The length of the filter params in bytes, 3 bytes.
+    pub length: u32,
+}
+
+impl<'a> EncryptionTagHeader<'a> {
+    /// Parse encryption tag header.
+    pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], EncryptionTagHeader<'a>> {
+        do_parse!(
+            input,
+            // Number of filters applied to the packet
+            num_filters: be_u8 >>
+            // Name of the filter, SCRIPTDATASTRING
+            filter_name: call!(ScriptDataValue::parse_string) >>
+            // Length of the filter params
+            length: be_u24 >>
+
+            (EncryptionTagHeader { num_filters, filter_name, length })
+        )
+    }
+}
+
+/// The params of the filter.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FilterParams<'a> {
+    /// The params of `Encryption` filter.
+    Encryption(EncryptionFilterParams),
+    /// The params of `SE` (selective encryption) filter.
+    SelectiveEncryption(SelectiveEncryptionFilterParams),
+    /// The params of unknown filter.
+    Unknown(&'a [u8]),
+}
+
+/// The params of `Encryption` filter.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EncryptionFilterParams {
+    /// The initialization vector to be used for AES-128-CBC decryption, 16 bytes.
+    pub iv: [u8; FILTER_IV_SIZE],
+}
+
+/// The params of `SE` (selective encryption) filter.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SelectiveEncryptionFilterParams {
+    /// Whether the packet is encrypted, 1 bit (followed by 7 reserved bits).
+    pub encrypted_au: bool,
+    /// The initialization vector, 16 bytes, only present if the packet is encrypted.
+    pub iv: Option<[u8; FILTER_IV_SIZE]>,
+}
+
+impl<'a> FilterParams<'a> {
+    /// Parse filter params of the filter, whose length is `length` bytes.
+    ///
+    /// The params of unknown filters are kept as raw bytes.
+    pub fn parse(
+        input: &'a [u8],
+        filter_name: &str,
+        length: usize,
+    ) -> IResult<&'a [u8], FilterParams<'a>> {
+        let (remain, params) = take!(input, length)?;
+        let params = match filter_name {
+            ENCRYPTION_FILTER_NAME => {
+                let (_, iv) = parse_iv(params)?;
+                FilterParams::Encryption(EncryptionFilterParams { iv })
+            }
+            SELECTIVE_ENCRYPTION_FILTER_NAME => {
+                let (params, flags) = be_u8(params)?;
+                let encrypted_au = flags & 0x80 != 0;
+                let iv = if encrypted_au {
+                    Some(parse_iv(params)?.1)
+                } else {
+                    None
+                };
+                FilterParams::SelectiveEncryption(SelectiveEncryptionFilterParams {
+                    encrypted_au,
+                    iv,
+                })
+            }
+            _ => FilterParams::Unknown(params),
+        };
+        Ok((remain, params))
+    }
+
+    /// The initialization vector, `None` if not present or the filter is unknown.
+    pub fn iv(&self) -> Option<&[u8; FILTER_IV_SIZE]> {
+        match self {
+            FilterParams::Encryption(params) => Some(&params.iv),
+            FilterParams::SelectiveEncryption(params) => params.iv.as_ref(),
+            FilterParams::Unknown(_) => None,
+        }
+    }
+}
+
+fn parse_iv(input: &[u8]) -> IResult<&[u8], [u8; FILTER_IV_SIZE]> {
+    map!(input, take!(FILTER_IV_SIZE), |iv| {
+        let mut buf = [0; FILTER_IV_SIZE];
+        buf.copy_from_slice(iv);
+        buf
+    })
+}
@@ -0,0 +1,38 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use core::fmt;
+
+/// The FourCC identifier of codec used by Enhanced RTMP.
+/// [Enhanced RTMP](https://github.com/veovera/enhanced-rtmp)
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FourCC(pub [u8; 4]);
+
+impl FourCC {
+    /// `avc1`, H.264.
+    pub const AVC: FourCC = FourCC(*b"avc1");
+    /// `hvc1`, H.265.
+    pub const HEVC: FourCC = FourCC(*b"hvc1");
+    /// `av01`, AV1.
+    pub const AV1: FourCC = FourCC(*b"av01");
+    /// `vp09`, VP9.
+    pub const VP9: FourCC = FourCC(*b"vp09");
+
+    /// `Opus`, Opus.
+    pub const OPUS: FourCC = FourCC(*b"Opus");
+    /// `fLaC`, FLAC.
+    pub const FLAC: FourCC = FourCC(*b"fLaC");
+    /// `ac-3`, AC-3.
+    pub const AC3: FourCC = FourCC(*b"ac-3");
+    /// `ec-3`, E-AC-3.
+    pub const EAC3: FourCC = FourCC(*b"ec-3");
+    /// `.mp3`, MP3.
+    pub const MP3: FourCC = FourCC(*b".mp3");
+    /// `mp4a`, AAC.
+    pub const AAC: FourCC = FourCC(*b"mp4a");
+}
+
+impl fmt::Debug for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FourCC(\"{}\")", self.0.escape_ascii())
+    }
+}
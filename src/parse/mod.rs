@@ -3,7 +3,10 @@
 // Parse the structure of the contents of FLV files.
 // [The FLV File Format Spec](https://www.adobe.com/content/dam/acom/en/devnet/flv/video_file_format_spec_v10_1.pdf)
 
+mod amf3;
 mod audio;
+mod filter;
+mod fourcc;
 mod script;
 mod video;
 
@@ -12,34 +15,85 @@ use alloc::vec::Vec;
 
 use nom::{
     number::streaming::{be_u24, be_u32, be_u8},
-    IResult,
+    Err as NomErr, IResult, Needed,
 };
 
-pub use self::{audio::*, script::*, video::*};
+pub use self::{amf3::*, audio::*, filter::*, fourcc::*, script::*, video::*};
+use crate::error::{FlvError, FlvStructure};
 
 const FLV_HEADER_SIGNATURE: [u8; 3] = [0x46, 0x4c, 0x56];
+const FLV_FILE_HEADER_SIZE: usize = 9;
+const FLV_TAG_HEADER_SIZE: usize = 11;
+const PREVIOUS_TAG_SIZE_SIZE: usize = 4;
 
 /// The FLV file structure, including header and body.
 #[derive(Clone, Debug, PartialEq)]
 pub struct FlvFile<'a> {
     /// The header of FLV file.
     pub header: FlvFileHeader,
+    /// The extension data of FLV file header, the bytes between the 9-byte header
+    /// and `data_offset`, usually empty.
+    pub extension: &'a [u8],
     /// The body of FLV file.
     pub body: FlvFileBody<'a>,
 }
 
 impl<'a> FlvFile<'a> {
-    /// Parse FLV file.
+    /// Parse FLV file, the body starts at the `data_offset` of file header.
     pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], FlvFile<'a>> {
-        do_parse!(
-            input,
-            // parse file header
-            header: call!(FlvFileHeader::parse) >>
-            // parse file body
-            body: call!(FlvFileBody::parse) >>
+        let (_, (header, extension, first_previous_tag_size, mut iter)) = Self::parse_lazy(input)?;
+        let tags = iter.by_ref().collect();
+        Ok((
+            iter.remain(),
+            FlvFile {
+                header,
+                extension,
+                body: FlvFileBody {
+                    first_previous_tag_size,
+                    tags,
+                },
+            },
+        ))
+    }
 
-            (FlvFile { header, body })
-        )
+    /// Parse the file header, its extension data and the first previous tag size of FLV file,
+    /// and return an iterator that parses the rest of input (FLV tags) lazily,
+    /// whose errors have the offsets in the file.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_lazy(
+        input: &'a [u8],
+    ) -> IResult<&'a [u8], (FlvFileHeader, &'a [u8], u32, FlvTagIter<'a>)> {
+        let (body, (header, extension)) = FlvFileHeader::parse_with_extension(input)?;
+        let (tags, first_previous_tag_size) = be_u32(body)?;
+        let iter = FlvTagIter::with_offset(tags, input.len() - tags.len());
+        Ok((
+            &tags[tags.len()..],
+            (header, extension, first_previous_tag_size, iter),
+        ))
+    }
+
+    /// Parse FLV file, failing with the error of the first structure that cannot be parsed,
+    /// unlike `parse` which stops at the first invalid tag silently.
+    pub fn try_parse(input: &'a [u8]) -> Result<FlvFile<'a>, FlvError> {
+        let (body, (header, extension)) = FlvFileHeader::parse_with_extension(input)
+            .map_err(|err| FlvError::from_nom(err, FlvStructure::FileHeader, input, 0, None))?;
+        let body_offset = input.len() - body.len();
+        let (tags, first_previous_tag_size) = be_u32(body).map_err(|err| {
+            FlvError::from_nom(err, FlvStructure::PreviousTagSize, body, body_offset, None)
+        })?;
+        let mut iter = FlvTagIter::with_offset(tags, body_offset + PREVIOUS_TAG_SIZE_SIZE);
+        let tags = iter.by_ref().collect();
+        match iter.error() {
+            Some(err) => Err(*err),
+            None => Ok(FlvFile {
+                header,
+                extension,
+                body: FlvFileBody {
+                    first_previous_tag_size,
+                    tags,
+                },
+            }),
+        }
     }
 }
 
@@ -87,6 +141,56 @@ impl FlvFileHeader {
             })
         )
     }
+
+    /// Parse FLV file header and its extension data, the bytes between the 9-byte header
+    /// and `data_offset`, so the rest of input is the file body.
+    ///
+    /// The `data_offset` less than 9 is invalid.
+    pub fn parse_with_extension(input: &[u8]) -> IResult<&[u8], (FlvFileHeader, &[u8])> {
+        do_parse!(
+            input,
+            header: verify!(call!(FlvFileHeader::parse), |header: &FlvFileHeader| {
+                header.data_offset as usize >= FLV_FILE_HEADER_SIZE
+            }) >>
+            extension: take!(header.data_offset as usize - FLV_FILE_HEADER_SIZE) >>
+
+            ((header, extension))
+        )
+    }
+
+    /// The size of the extension data of file header.
+    pub fn extension_size(&self) -> usize {
+        (self.data_offset as usize).saturating_sub(FLV_FILE_HEADER_SIZE)
+    }
+
+    /// The reserved bits of the flags, which shall be 0.
+    pub fn reserved_flags(&self) -> FlvFileHeaderReservedFlags {
+        FlvFileHeaderReservedFlags {
+            high: self.flags >> 3,
+            low: self.flags & 0b10 != 0,
+        }
+    }
+}
+
+/// The reserved bits of the flags of FLV file header, which shall be 0.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FlvFileHeaderReservedFlags {
+    /// The TypeFlagsReserved (5 bits) before TypeFlagsAudio.
+    pub high: u8,
+    /// The TypeFlagsReserved (1 bit) between TypeFlagsAudio and TypeFlagsVideo.
+    pub low: bool,
+}
+
+impl FlvFileHeaderReservedFlags {
+    /// Whether none of the reserved bits is set.
+    pub fn is_empty(&self) -> bool {
+        self.high == 0 && !self.low
+    }
+
+    /// The reserved bits at their positions of the flags.
+    pub fn bits(&self) -> u8 {
+        self.high << 3 | u8::from(self.low) << 1
+    }
 }
 
 /// The body part of FLV file.
@@ -99,21 +203,186 @@ pub struct FlvFileBody<'a> {
 }
 
 impl<'a> FlvFileBody<'a> {
-    // https://github.com/Geal/nom/issues/790 - many0 returns Incomplete in weird cases.
     /// Parse FLV file body.
     pub fn parse(input: &'a [u8]) -> IResult<&'a [u8], FlvFileBody<'a>> {
-        do_parse!(
+        let (_, (first_previous_tag_size, mut iter)) = Self::parse_lazy(input)?;
+        let tags = iter.by_ref().collect();
+        Ok((
+            iter.remain(),
+            FlvFileBody {
+                first_previous_tag_size,
+                tags,
+            },
+        ))
+    }
+
+    /// Parse the first previous tag size of FLV file body,
+    /// and return an iterator that parses the rest of input (FLV tags) lazily.
+    ///
+    /// The error offsets assume the file header without extension data (see `FlvTagIter::new`),
+    /// use `FlvFile::parse_lazy` otherwise.
+    pub fn parse_lazy(input: &'a [u8]) -> IResult<&'a [u8], (u32, FlvTagIter<'a>)> {
+        let (remain, first_previous_tag_size) = be_u32(input)?;
+        Ok((
+            &remain[remain.len()..],
+            (first_previous_tag_size, FlvTagIter::new(remain)),
+        ))
+    }
+}
+
+/// An iterator over the FLV tags (and the size of each tag) of FLV file body,
+/// which parses one tag at a time instead of collecting all of them.
+///
+/// The values of script tags (such as ECMA arrays and objects) are still collected
+/// into `Vec`s, so the `alloc` (or `std`) feature is required.
+///
+/// The iteration ends at the end of input or at the first tag that cannot be parsed
+/// (like `FlvFileBody::parse`), the unparsed input is available through `remain`,
+/// and the error through `error`.
+#[derive(Clone, Debug)]
+pub struct FlvTagIter<'a> {
+    input: &'a [u8],
+    done: bool,
+    // The absolute offset of the input.
+    offset: usize,
+    index: usize,
+    error: Option<FlvError>,
+}
+
+impl<'a> FlvTagIter<'a> {
+    /// Create an iterator over the FLV tags of input,
+    /// the input should start after the first previous tag size of FLV file body.
+    ///
+    /// The error offsets assume the 9-byte file header without extension data,
+    /// use `with_offset` otherwise.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_offset(input, FLV_FILE_HEADER_SIZE + PREVIOUS_TAG_SIZE_SIZE)
+    }
+
+    /// Create an iterator over the FLV tags of input, which starts at the absolute
+    /// `offset` of FLV file (used by the errors).
+    pub fn with_offset(input: &'a [u8], offset: usize) -> Self {
+        Self {
             input,
-            // The first previous tag size.
-            first_previous_tag_size: be_u32 >>
-            // FLV Tag and the size of the tag.
-            tags: many0!(complete!(tuple!(call!(FlvTag::parse), be_u32))) >>
+            done: false,
+            offset,
+            index: 0,
+            error: None,
+        }
+    }
 
-            (FlvFileBody { first_previous_tag_size, tags })
-        )
+    /// The input that hasn't been parsed yet.
+    pub fn remain(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// The error of the tag that cannot be parsed, `None` if the iteration
+    /// hasn't ended or ended at the end of input.
+    pub fn error(&self) -> Option<&FlvError> {
+        self.error.as_ref()
     }
 }
 
+impl<'a> Iterator for FlvTagIter<'a> {
+    type Item = (FlvTag<'a>, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.input.is_empty() {
+            self.done = true;
+            return None;
+        }
+        match flv_tag_with_size(self.input, self.offset, self.index) {
+            Ok((remain, tag, previous_tag_size)) => {
+                self.offset += self.input.len() - remain.len();
+                self.index += 1;
+                self.input = remain;
+                Some((tag, previous_tag_size))
+            }
+            Err(err) => {
+                self.error = Some(err);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Parse the FLV tag and the previous tag size that follows it, the input is at
+/// the absolute `offset` of FLV file, and the tag is the `index`-th tag.
+///
+/// The tag data is parsed within the data size, so the data that is shorter than
+/// its structure is malformed rather than truncated.
+pub(crate) fn flv_tag_with_size(
+    input: &[u8],
+    offset: usize,
+    index: usize,
+) -> Result<(&[u8], FlvTag<'_>, u32), FlvError> {
+    let error = |err, structure, input, offset| {
+        FlvError::from_nom(err, structure, input, offset, Some(index))
+    };
+    let (rest, header) = FlvTagHeader::parse(input)
+        .map_err(|err| error(err, FlvStructure::TagHeader, input, offset))?;
+    let data_offset = offset + FLV_TAG_HEADER_SIZE;
+    let size = header.data_size as usize;
+    if rest.len() < size {
+        return Err(error(
+            NomErr::Incomplete(Needed::new(size - rest.len())),
+            FlvStructure::TagData,
+            rest,
+            data_offset,
+        ));
+    }
+    let (data, rest) = rest.split_at(size);
+    let data = match header.tag_type {
+        _ if header.filter => {
+            let (_, filtered) = FilteredTag::parse(data, header.tag_type, size).map_err(|err| {
+                error(err, FlvStructure::EncryptionHeader, data, data_offset).complete()
+            })?;
+            FlvTagData::Filtered(filtered)
+        }
+        FlvTagType::Audio => {
+            let (body, audio) = AudioTagHeader::parse(data, size).map_err(|err| {
+                error(err, FlvStructure::AudioTagHeader, data, data_offset).complete()
+            })?;
+            let body_offset = data_offset + audio.size();
+            let (_, body) = AudioTagBody::parse(body, size - audio.size()).map_err(|err| {
+                error(err, FlvStructure::AudioTagBody, body, body_offset).complete()
+            })?;
+            FlvTagData::Audio(AudioTag {
+                header: audio,
+                body,
+            })
+        }
+        FlvTagType::Video => {
+            let (body, video) = VideoTagHeader::parse(data, size).map_err(|err| {
+                error(err, FlvStructure::VideoTagHeader, data, data_offset).complete()
+            })?;
+            let body_offset = data_offset + video.size();
+            let (_, body) = VideoTagBody::parse(body, size - video.size()).map_err(|err| {
+                error(err, FlvStructure::VideoTagBody, body, body_offset).complete()
+            })?;
+            FlvTagData::Video(VideoTag {
+                header: video,
+                body,
+            })
+        }
+        FlvTagType::Script => {
+            let (_, script) = ScriptTag::parse(data, size).map_err(|err| {
+                error(err, FlvStructure::ScriptData, data, data_offset).complete()
+            })?;
+            FlvTagData::Script(script)
+        }
+    };
+    let (rest, previous_tag_size) = be_u32(rest)
+        .map_err(|err| error(err, FlvStructure::PreviousTagSize, rest, data_offset + size))?;
+    Ok((rest, FlvTag { header, data }, previous_tag_size))
+}
+
+impl<'a> core::iter::FusedIterator for FlvTagIter<'a> {}
+
 /// The FLV tag has three types: `script tag`, `audio tag` and `video tag`.
 /// Each tag contains tag header and tag data.
 /// The structure of each type of tag header is the same.
@@ -136,7 +405,13 @@ impl<'a> FlvTag<'a> {
             // parse tag header
             header: call!(FlvTagHeader::parse) >>
             // parse tag data
-            data: call!(FlvTagData::parse, header.tag_type, header.data_size as usize) >>
+            data: switch!(value!(header.filter),
+                false => call!(FlvTagData::parse, header.tag_type, header.data_size as usize) |
+                true  => map!(
+                    call!(FilteredTag::parse, header.tag_type, header.data_size as usize),
+                    FlvTagData::Filtered
+                )
+            ) >>
 
             (FlvTag { header, data })
         )
@@ -146,13 +421,15 @@ impl<'a> FlvTag<'a> {
 /// The tag header part of FLV tag.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct FlvTagHeader {
-    /// Reserved    2 bits  Reserved for FMS, should be 0.
-    /// Filter      1 bit   Indicates if packets are filtered.
-    ///                     0 = No pre-processing required
-    ///                     1 = Pre-processing (Such as decryption) of the packet
-    ///                         is required before it can be rendered.
-    /// TagType     5 bits  The type of contents in this tag,
-    ///                     8 = audio, 9 = video, 18 = script.
+    /// Reserved for FMS, should be 0, 2 bits.
+    pub reserved: u8,
+    /// Indicates if packets are filtered, 1 bit.
+    /// false = No pre-processing required
+    /// true  = Pre-processing (Such as decryption) of the packet
+    ///         is required before it can be rendered.
+    pub filter: bool,
+    /// The type of contents in this tag, 5 bits,
+    /// 8 = audio, 9 = video, 18 = script.
     pub tag_type: FlvTagType,
     /// The size of the tag's data part, 3 bytes.
     pub data_size: u32,
@@ -170,7 +447,7 @@ pub enum FlvTagType {
     /// Video tag type.
     Video = 0x09,
     /// Script tag type.
-    Script = 0x18,
+    Script = 0x12,
 }
 
 impl FlvTagHeader {
@@ -178,8 +455,10 @@ impl FlvTagHeader {
     pub fn parse(input: &[u8]) -> IResult<&[u8], FlvTagHeader> {
         do_parse!(
             input,
+            // Reserved (2 bits) + Filter (1 bit) + TagType (5 bits)
+            flags: peek!(be_u8) >>
             // Tag Type
-            tag_type: switch!(be_u8,
+            tag_type: switch!(map!(be_u8, |byte| byte & 0x1f),
                     8  => value!(FlvTagType::Audio) |
                     9  => value!(FlvTagType::Video) |
                     18 => value!(FlvTagType::Script)
@@ -194,6 +473,8 @@ impl FlvTagHeader {
             stream_id: be_u24 >>
 
             (FlvTagHeader {
+                reserved: flags >> 6,
+                filter: flags & 0x20 != 0,
                 tag_type,
                 data_size,
                 timestamp: (u32::from(timestamp_extended) << 24) + timestamp,
@@ -212,10 +493,12 @@ pub enum FlvTagData<'a> {
     Video(VideoTag<'a>),
     /// Script tag data.
     Script(ScriptTag<'a>),
+    /// Filtered (e.g. encrypted) tag data, if the Filter bit of tag header is set.
+    Filtered(FilteredTag<'a>),
 }
 
 impl<'a> FlvTagData<'a> {
-    /// Parse FLV tag data.
+    /// Parse FLV tag data, which isn't filtered.
     pub fn parse(
         input: &'a [u8],
         tag_type: FlvTagType,
@@ -6,9 +6,11 @@ use core::str;
 
 use nom::{
     number::streaming::{be_f64, be_i16, be_u16, be_u32, be_u8},
-    IResult,
+    Err as NomErr, IResult, Needed,
 };
 
+use super::Amf3Value;
+
 const SCRIPT_DATA_VALUE_STRING_TYPE: [u8; 1] = [0x02];
 const OBJECT_END_MARKER: [u8; 3] = [0x00, 0x00, 0x09];
 
@@ -23,13 +25,21 @@ pub struct ScriptTag<'a> {
     /// AMF arguments or object properties.
     /// ScriptTagValue.Type = 8 (ECMAArray)
     pub value: ScriptDataValue<'a>,
+    /// The raw script tag data it was parsed from (including the bytes after `value`),
+    /// which is written back as is; `None` to write the `name` and `value` instead.
+    pub raw: Option<&'a [u8]>,
 }
 
 impl<'a> ScriptTag<'a> {
-    /// Parse script tag data.
-    pub fn parse(input: &'a [u8], _size: usize) -> IResult<&'a [u8], ScriptTag<'a>> {
-        do_parse!(
-            input,
+    /// Parse script tag data, whose size is `size` bytes.
+    pub fn parse(input: &'a [u8], size: usize) -> IResult<&'a [u8], ScriptTag<'a>> {
+        if input.len() < size {
+            return Err(NomErr::Incomplete(Needed::new(size)));
+        }
+
+        let (raw, remain) = input.split_at(size);
+        let (_, (name, value)) = do_parse!(
+            raw,
             // ScriptTagValue.Type = 2 (String)
             tag!(SCRIPT_DATA_VALUE_STRING_TYPE) >>
             // Method or object name.
@@ -38,8 +48,16 @@ impl<'a> ScriptTag<'a> {
             // ScriptTagValue.Type = 8 (ECMA array)
             value: call!(ScriptDataValue::parse) >>
 
-            (ScriptTag { name, value })
-        )
+            ((name, value))
+        )?;
+        Ok((
+            remain,
+            ScriptTag {
+                name,
+                value,
+                raw: Some(raw),
+            },
+        ))
     }
 }
 
@@ -70,6 +88,8 @@ pub enum ScriptDataValue<'a> {
     Date(ScriptDataDate),
     /// 12, Long String value.
     LongString(&'a str),
+    /// 17, AVM+ value, switching to AMF3.
+    AVMPlus(Amf3Value<'a>),
 }
 
 impl<'a> ScriptDataValue<'a> {
@@ -90,7 +110,8 @@ impl<'a> ScriptDataValue<'a> {
             8  => map!(Self::parse_ecma_array, ScriptDataValue::ECMAArray)        |
             10 => map!(Self::parse_strict_array, ScriptDataValue::StrictArray)    |
             11 => map!(Self::parse_date, ScriptDataValue::Date)                   |
-            12 => map!(Self::parse_long_string, ScriptDataValue::LongString)
+            12 => map!(Self::parse_long_string, ScriptDataValue::LongString)     |
+            17 => map!(Amf3Value::parse, ScriptDataValue::AVMPlus)
         )
     }
 
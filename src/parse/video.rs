@@ -5,6 +5,8 @@ use nom::{
     Err as NomErr, IResult, Needed,
 };
 
+use super::FourCC;
+
 /// The tag data part of `video` FLV tag, including `tag data header` and `tag data body`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct VideoTag<'a> {
@@ -22,7 +24,7 @@ impl<'a> VideoTag<'a> {
             // parse video tag data header
             header: call!(VideoTagHeader::parse, size) >>
             // parse video tag data body
-            body: call!(VideoTagBody::parse, size - 1) >>
+            body: call!(VideoTagBody::parse, size - header.size()) >>
 
             (VideoTag {header, body })
         )
@@ -35,7 +37,56 @@ pub struct VideoTagHeader {
     /// The frame type of `video` FLV tag, 4 bits.
     pub frame_type: FrameType,
     /// The codec id of `video` FLV tag, 4 bits.
+    /// For the extended header of Enhanced RTMP, the codec id is `ExHeader`.
     pub codec_id: CodecID,
+    /// The extended header of Enhanced RTMP, only if IsExHeader (the high bit) is set.
+    pub ex_header: Option<ExVideoTagHeader>,
+}
+
+/// The extended video tag header of Enhanced RTMP,
+/// the frame type is 3 bits and the codec id is replaced by the packet type.
+/// [Enhanced RTMP](https://github.com/veovera/enhanced-rtmp)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExVideoTagHeader {
+    /// The type of video packet, 4 bits.
+    pub packet_type: VideoPacketType,
+    /// The FourCC of video codec, 4 bytes.
+    pub fourcc: FourCC,
+}
+
+/// The type of video packet of Enhanced RTMP.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VideoPacketType {
+    /// 0, SequenceStart, the codec configuration record.
+    SequenceStart,
+    /// 1, CodedFrames, with composition time for `avc1` and `hvc1`.
+    CodedFrames,
+    /// 2, SequenceEnd.
+    SequenceEnd,
+    /// 3, CodedFramesX, the composition time is implicitly 0.
+    CodedFramesX,
+    /// 4, Metadata, the AMF encoded metadata of video (like HDR).
+    Metadata,
+    /// 5, MPEG2TSSequenceStart, the MPEG-2 TS format of codec configuration.
+    MPEG2TSSequenceStart,
+    /// Unknown, with the raw value.
+    Unknown(u8),
+}
+
+impl VideoPacketType {
+    /// The value of packet type.
+    pub fn value(self) -> u8 {
+        match self {
+            VideoPacketType::SequenceStart => 0,
+            VideoPacketType::CodedFrames => 1,
+            VideoPacketType::SequenceEnd => 2,
+            VideoPacketType::CodedFramesX => 3,
+            VideoPacketType::Metadata => 4,
+            VideoPacketType::MPEG2TSSequenceStart => 5,
+            VideoPacketType::Unknown(value) => value,
+        }
+    }
 }
 
 /// The type of video frame.
@@ -51,8 +102,22 @@ pub enum FrameType {
     Generated,
     /// 5, Command frame.
     Command,
-    /// Unknown frame.
-    Unknown,
+    /// Unknown frame, with the raw value.
+    Unknown(u8),
+}
+
+impl FrameType {
+    /// The value of frame type.
+    pub fn value(self) -> u8 {
+        match self {
+            FrameType::Key => 1,
+            FrameType::Inter => 2,
+            FrameType::DisposableInter => 3,
+            FrameType::Generated => 4,
+            FrameType::Command => 5,
+            FrameType::Unknown(value) => value,
+        }
+    }
 }
 
 /// The code identifier of video.
@@ -71,8 +136,29 @@ pub enum CodecID {
     Screen2,
     /// 7, MPEG-4 Part 10 AVC / H.264
     AVC,
-    /// Unknown codec ID.
-    Unknown,
+    /// 12, HEVC / H.265, the unofficial extension with the same packet layout as AVC.
+    HEVC,
+    /// The extended header of Enhanced RTMP, the codec is given by the FourCC.
+    ExHeader,
+    /// Unknown codec ID, with the raw value.
+    Unknown(u8),
+}
+
+impl CodecID {
+    /// The value of codec id, `None` for the extended header.
+    pub fn value(self) -> Option<u8> {
+        match self {
+            CodecID::SorensonH263 => Some(2),
+            CodecID::Screen1 => Some(3),
+            CodecID::VP6 => Some(4),
+            CodecID::VP6Alpha => Some(5),
+            CodecID::Screen2 => Some(6),
+            CodecID::AVC => Some(7),
+            CodecID::HEVC => Some(12),
+            CodecID::Unknown(value) => Some(value),
+            CodecID::ExHeader => None,
+        }
+    }
 }
 
 impl VideoTagHeader {
@@ -82,6 +168,11 @@ impl VideoTagHeader {
             return Err(NomErr::Incomplete(Needed::new(1)));
         }
 
+        // IsExHeader
+        if matches!(input.first(), Some(byte) if byte & 0x80 != 0) {
+            return Self::parse_ex(input, size);
+        }
+
         let (remain, (frame_type, codec_id)) = try_parse!(
             input,
             bits!(tuple!(
@@ -92,17 +183,18 @@ impl VideoTagHeader {
                     3  => value!(FrameType::DisposableInter) |
                     4  => value!(FrameType::Generated)       |
                     5  => value!(FrameType::Command)         |
-                    _  => value!(FrameType::Unknown)
+                    other => value!(FrameType::Unknown(other))
                 ),
                 // parse code id
                 switch!(take_bits!(4u8),
-                    2 => value!(CodecID::SorensonH263) |
-                    3 => value!(CodecID::Screen1)      |
-                    4 => value!(CodecID::VP6)          |
-                    5 => value!(CodecID::VP6Alpha)     |
-                    6 => value!(CodecID::Screen2)      |
-                    7 => value!(CodecID::AVC)          |
-                    _ => value!(CodecID::Unknown)
+                    2  => value!(CodecID::SorensonH263) |
+                    3  => value!(CodecID::Screen1)      |
+                    4  => value!(CodecID::VP6)          |
+                    5  => value!(CodecID::VP6Alpha)     |
+                    6  => value!(CodecID::Screen2)      |
+                    7  => value!(CodecID::AVC)          |
+                    12 => value!(CodecID::HEVC)         |
+                    other => value!(CodecID::Unknown(other))
                 )
             ))
         );
@@ -112,9 +204,70 @@ impl VideoTagHeader {
             VideoTagHeader {
                 frame_type,
                 codec_id,
+                ex_header: None,
+            },
+        ))
+    }
+
+    fn parse_ex(input: &[u8], size: usize) -> IResult<&[u8], VideoTagHeader> {
+        if size < 5 {
+            return Err(NomErr::Incomplete(Needed::new(5)));
+        }
+
+        let (remain, ((_, frame_type, packet_type), fourcc)) = try_parse!(
+            input,
+            tuple!(
+                bits!(tuple!(
+                    // skip IsExHeader
+                    map!(take_bits!(1u8), |_: u8| ()),
+                    // parse frame type
+                    switch!(take_bits!(3u8),
+                        1  => value!(FrameType::Key)             |
+                        2  => value!(FrameType::Inter)           |
+                        3  => value!(FrameType::DisposableInter) |
+                        4  => value!(FrameType::Generated)       |
+                        5  => value!(FrameType::Command)         |
+                        other => value!(FrameType::Unknown(other))
+                    ),
+                    // parse packet type
+                    switch!(take_bits!(4u8),
+                        0 => value!(VideoPacketType::SequenceStart)        |
+                        1 => value!(VideoPacketType::CodedFrames)          |
+                        2 => value!(VideoPacketType::SequenceEnd)          |
+                        3 => value!(VideoPacketType::CodedFramesX)         |
+                        4 => value!(VideoPacketType::Metadata)             |
+                        5 => value!(VideoPacketType::MPEG2TSSequenceStart) |
+                        other => value!(VideoPacketType::Unknown(other))
+                    )
+                )),
+                // parse FourCC
+                map!(take!(4), |fourcc| FourCC([
+                    fourcc[0], fourcc[1], fourcc[2], fourcc[3]
+                ]))
+            )
+        );
+
+        Ok((
+            remain,
+            VideoTagHeader {
+                frame_type,
+                codec_id: CodecID::ExHeader,
+                ex_header: Some(ExVideoTagHeader {
+                    packet_type,
+                    fourcc,
+                }),
             },
         ))
     }
+
+    /// The size of video tag data header, 1 byte, or 5 bytes for the extended header.
+    pub fn size(&self) -> usize {
+        if self.ex_header.is_some() {
+            5
+        } else {
+            1
+        }
+    }
 }
 
 /// The `tag data body` part of `video` FLV tag data.
@@ -165,12 +318,15 @@ pub enum AvcPacketType {
     NALU,
     /// 2, EndOfSequence.
     EndOfSequence,
-    /// Unknown
-    Unknown,
+    /// Unknown, with the raw value.
+    Unknown(u8),
 }
 
 /// Parse AVC video packet.
-pub fn avc_video_packet(input: &[u8], size: usize) -> IResult<&[u8], AvcVideoPacket> {
+///
+/// The video packet of `CodecID` 12 -- HEVC has the same layout,
+/// whose sequence header is the HEVCDecoderConfigurationRecord.
+pub fn avc_video_packet(input: &[u8], size: usize) -> IResult<&[u8], AvcVideoPacket<'_>> {
     if input.len() < size {
         return Err(NomErr::Incomplete(Needed::new(size)));
     }
@@ -186,7 +342,7 @@ pub fn avc_video_packet(input: &[u8], size: usize) -> IResult<&[u8], AvcVideoPac
                 0 => value!(AvcPacketType::SequenceHeader)  |
                 1 => value!(AvcPacketType::NALU)            |
                 2 => value!(AvcPacketType::EndOfSequence)   |
-                _ => value!(AvcPacketType::Unknown)
+                other => value!(AvcPacketType::Unknown(other))
             ),
             be_i24
         )
@@ -201,3 +357,50 @@ pub fn avc_video_packet(input: &[u8], size: usize) -> IResult<&[u8], AvcVideoPac
         },
     ))
 }
+
+/// The `tag data body` part of `video` FLV tag data with the extended header of Enhanced RTMP.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExVideoPacket<'a> {
+    /// The type of video packet.
+    pub packet_type: VideoPacketType,
+    /// The FourCC of video codec.
+    pub fourcc: FourCC,
+    /// The composition time offset (in milliseconds), 3 bytes,
+    /// only present in `CodedFrames` packet of `avc1` and `hvc1`, otherwise 0.
+    pub composition_time: i32,
+    /// The actual video data.
+    pub data: &'a [u8],
+}
+
+/// Parse video packet with the extended header of Enhanced RTMP.
+pub fn ex_video_packet<'a>(
+    header: &ExVideoTagHeader,
+    input: &'a [u8],
+    size: usize,
+) -> IResult<&'a [u8], ExVideoPacket<'a>> {
+    if input.len() < size {
+        return Err(NomErr::Incomplete(Needed::new(size)));
+    }
+
+    let has_composition_time = header.packet_type == VideoPacketType::CodedFrames
+        && (header.fourcc == FourCC::AVC || header.fourcc == FourCC::HEVC);
+    let (composition_time, data) = if has_composition_time {
+        if size < 3 {
+            return Err(NomErr::Incomplete(Needed::new(3)));
+        }
+        let (_, composition_time) = be_i24(input)?;
+        (composition_time, &input[3..size])
+    } else {
+        (0, &input[..size])
+    };
+
+    Ok((
+        &input[size..],
+        ExVideoPacket {
+            packet_type: header.packet_type,
+            fourcc: header.fourcc,
+            composition_time,
+            data,
+        },
+    ))
+}
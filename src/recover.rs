@@ -0,0 +1,142 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Parse the FLV tags with recovery from the corrupted ones, by scanning forward
+// for the next plausible tag header.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use core::ops::Range;
+
+use nom::{
+    number::streaming::{be_u24, be_u32, be_u8},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::parse::{FlvFile, FlvFileBody, FlvFileHeader, FlvTag};
+
+const FLV_TAG_HEADER_SIZE: usize = 11;
+const PREVIOUS_TAG_SIZE_SIZE: usize = 4;
+
+/// The item yielded by `FlvRecoveringTagIter`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlvRecoveryEvent<'a> {
+    /// A FLV tag and the previous tag size that follows it.
+    Tag(FlvTag<'a>, u32),
+    /// The byte range (relative to the input of the iterator) that was skipped,
+    /// since no plausible tag starts there.
+    Skipped(Range<usize>),
+}
+
+/// An iterator over the FLV tags of FLV file body, which recovers from the corrupted tags
+/// instead of stopping at them.
+///
+/// The tag at the expected position (right after the previous one) is parsed as usual.
+/// If it cannot be parsed, the input is scanned forward byte by byte for the next
+/// plausible tag, and the bytes in between are reported as skipped.
+///
+/// While scanning, a tag is accepted only if its header is plausible: the tag type is 8, 9 or 18
+/// (the Filter bit may be set, but not the reserved bits), the stream id is 0, the data size is not 0 and the tag fits in the input,
+/// and the following previous tag size matches the tag; and its data can be parsed.
+#[derive(Clone, Debug)]
+pub struct FlvRecoveringTagIter<'a> {
+    input: &'a [u8],
+    // The position of the next tag.
+    pos: usize,
+}
+
+impl<'a> FlvRecoveringTagIter<'a> {
+    /// Create an iterator over the FLV tags of input,
+    /// the input should start after the first previous tag size of FLV file body.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// The input that hasn't been parsed yet.
+    pub fn remain(&self) -> &'a [u8] {
+        &self.input[self.pos..]
+    }
+
+    // Parse the tag and the previous tag size at the position as usual,
+    // and return the tag, the previous tag size and the size of both.
+    fn parse_at(&self, pos: usize) -> Option<(FlvTag<'a>, u32, usize)> {
+        let input = &self.input[pos..];
+        let (remain, tag) = FlvTag::parse(input).ok()?;
+        let (remain, previous_tag_size) = be_u32::<_, ()>(remain).ok()?;
+        Some((tag, previous_tag_size, input.len() - remain.len()))
+    }
+
+    // Parse the tag at the position if it's plausible, like `parse_at`.
+    fn parse_plausible_at(&self, pos: usize) -> Option<(FlvTag<'a>, u32, usize)> {
+        let input = &self.input[pos..];
+        let (_, (tag_type, data_size, _, _, stream_id)) =
+            tuple::<_, _, (), _>((be_u8, be_u24, be_u24, be_u8, be_u24))(input).ok()?;
+        if !matches!(tag_type & 0xdf, 8 | 9 | 18) || stream_id != 0 || data_size == 0 {
+            return None;
+        }
+        let tag_size = FLV_TAG_HEADER_SIZE + data_size as usize;
+        let (_, previous_tag_size) = be_u32::<_, ()>(input.get(tag_size..)?).ok()?;
+        if previous_tag_size as usize != tag_size {
+            return None;
+        }
+        let (_, tag) = FlvTag::parse(&input[..tag_size]).ok()?;
+        Some((tag, previous_tag_size, tag_size + PREVIOUS_TAG_SIZE_SIZE))
+    }
+}
+
+impl<'a> Iterator for FlvRecoveringTagIter<'a> {
+    type Item = FlvRecoveryEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        if start >= self.input.len() {
+            return None;
+        }
+        if let Some((tag, previous_tag_size, size)) = self.parse_at(start) {
+            self.pos += size;
+            return Some(FlvRecoveryEvent::Tag(tag, previous_tag_size));
+        }
+        self.pos = (start + 1..self.input.len())
+            .find(|&pos| self.parse_plausible_at(pos).is_some())
+            .unwrap_or(self.input.len());
+        Some(FlvRecoveryEvent::Skipped(start..self.pos))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for FlvRecoveringTagIter<'a> {}
+
+impl<'a> FlvFile<'a> {
+    /// Parse FLV file, recovering from the corrupted tags (see `FlvRecoveringTagIter`),
+    /// and return the skipped byte ranges (relative to the start of file) too.
+    ///
+    /// Only the file header and the first previous tag size are required to be valid,
+    /// the body starts at the `data_offset` of file header.
+    pub fn parse_recovering(
+        input: &'a [u8],
+    ) -> IResult<&'a [u8], (FlvFile<'a>, Vec<Range<usize>>)> {
+        let (body, (header, extension)) = FlvFileHeader::parse_with_extension(input)?;
+        let (tags, first_previous_tag_size) = be_u32(body)?;
+        let offset = input.len() - tags.len();
+        let mut file = FlvFile {
+            header,
+            extension,
+            body: FlvFileBody {
+                first_previous_tag_size,
+                tags: Vec::new(),
+            },
+        };
+        let mut skipped = Vec::new();
+        for event in FlvRecoveringTagIter::new(tags) {
+            match event {
+                FlvRecoveryEvent::Tag(tag, previous_tag_size) => {
+                    file.body.tags.push((tag, previous_tag_size))
+                }
+                FlvRecoveryEvent::Skipped(range) => {
+                    skipped.push(range.start + offset..range.end + offset)
+                }
+            }
+        }
+        Ok((&tags[tags.len()..], (file, skipped)))
+    }
+}
@@ -0,0 +1,454 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Validate the structure of FLV file against the spec, reporting every issue found.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use nom::{
+    number::streaming::{be_u24, be_u32, be_u8},
+    sequence::tuple,
+    Err as NomErr,
+};
+
+use crate::parse::{
+    aac_audio_packet, avc_video_packet, AACPacketType, AudioPacketType, AudioTag, AvcPacketType,
+    CodecID, FilteredTag, FlvFileHeader, FlvTagData, FlvTagType, FourCC, SoundFormat,
+    VideoPacketType, VideoTag,
+};
+
+const FLV_FILE_HEADER_SIZE: usize = 9;
+const FLV_TAG_HEADER_SIZE: usize = 11;
+const PREVIOUS_TAG_SIZE_SIZE: usize = 4;
+// Reserved (2 bits) + Filter + TagType (5 bits)
+const TAG_HEADER_RESERVED_BITS: u8 = 0b1100_0000;
+const TAG_HEADER_FILTER_BIT: u8 = 0b0010_0000;
+
+/// An issue found by `validate`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The kind of the issue.
+    pub kind: DiagnosticKind,
+    /// The byte offset (from the start of file) of the field that has the issue.
+    pub offset: u64,
+    /// The index of the tag that has the issue, `None` if the issue isn't about one tag.
+    pub tag_index: Option<usize>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.tag_index {
+            Some(index) => write!(f, "offset {}, tag {}: {}", self.offset, index, self.kind),
+            None => write!(f, "offset {}: {}", self.offset, self.kind),
+        }
+    }
+}
+
+/// The kind of the issue found by `validate`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// The file header (including `data_offset`) is invalid, nothing else is checked.
+    InvalidFileHeader,
+    /// The reserved bits of the flags of file header are set.
+    ReservedFileHeaderFlags(u8),
+    /// The `has_audio` or `has_video` flag of file header contradicts the tags present.
+    HeaderFlagMismatch {
+        /// The type of tags (audio or video).
+        tag_type: FlvTagType,
+        /// The value of the flag.
+        flag: bool,
+    },
+    /// The first previous tag size isn't 0.
+    NonZeroFirstPreviousTagSize(u32),
+    /// The previous tag size doesn't match the size of the tag.
+    PreviousTagSizeMismatch {
+        /// The size of the tag (tag header + tag data).
+        expected: u32,
+        /// The previous tag size.
+        actual: u32,
+    },
+    /// The reserved bits of tag header are set.
+    ReservedTagHeaderBits(u8),
+    /// The tag type is unknown, the tag data isn't checked.
+    UnknownTagType(u8),
+    /// The stream id of tag header isn't 0.
+    NonZeroStreamId(u32),
+    /// The timestamp is less than the timestamp of the previous tag of the same track.
+    NonMonotonicTimestamp {
+        /// The type of the track (audio or video).
+        tag_type: FlvTagType,
+        /// The timestamp of the previous tag of the same track.
+        previous: u32,
+        /// The timestamp of the tag.
+        timestamp: u32,
+    },
+    /// A coded frame precedes the first sequence header of the track.
+    MissingSequenceHeader(FlvTagType),
+    /// A sequence header is the same as the previous one, without coded frames in between.
+    DuplicateSequenceHeader(FlvTagType),
+    /// The tag data cannot be parsed.
+    InvalidTagData,
+    /// The file ends in the middle of a tag or a previous tag size.
+    Truncated,
+}
+
+impl DiagnosticKind {
+    /// The stable identifier of the kind, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::InvalidFileHeader => "invalid-file-header",
+            DiagnosticKind::ReservedFileHeaderFlags(_) => "reserved-file-header-flags",
+            DiagnosticKind::HeaderFlagMismatch { .. } => "header-flag-mismatch",
+            DiagnosticKind::NonZeroFirstPreviousTagSize(_) => "non-zero-first-previous-tag-size",
+            DiagnosticKind::PreviousTagSizeMismatch { .. } => "previous-tag-size-mismatch",
+            DiagnosticKind::ReservedTagHeaderBits(_) => "reserved-tag-header-bits",
+            DiagnosticKind::UnknownTagType(_) => "unknown-tag-type",
+            DiagnosticKind::NonZeroStreamId(_) => "non-zero-stream-id",
+            DiagnosticKind::NonMonotonicTimestamp { .. } => "non-monotonic-timestamp",
+            DiagnosticKind::MissingSequenceHeader(_) => "missing-sequence-header",
+            DiagnosticKind::DuplicateSequenceHeader(_) => "duplicate-sequence-header",
+            DiagnosticKind::InvalidTagData => "invalid-tag-data",
+            DiagnosticKind::Truncated => "truncated",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticKind::InvalidFileHeader => write!(f, "invalid file header"),
+            DiagnosticKind::ReservedFileHeaderFlags(flags) => {
+                write!(
+                    f,
+                    "reserved bits of file header flags are set: {:08b}",
+                    flags
+                )
+            }
+            DiagnosticKind::HeaderFlagMismatch { tag_type, flag } => write!(
+                f,
+                "{:?} flag of file header is {}, but {:?} tags are {}",
+                tag_type,
+                flag,
+                tag_type,
+                if *flag { "absent" } else { "present" }
+            ),
+            DiagnosticKind::NonZeroFirstPreviousTagSize(size) => {
+                write!(f, "first previous tag size is {}, expected 0", size)
+            }
+            DiagnosticKind::PreviousTagSizeMismatch { expected, actual } => {
+                write!(f, "previous tag size is {}, expected {}", actual, expected)
+            }
+            DiagnosticKind::ReservedTagHeaderBits(bits) => {
+                write!(f, "reserved bits of tag header are set: {:08b}", bits)
+            }
+            DiagnosticKind::UnknownTagType(tag_type) => write!(f, "unknown tag type {}", tag_type),
+            DiagnosticKind::NonZeroStreamId(stream_id) => {
+                write!(f, "stream id is {}, expected 0", stream_id)
+            }
+            DiagnosticKind::NonMonotonicTimestamp {
+                tag_type,
+                previous,
+                timestamp,
+            } => write!(
+                f,
+                "{:?} timestamp {} is less than the previous timestamp {}",
+                tag_type, timestamp, previous
+            ),
+            DiagnosticKind::MissingSequenceHeader(tag_type) => {
+                write!(f, "{:?} frame precedes the sequence header", tag_type)
+            }
+            DiagnosticKind::DuplicateSequenceHeader(tag_type) => {
+                write!(f, "duplicate {:?} sequence header", tag_type)
+            }
+            DiagnosticKind::InvalidTagData => write!(f, "invalid tag data"),
+            DiagnosticKind::Truncated => write!(f, "truncated file"),
+        }
+    }
+}
+
+// The packet of audio or video tag, with regard to the sequence header.
+enum Packet<'a> {
+    // The sequence header and its configuration record.
+    SequenceHeader(&'a [u8]),
+    // The coded frame that requires a sequence header.
+    Frame,
+    Other,
+}
+
+// The state of audio or video track.
+#[derive(Default)]
+struct Track<'a> {
+    first_index: Option<usize>,
+    last_timestamp: Option<u32>,
+    sequence_header: Option<&'a [u8]>,
+    // Whether coded frames follow the last sequence header.
+    has_frames: bool,
+    missing_reported: bool,
+}
+
+/// Validate the structure of FLV file against the spec, and return every issue found,
+/// in the order of the byte offsets (except the header flags, which are checked at the end).
+///
+/// The checks include the reserved bits, the previous tag sizes, the stream ids,
+/// the timestamps of each track, the header flags against the tags present,
+/// and the missing or duplicate sequence headers of AAC, AVC, HEVC and the
+/// Enhanced RTMP codecs with configuration records.
+/// The tag whose data cannot be parsed is reported and skipped, the check stops
+/// at the first tag that is truncated.
+pub fn validate(input: &[u8]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |kind, offset: usize, tag_index| {
+        diagnostics.push(Diagnostic {
+            kind,
+            offset: offset as u64,
+            tag_index,
+        })
+    };
+
+    // The body starts at the `data_offset` of file header.
+    let (body, header) = match FlvFileHeader::parse_with_extension(input) {
+        Ok((body, (header, _))) => (body, header),
+        Err(NomErr::Incomplete(_)) if FlvFileHeader::parse(input).is_ok() => {
+            report(DiagnosticKind::Truncated, FLV_FILE_HEADER_SIZE, None);
+            return diagnostics;
+        }
+        Err(_) => {
+            report(DiagnosticKind::InvalidFileHeader, 0, None);
+            return diagnostics;
+        }
+    };
+    let reserved_flags = header.reserved_flags();
+    if !reserved_flags.is_empty() {
+        report(
+            DiagnosticKind::ReservedFileHeaderFlags(reserved_flags.bits()),
+            4,
+            None,
+        );
+    }
+
+    let mut offset = input.len() - body.len();
+    match be_u32::<_, ()>(&input[offset..]) {
+        Ok((_, 0)) => {}
+        Ok((_, size)) => report(
+            DiagnosticKind::NonZeroFirstPreviousTagSize(size),
+            offset,
+            None,
+        ),
+        Err(_) => report(DiagnosticKind::Truncated, offset, None),
+    }
+    offset += PREVIOUS_TAG_SIZE_SIZE;
+
+    let mut audio = Track::default();
+    let mut video = Track::default();
+    let mut index = 0;
+    while offset < input.len() {
+        let tag_header =
+            tuple::<_, _, (), _>((be_u8, be_u24, be_u24, be_u8, be_u24))(&input[offset..]);
+        let (rest, (type_byte, data_size, timestamp, timestamp_extended, stream_id)) =
+            match tag_header {
+                Ok(tag_header) => tag_header,
+                Err(_) => {
+                    report(DiagnosticKind::Truncated, offset, Some(index));
+                    break;
+                }
+            };
+        let timestamp = (u32::from(timestamp_extended) << 24) + timestamp;
+        let data_size = data_size as usize;
+        if rest.len() < data_size + PREVIOUS_TAG_SIZE_SIZE {
+            report(DiagnosticKind::Truncated, offset, Some(index));
+            break;
+        }
+        let data = &rest[..data_size];
+        let previous_tag_size = u32::from_be_bytes([
+            rest[data_size],
+            rest[data_size + 1],
+            rest[data_size + 2],
+            rest[data_size + 3],
+        ]);
+
+        if type_byte & TAG_HEADER_RESERVED_BITS != 0 {
+            report(
+                DiagnosticKind::ReservedTagHeaderBits(type_byte & TAG_HEADER_RESERVED_BITS),
+                offset,
+                Some(index),
+            );
+        }
+        if stream_id != 0 {
+            report(
+                DiagnosticKind::NonZeroStreamId(stream_id),
+                offset + 8,
+                Some(index),
+            );
+        }
+
+        let tag_type = match type_byte & 0x1f {
+            8 => Some(FlvTagType::Audio),
+            9 => Some(FlvTagType::Video),
+            18 => Some(FlvTagType::Script),
+            other => {
+                report(DiagnosticKind::UnknownTagType(other), offset, Some(index));
+                None
+            }
+        };
+        if let Some(tag_type) = tag_type {
+            if let Some(track) = track(tag_type, &mut audio, &mut video) {
+                track.first_index.get_or_insert(index);
+                if let Some(previous) = track.last_timestamp {
+                    if timestamp < previous {
+                        report(
+                            DiagnosticKind::NonMonotonicTimestamp {
+                                tag_type,
+                                previous,
+                                timestamp,
+                            },
+                            offset + 4,
+                            Some(index),
+                        );
+                    }
+                }
+                track.last_timestamp = Some(timestamp);
+            }
+
+            // Only the encryption header of filtered (e.g. encrypted) tags can be checked.
+            let parsed = if type_byte & TAG_HEADER_FILTER_BIT != 0 {
+                FilteredTag::parse(data, tag_type, data_size)
+                    .map(|(remain, tag)| (remain, FlvTagData::Filtered(tag)))
+            } else {
+                FlvTagData::parse(data, tag_type, data_size)
+            };
+            let packet = match parsed {
+                Ok((_, FlvTagData::Audio(tag))) => audio_packet(&tag),
+                Ok((_, FlvTagData::Video(tag))) => video_packet(&tag),
+                Ok((_, FlvTagData::Script(_))) | Ok((_, FlvTagData::Filtered(_))) => Packet::Other,
+                Err(_) => {
+                    report(
+                        DiagnosticKind::InvalidTagData,
+                        offset + FLV_TAG_HEADER_SIZE,
+                        Some(index),
+                    );
+                    Packet::Other
+                }
+            };
+            if let Some(track) = track(tag_type, &mut audio, &mut video) {
+                match packet {
+                    Packet::SequenceHeader(config) => {
+                        if !track.has_frames && track.sequence_header == Some(config) {
+                            report(
+                                DiagnosticKind::DuplicateSequenceHeader(tag_type),
+                                offset,
+                                Some(index),
+                            );
+                        }
+                        track.sequence_header = Some(config);
+                        track.has_frames = false;
+                    }
+                    Packet::Frame => {
+                        if track.sequence_header.is_none() && !track.missing_reported {
+                            report(
+                                DiagnosticKind::MissingSequenceHeader(tag_type),
+                                offset,
+                                Some(index),
+                            );
+                            track.missing_reported = true;
+                        }
+                        track.has_frames = true;
+                    }
+                    Packet::Other => {}
+                }
+            }
+        }
+
+        let expected = (FLV_TAG_HEADER_SIZE + data_size) as u32;
+        if previous_tag_size != expected {
+            report(
+                DiagnosticKind::PreviousTagSizeMismatch {
+                    expected,
+                    actual: previous_tag_size,
+                },
+                offset + FLV_TAG_HEADER_SIZE + data_size,
+                Some(index),
+            );
+        }
+        offset += FLV_TAG_HEADER_SIZE + data_size + PREVIOUS_TAG_SIZE_SIZE;
+        index += 1;
+    }
+
+    for (tag_type, flag, track) in [
+        (FlvTagType::Audio, header.has_audio, &audio),
+        (FlvTagType::Video, header.has_video, &video),
+    ] {
+        if flag != track.first_index.is_some() {
+            report(
+                DiagnosticKind::HeaderFlagMismatch { tag_type, flag },
+                4,
+                track.first_index,
+            );
+        }
+    }
+    diagnostics
+}
+
+// The track of the audio or video tags.
+fn track<'t, 'a>(
+    tag_type: FlvTagType,
+    audio: &'t mut Track<'a>,
+    video: &'t mut Track<'a>,
+) -> Option<&'t mut Track<'a>> {
+    match tag_type {
+        FlvTagType::Audio => Some(audio),
+        FlvTagType::Video => Some(video),
+        FlvTagType::Script => None,
+    }
+}
+
+fn audio_packet<'a>(tag: &AudioTag<'a>) -> Packet<'a> {
+    if let Some(ex_header) = &tag.header.ex_header {
+        if !matches!(ex_header.fourcc, FourCC::AAC | FourCC::FLAC) {
+            return Packet::Other;
+        }
+        return match ex_header.packet_type {
+            AudioPacketType::SequenceStart => Packet::SequenceHeader(tag.body.data),
+            AudioPacketType::CodedFrames => Packet::Frame,
+            _ => Packet::Other,
+        };
+    }
+    if tag.header.sound_format != SoundFormat::AAC {
+        return Packet::Other;
+    }
+    match aac_audio_packet(tag.body.data, tag.body.data.len()) {
+        Ok((_, packet)) => match packet.packet_type {
+            AACPacketType::SequenceHeader => Packet::SequenceHeader(packet.aac_data),
+            AACPacketType::Raw => Packet::Frame,
+        },
+        Err(_) => Packet::Other,
+    }
+}
+
+fn video_packet<'a>(tag: &VideoTag<'a>) -> Packet<'a> {
+    if let Some(ex_header) = &tag.header.ex_header {
+        if !matches!(
+            ex_header.fourcc,
+            FourCC::AVC | FourCC::HEVC | FourCC::AV1 | FourCC::VP9
+        ) {
+            return Packet::Other;
+        }
+        return match ex_header.packet_type {
+            VideoPacketType::SequenceStart => Packet::SequenceHeader(tag.body.data),
+            VideoPacketType::CodedFrames | VideoPacketType::CodedFramesX => Packet::Frame,
+            _ => Packet::Other,
+        };
+    }
+    if !matches!(tag.header.codec_id, CodecID::AVC | CodecID::HEVC) {
+        return Packet::Other;
+    }
+    match avc_video_packet(tag.body.data, tag.body.data.len()) {
+        Ok((_, packet)) => match packet.packet_type {
+            AvcPacketType::SequenceHeader => Packet::SequenceHeader(packet.avc_data),
+            AvcPacketType::NALU => Packet::Frame,
+            _ => Packet::Other,
+        },
+        Err(_) => Packet::Other,
+    }
+}
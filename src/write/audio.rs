@@ -0,0 +1,72 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use std::io::{self, Write};
+
+use super::invalid_input;
+use crate::parse::{
+    AACAudioPacket, AACPacketType, AudioTag, AudioTagBody, AudioTagHeader, SoundFormat, SoundRate,
+    SoundSize, SoundType,
+};
+
+impl<'a> AudioTag<'a> {
+    /// Write audio tag data.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.write(writer)?;
+        self.body.write(writer)
+    }
+}
+
+impl AudioTagHeader {
+    /// Write audio tag data header.
+    ///
+    /// The unknown values are written back as is, they must fit in their bits.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let sound_format = u8::from(self.sound_format);
+        if let Some(ex_header) = &self.ex_header {
+            let packet_type = ex_header.packet_type.value();
+            if packet_type > 0x0f {
+                return Err(invalid_input("audio tag header value out of range"));
+            }
+            // SoundFormat 9 (ExHeader) + PacketType (4 bits)
+            writer.write_all(&[u8::from(SoundFormat::ExHeader) << 4 | packet_type])?;
+            return writer.write_all(&ex_header.fourcc.0);
+        }
+        let sound_rate = match self.sound_rate {
+            SoundRate::_5_5KHZ => 0,
+            SoundRate::_11KHZ => 1,
+            SoundRate::_22KHZ => 2,
+            SoundRate::_44KHZ => 3,
+        };
+        let sound_size = match self.sound_size {
+            SoundSize::_8Bit => 0,
+            SoundSize::_16Bit => 1,
+        };
+        let sound_type = match self.sound_type {
+            SoundType::Mono => 0,
+            SoundType::Stereo => 1,
+        };
+        if sound_format > 0x0f {
+            return Err(invalid_input("audio tag header value out of range"));
+        }
+        writer.write_all(&[sound_format << 4 | sound_rate << 2 | sound_size << 1 | sound_type])
+    }
+}
+
+impl<'a> AudioTagBody<'a> {
+    /// Write audio tag data body.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.data)
+    }
+}
+
+impl<'a> AACAudioPacket<'a> {
+    /// Write AAC audio packet.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let packet_type = match self.packet_type {
+            AACPacketType::SequenceHeader => 0,
+            AACPacketType::Raw => 1,
+        };
+        writer.write_all(&[packet_type])?;
+        writer.write_all(self.aac_data)
+    }
+}
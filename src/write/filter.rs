@@ -0,0 +1,54 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use std::io::{self, Write};
+
+use super::{invalid_input, script::write_string, write_be_u24};
+use crate::parse::{EncryptionTagHeader, FilterParams, FilteredTag};
+
+impl<'a> FilteredTag<'a> {
+    /// Write filtered tag data.
+    ///
+    /// The `length` of encryption tag header is computed from the written filter params.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut params = Vec::new();
+        self.filter_params.write(&mut params)?;
+        let encryption_header = EncryptionTagHeader {
+            length: params.len() as u32,
+            ..self.encryption_header
+        };
+        writer.write_all(self.header)?;
+        encryption_header.write(writer)?;
+        writer.write_all(&params)?;
+        writer.write_all(self.data)
+    }
+}
+
+impl<'a> EncryptionTagHeader<'a> {
+    /// Write encryption tag header.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[self.num_filters])?;
+        write_string(writer, self.filter_name)?;
+        write_be_u24(writer, self.length)
+    }
+}
+
+impl<'a> FilterParams<'a> {
+    /// Write filter params.
+    ///
+    /// The IV of `SE` filter params must be present if and only if the packet is encrypted.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            FilterParams::Encryption(params) => writer.write_all(&params.iv),
+            FilterParams::SelectiveEncryption(params) => {
+                // EncryptedAU (1 bit) + Reserved (7 bits)
+                writer.write_all(&[u8::from(params.encrypted_au) << 7])?;
+                match (params.encrypted_au, &params.iv) {
+                    (true, Some(iv)) => writer.write_all(iv),
+                    (false, None) => Ok(()),
+                    _ => Err(invalid_input("IV doesn't match EncryptedAU")),
+                }
+            }
+            FilterParams::Unknown(params) => writer.write_all(params),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Write the structure of the contents of FLV files, the reverse of parsing.
+// [The FLV File Format Spec](https://www.adobe.com/content/dam/acom/en/devnet/flv/video_file_format_spec_v10_1.pdf)
+
+mod audio;
+mod filter;
+mod script;
+mod video;
+
+use std::{
+    convert::TryFrom,
+    io::{self, Write},
+};
+
+use crate::parse::{FlvFile, FlvFileBody, FlvFileHeader, FlvTag, FlvTagData, FlvTagHeader};
+
+const FLV_HEADER_SIGNATURE: [u8; 3] = [0x46, 0x4c, 0x56];
+const FLV_FILE_HEADER_SIZE: usize = 9;
+const FLV_TAG_HEADER_SIZE: u32 = 11;
+const MAX_U24: u32 = 0x00ff_ffff;
+
+fn invalid_input(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn write_be_u24<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    if value > MAX_U24 {
+        return Err(invalid_input("value is too large for 3 bytes"));
+    }
+    writer.write_all(&value.to_be_bytes()[1..])
+}
+
+impl<'a> FlvFile<'a> {
+    /// Write FLV file.
+    ///
+    /// The `data_offset` of file header is computed from the extension data,
+    /// the previous tag sizes are written as is.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.write_with_extension(writer, self.extension)?;
+        self.body.write(writer)
+    }
+
+    /// Write FLV file like `write`, but with the previous tag sizes computed
+    /// from the written tags.
+    pub fn write_with_computed_sizes<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.write_with_extension(writer, self.extension)?;
+        self.body.write_with_computed_sizes(writer)
+    }
+}
+
+impl FlvFileHeader {
+    /// Write FLV file header.
+    ///
+    /// The audio and video bits of `flags` are taken from `has_audio` and `has_video`,
+    /// the `data_offset` is written as is, the extension data (if any) must follow.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let flags = (self.flags & !0b0000_0101)
+            | (u8::from(self.has_audio) << 2)
+            | u8::from(self.has_video);
+        writer.write_all(&FLV_HEADER_SIGNATURE)?;
+        writer.write_all(&[self.version, flags])?;
+        writer.write_all(&self.data_offset.to_be_bytes())
+    }
+
+    /// Write FLV file header followed by the extension data,
+    /// the `data_offset` is computed from the extension data.
+    pub fn write_with_extension<W: Write>(
+        &self,
+        writer: &mut W,
+        extension: &[u8],
+    ) -> io::Result<()> {
+        let data_offset = u32::try_from(FLV_FILE_HEADER_SIZE + extension.len())
+            .map_err(|_| invalid_input("extension data is too large"))?;
+        FlvFileHeader {
+            data_offset,
+            ..*self
+        }
+        .write(writer)?;
+        writer.write_all(extension)
+    }
+}
+
+impl<'a> FlvFileBody<'a> {
+    /// Write FLV file body, the previous tag sizes (including the first one)
+    /// are written as is.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.first_previous_tag_size.to_be_bytes())?;
+        for (tag, previous_tag_size) in &self.tags {
+            tag.write(writer)?;
+            writer.write_all(&previous_tag_size.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Write FLV file body, the previous tag sizes are computed from the written tags,
+    /// instead of using the ones stored in `tags`.
+    pub fn write_with_computed_sizes<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // The size of the first previous tag is always 0.
+        writer.write_all(&0u32.to_be_bytes())?;
+        for (tag, _) in &self.tags {
+            let tag_size = tag.write(writer)?;
+            writer.write_all(&tag_size.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FlvTag<'a> {
+    /// Write FLV tag (without the following previous tag size),
+    /// and return the size of the written tag.
+    ///
+    /// The `data_size` of tag header is computed from the written tag data.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<u32> {
+        let mut data = Vec::new();
+        self.data.write(&mut data)?;
+        if data.len() > MAX_U24 as usize {
+            return Err(invalid_input("tag data is too large"));
+        }
+
+        let header = FlvTagHeader {
+            data_size: data.len() as u32,
+            ..self.header
+        };
+        header.write(writer)?;
+        writer.write_all(&data)?;
+        Ok(FLV_TAG_HEADER_SIZE + header.data_size)
+    }
+}
+
+impl FlvTagHeader {
+    /// Write FLV tag header.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // Reserved (2 bits) + Filter (1 bit) + TagType (5 bits)
+        let flags = (self.reserved & 0b11) << 6 | u8::from(self.filter) << 5;
+        writer.write_all(&[flags | self.tag_type as u8])?;
+        write_be_u24(writer, self.data_size)?;
+        // Timestamp (lower 24 bits) + TimestampExtended (upper 8 bits)
+        write_be_u24(writer, self.timestamp & MAX_U24)?;
+        writer.write_all(&[(self.timestamp >> 24) as u8])?;
+        write_be_u24(writer, self.stream_id)
+    }
+}
+
+impl<'a> FlvTagData<'a> {
+    /// Write FLV tag data.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            FlvTagData::Audio(audio) => audio.write(writer),
+            FlvTagData::Video(video) => video.write(writer),
+            FlvTagData::Script(script) => script.write(writer),
+            FlvTagData::Filtered(filtered) => filtered.write(writer),
+        }
+    }
+}
+
+/// A FLV writer that writes the FLV file header and then FLV tags one by one,
+/// each tag is followed by its previous tag size.
+#[derive(Debug)]
+pub struct FlvWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FlvWriter<W> {
+    /// Create a FLV writer, writing the FLV file header and the first previous tag size.
+    ///
+    /// No extension data is written, so the `data_offset` of file header is written as 9.
+    pub fn new(mut writer: W, header: &FlvFileHeader) -> io::Result<Self> {
+        header.write_with_extension(&mut writer, &[])?;
+        writer.write_all(&0u32.to_be_bytes())?;
+        Ok(Self { writer })
+    }
+
+    /// Write a FLV tag and its previous tag size.
+    pub fn write_tag(&mut self, tag: &FlvTag) -> io::Result<()> {
+        let tag_size = tag.write(&mut self.writer)?;
+        self.writer.write_all(&tag_size.to_be_bytes())
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush the underlying writer and unwrap it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
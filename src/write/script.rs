@@ -0,0 +1,120 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use std::io::{self, Write};
+
+use super::invalid_input;
+use crate::parse::{ScriptDataDate, ScriptDataObjectProperty, ScriptDataValue, ScriptTag};
+
+const OBJECT_END_MARKER: [u8; 3] = [0x00, 0x00, 0x09];
+
+impl<'a> ScriptTag<'a> {
+    /// Write script tag data, the `raw` data as is if any.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if let Some(raw) = self.raw {
+            return writer.write_all(raw);
+        }
+        // ScriptTagValue.Type = 2 (String)
+        writer.write_all(&[2])?;
+        write_string(writer, self.name)?;
+        self.value.write(writer)
+    }
+}
+
+impl<'a> ScriptDataValue<'a> {
+    /// Write script tag data value, encoded as AMF0.
+    ///
+    /// A `String` value longer than 65535 bytes is written as a `LongString` value.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            ScriptDataValue::Number(number) => {
+                writer.write_all(&[0])?;
+                writer.write_all(&number.to_be_bytes())
+            }
+            ScriptDataValue::Boolean(boolean) => writer.write_all(&[1, u8::from(*boolean)]),
+            ScriptDataValue::String(string) if string.len() > u16::MAX as usize => {
+                ScriptDataValue::LongString(string).write(writer)
+            }
+            ScriptDataValue::String(string) => {
+                writer.write_all(&[2])?;
+                write_string(writer, string)
+            }
+            ScriptDataValue::Object(properties) => {
+                writer.write_all(&[3])?;
+                write_object(writer, properties)
+            }
+            ScriptDataValue::MovieClip => writer.write_all(&[4]),
+            ScriptDataValue::Null => writer.write_all(&[5]),
+            ScriptDataValue::Undefined => writer.write_all(&[6]),
+            ScriptDataValue::Reference(reference) => {
+                writer.write_all(&[7])?;
+                writer.write_all(&reference.to_be_bytes())
+            }
+            ScriptDataValue::ECMAArray(properties) => {
+                writer.write_all(&[8])?;
+                // ECMA array length
+                writer.write_all(&(properties.len() as u32).to_be_bytes())?;
+                write_object(writer, properties)
+            }
+            ScriptDataValue::StrictArray(values) => {
+                writer.write_all(&[10])?;
+                // Strict array length
+                writer.write_all(&(values.len() as u32).to_be_bytes())?;
+                for value in values {
+                    value.write(writer)?;
+                }
+                Ok(())
+            }
+            ScriptDataValue::Date(date) => {
+                writer.write_all(&[11])?;
+                date.write(writer)
+            }
+            ScriptDataValue::LongString(string) => {
+                if string.len() > u32::MAX as usize {
+                    return Err(invalid_input("long string is too long"));
+                }
+                writer.write_all(&[12])?;
+                writer.write_all(&(string.len() as u32).to_be_bytes())?;
+                writer.write_all(string.as_bytes())
+            }
+            ScriptDataValue::AVMPlus(_) => Err(invalid_input("AMF3 values cannot be written")),
+        }
+    }
+}
+
+impl<'a> ScriptDataObjectProperty<'a> {
+    /// Write script tag data object property.
+    ///
+    /// The property name cannot be longer than 65535 bytes.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_string(writer, self.name)?;
+        self.value.write(writer)
+    }
+}
+
+impl ScriptDataDate {
+    /// Write script tag data date value (without the type marker).
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // Number of milliseconds since UNIX_EPOCH.
+        writer.write_all(&self.date_time.to_be_bytes())?;
+        // Local time offset in minutes from UTC.
+        writer.write_all(&self.local_date_time_offset.to_be_bytes())
+    }
+}
+
+pub(super) fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    if string.len() > u16::MAX as usize {
+        return Err(invalid_input("string is too long"));
+    }
+    writer.write_all(&(string.len() as u16).to_be_bytes())?;
+    writer.write_all(string.as_bytes())
+}
+
+fn write_object<W: Write>(
+    writer: &mut W,
+    properties: &[ScriptDataObjectProperty],
+) -> io::Result<()> {
+    for property in properties {
+        property.write(writer)?;
+    }
+    writer.write_all(&OBJECT_END_MARKER)
+}
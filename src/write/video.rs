@@ -0,0 +1,63 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use std::io::{self, Write};
+
+use super::{invalid_input, write_be_u24, MAX_U24};
+use crate::parse::{AvcPacketType, AvcVideoPacket, VideoTag, VideoTagBody, VideoTagHeader};
+
+impl<'a> VideoTag<'a> {
+    /// Write video tag data.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.write(writer)?;
+        self.body.write(writer)
+    }
+}
+
+impl VideoTagHeader {
+    /// Write video tag data header.
+    ///
+    /// The unknown values are written back as is, they must fit in their bits.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let frame_type = self.frame_type.value();
+        if let Some(ex_header) = &self.ex_header {
+            let packet_type = ex_header.packet_type.value();
+            if frame_type > 0x07 || packet_type > 0x0f {
+                return Err(invalid_input("video tag header value out of range"));
+            }
+            // IsExHeader + FrameType (3 bits) + PacketType (4 bits)
+            writer.write_all(&[0x80 | frame_type << 4 | packet_type])?;
+            return writer.write_all(&ex_header.fourcc.0);
+        }
+        let codec_id = self
+            .codec_id
+            .value()
+            .ok_or_else(|| invalid_input("missing extended video tag header"))?;
+        if frame_type > 0x0f || codec_id > 0x0f {
+            return Err(invalid_input("video tag header value out of range"));
+        }
+        writer.write_all(&[frame_type << 4 | codec_id])
+    }
+}
+
+impl<'a> VideoTagBody<'a> {
+    /// Write video tag data body.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.data)
+    }
+}
+
+impl<'a> AvcVideoPacket<'a> {
+    /// Write AVC video packet.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let packet_type = match self.packet_type {
+            AvcPacketType::SequenceHeader => 0,
+            AvcPacketType::NALU => 1,
+            AvcPacketType::EndOfSequence => 2,
+            AvcPacketType::Unknown(value) => value,
+        };
+        writer.write_all(&[packet_type])?;
+        // SI24, two's complement.
+        write_be_u24(writer, self.composition_time as u32 & MAX_U24)?;
+        writer.write_all(self.avc_data)
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+#[test]
+fn test_aac_lc_audio_specific_config() {
+    let (remain, config) = AudioSpecificConfig::parse(&[0x12, 0x10]).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(config.audio_object_type, 2);
+    assert_eq!(config.sampling_frequency_index, 4);
+    assert_eq!(config.sampling_frequency, 44100);
+    assert_eq!(config.channel_configuration, 2);
+    assert_eq!(config.channels(), Some(2));
+    assert_eq!(config.sbr_present, None);
+    assert_eq!(config.output_sampling_frequency(), 44100);
+    assert_eq!(config.implicit_output_sampling_frequency(), 44100);
+    let ga = config.ga_specific_config.unwrap();
+    assert!(!ga.frame_length_flag);
+    assert_eq!(ga.core_coder_delay, None);
+    assert!(!ga.extension_flag);
+}
+
+#[test]
+fn test_audio_specific_config_of_aac_sequence_header() {
+    let tag = flv_tag(AUDIO_TAG_TYPE, 0, AAC_SEQUENCE_HEADER);
+    let (_, tag) = FlvTag::parse(&tag).unwrap();
+    let audio = match tag.data {
+        FlvTagData::Audio(audio) => audio,
+        _ => panic!("expected audio tag"),
+    };
+    let (_, packet) = aac_audio_packet(audio.body.data, audio.body.data.len()).unwrap();
+    let config = packet.audio_specific_config().unwrap();
+    assert_eq!(config.audio_object_type, 2);
+    assert_eq!(config.sampling_frequency, 44100);
+
+    let (_, raw) = aac_audio_packet(&AAC_RAW_FRAME[1..], AAC_RAW_FRAME.len() - 1).unwrap();
+    assert_eq!(raw.audio_specific_config(), None);
+}
+
+#[test]
+fn test_explicit_hierarchical_sbr_signalling() {
+    // HE-AAC: SBR, 22050 Hz, stereo, 44100 Hz extension, AAC LC core.
+    let (_, config) = AudioSpecificConfig::parse(&[0x2b, 0x92, 0x08, 0x00]).unwrap();
+    assert_eq!(config.audio_object_type, 2);
+    assert_eq!(config.sampling_frequency, 22050);
+    assert_eq!(config.extension_audio_object_type, Some(AOT_SBR));
+    assert_eq!(config.extension_sampling_frequency, Some(44100));
+    assert_eq!(config.sbr_present, Some(true));
+    assert_eq!(config.ps_present, None);
+    assert_eq!(config.output_sampling_frequency(), 44100);
+    assert_eq!(config.implicit_output_sampling_frequency(), 44100);
+}
+
+#[test]
+fn test_backward_compatible_sbr_ps_signalling() {
+    // AAC LC, 24000 Hz, stereo, then the sync extensions of SBR (48000 Hz) and PS.
+    let data = [0x13, 0x10, 0x56, 0xe5, 0x9d, 0x48, 0x80];
+    let (_, config) = AudioSpecificConfig::parse(&data).unwrap();
+    assert_eq!(config.audio_object_type, 2);
+    assert_eq!(config.sampling_frequency, 24000);
+    assert_eq!(config.extension_audio_object_type, Some(AOT_SBR));
+    assert_eq!(config.sbr_present, Some(true));
+    assert_eq!(config.extension_sampling_frequency, Some(48000));
+    assert_eq!(config.ps_present, Some(true));
+    assert_eq!(config.output_sampling_frequency(), 48000);
+}
+
+#[test]
+fn test_implicit_sbr_signalling() {
+    // AAC LC, 22050 Hz, mono, without sync extension.
+    let (_, config) = AudioSpecificConfig::parse(&[0x13, 0x88]).unwrap();
+    assert_eq!(config.sampling_frequency, 22050);
+    assert_eq!(config.sbr_present, None);
+    assert_eq!(config.channels(), Some(1));
+    assert_eq!(config.output_sampling_frequency(), 22050);
+    assert_eq!(config.implicit_output_sampling_frequency(), 44100);
+}
+
+#[test]
+fn test_escaped_audio_object_type_and_explicit_frequency() {
+    // The audio object type 42 (USAC), 48000 Hz, stereo.
+    let (_, config) = AudioSpecificConfig::parse(&[0xf9, 0x46, 0x40]).unwrap();
+    assert_eq!(config.audio_object_type, 42);
+    assert_eq!(config.sampling_frequency, 48000);
+    assert_eq!(config.channel_configuration, 2);
+    assert_eq!(config.ga_specific_config, None);
+
+    // AAC LC, explicit 44100 Hz, mono.
+    let (_, config) = AudioSpecificConfig::parse(&[0x17, 0x80, 0x56, 0x22, 0x08]).unwrap();
+    assert_eq!(config.sampling_frequency_index, 15);
+    assert_eq!(config.sampling_frequency, 44100);
+    assert_eq!(config.channels(), Some(1));
+}
+
+#[test]
+fn test_program_config_element() {
+    // AAC LC, 48000 Hz, channels defined by the program config element:
+    // front SCE + front CPE, back CPE, LFE.
+    let data = [0x11, 0x80, 0x04, 0xc8, 0x05, 0x00, 0x01, 0x19, 0x00, 0x00];
+    let (remain, config) = AudioSpecificConfig::parse(&data).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(config.channel_configuration, 0);
+    let pce = config
+        .ga_specific_config
+        .and_then(|ga| ga.program_config_element)
+        .unwrap();
+    assert_eq!(pce.front_channels, 3);
+    assert_eq!(pce.side_channels, 0);
+    assert_eq!(pce.back_channels, 2);
+    assert_eq!(pce.lfe_channels, 1);
+    assert_eq!(pce.channels(), 6);
+}
+
+#[test]
+fn test_invalid_audio_specific_config() {
+    // The reserved sampling frequency index 13.
+    assert!(AudioSpecificConfig::parse(&[0x16, 0x90]).is_err());
+    assert!(matches!(
+        AudioSpecificConfig::parse(&[0x12]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
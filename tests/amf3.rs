@@ -0,0 +1,291 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use flvparse::*;
+
+fn decode(input: &[u8]) -> Amf3Value<'_> {
+    let (remain, value) = Amf3Value::parse(input).unwrap();
+    assert!(remain.is_empty());
+    value
+}
+
+#[test]
+fn test_amf3_u29() {
+    assert_eq!(amf3_u29(&[0x00]), Ok((&b""[..], 0)));
+    assert_eq!(amf3_u29(&[0x7f]), Ok((&b""[..], 0x7f)));
+    assert_eq!(amf3_u29(&[0x81, 0x00]), Ok((&b""[..], 0x80)));
+    assert_eq!(amf3_u29(&[0xff, 0x7f]), Ok((&b""[..], 0x3fff)));
+    assert_eq!(amf3_u29(&[0x81, 0x80, 0x00]), Ok((&b""[..], 0x4000)));
+    assert_eq!(
+        amf3_u29(&[0xbf, 0xff, 0xff, 0xff]),
+        Ok((&b""[..], 0x0fff_ffff))
+    );
+    assert_eq!(
+        amf3_u29(&[0xff, 0xff, 0xff, 0xff]),
+        Ok((&b""[..], 0x1fff_ffff))
+    );
+    assert!(amf3_u29(&[0x81, 0x80]).is_err());
+}
+
+#[test]
+fn test_amf3_simple_values() {
+    assert_eq!(decode(&[0x00]), Amf3Value::Undefined);
+    assert_eq!(decode(&[0x01]), Amf3Value::Null);
+    assert_eq!(decode(&[0x02]), Amf3Value::Boolean(false));
+    assert_eq!(decode(&[0x03]), Amf3Value::Boolean(true));
+    assert_eq!(decode(&[0x04, 0x05]), Amf3Value::Integer(5));
+    assert_eq!(
+        decode(&[0x04, 0xbf, 0xff, 0xff, 0xff]),
+        Amf3Value::Integer(0x0fff_ffff)
+    );
+    assert_eq!(
+        decode(&[0x04, 0xff, 0xff, 0xff, 0xff]),
+        Amf3Value::Integer(-1)
+    );
+    assert_eq!(
+        decode(&[0x04, 0xc0, 0x80, 0x80, 0x00]),
+        Amf3Value::Integer(-0x1000_0000)
+    );
+
+    let mut double = vec![0x05];
+    double.extend_from_slice(&2.5f64.to_be_bytes());
+    assert_eq!(decode(&double), Amf3Value::Double(2.5));
+
+    let mut date = vec![0x08, 0x01];
+    date.extend_from_slice(&1_600_000_000_000f64.to_be_bytes());
+    assert_eq!(decode(&date), Amf3Value::Date(1_600_000_000_000.0));
+
+    assert_eq!(
+        decode(&[0x06, 0x07, b'f', b'l', b'v']),
+        Amf3Value::String("flv")
+    );
+    assert_eq!(decode(&[0x06, 0x01]), Amf3Value::String(""));
+    assert_eq!(decode(&[0x0b, 0x05, b'<', b'>']), Amf3Value::Xml("<>"));
+    assert_eq!(
+        decode(&[0x07, 0x05, b'<', b'>']),
+        Amf3Value::XmlDocument("<>")
+    );
+    assert_eq!(
+        decode(&[0x0c, 0x07, 1, 2, 3]),
+        Amf3Value::ByteArray(&[1, 2, 3])
+    );
+}
+
+#[test]
+fn test_amf3_string_reference() {
+    let input = [
+        0x09, 0x07, 0x01, // dense array with 3 items, no associative items
+        0x06, 0x07, b'a', b'b', b'c', // inline string
+        0x06, 0x00, // string reference 0
+        0x04, 0x05, // integer
+    ];
+    assert_eq!(
+        decode(&input),
+        Amf3Value::Array(Amf3Array {
+            assoc: vec![],
+            dense: vec![
+                Amf3Value::String("abc"),
+                Amf3Value::String("abc"),
+                Amf3Value::Integer(5)
+            ],
+        })
+    );
+    // unknown string reference
+    assert!(Amf3Value::parse(&[0x06, 0x02]).is_err());
+}
+
+#[test]
+fn test_amf3_object_and_traits_reference() {
+    let input = [
+        0x09, 0x07, 0x01, // dense array with 3 items, no associative items
+        // object with inline traits: class `Pt`, sealed names `x` and `y`
+        0x0a, 0x23, 0x05, b'P', b't', 0x03, b'x', 0x03, b'y', 0x04, 0x01, 0x04, 0x02,
+        // object with traits reference 0
+        0x0a, 0x01, 0x04, 0x03, 0x04, 0x04, // object reference 1 (0 is the array)
+        0x0a, 0x02,
+    ];
+    let traits = Amf3Traits {
+        class_name: "Pt",
+        dynamic: false,
+        sealed_names: vec!["x", "y"],
+    };
+    let point = |x, y| {
+        Amf3Value::Object(Amf3Object {
+            traits: traits.clone(),
+            sealed: vec![
+                Amf3Property {
+                    name: "x",
+                    value: Amf3Value::Integer(x),
+                },
+                Amf3Property {
+                    name: "y",
+                    value: Amf3Value::Integer(y),
+                },
+            ],
+            dynamic: vec![],
+        })
+    };
+    assert_eq!(
+        decode(&input),
+        Amf3Value::Array(Amf3Array {
+            assoc: vec![],
+            dense: vec![point(1, 2), point(3, 4), Amf3Value::Reference(1)],
+        })
+    );
+}
+
+#[test]
+fn test_amf3_dynamic_object_and_assoc_array() {
+    let input = [
+        0x0a, 0x0b, 0x01, // anonymous dynamic object
+        0x0b, b'w', b'i', b'd', b't', b'h', 0x04, 0x81, 0x00, // width = 128
+        0x09, b'l', b'i', b's', b't', // list =
+        0x09, 0x03, 0x03, b'k', 0x03, 0x01, 0x01, // [k = true, null]
+        0x01, // end of dynamic members
+    ];
+    assert_eq!(
+        decode(&input),
+        Amf3Value::Object(Amf3Object {
+            traits: Amf3Traits {
+                class_name: "",
+                dynamic: true,
+                sealed_names: vec![],
+            },
+            sealed: vec![],
+            dynamic: vec![
+                Amf3Property {
+                    name: "width",
+                    value: Amf3Value::Integer(128),
+                },
+                Amf3Property {
+                    name: "list",
+                    value: Amf3Value::Array(Amf3Array {
+                        assoc: vec![Amf3Property {
+                            name: "k",
+                            value: Amf3Value::Boolean(true),
+                        }],
+                        dense: vec![Amf3Value::Null],
+                    }),
+                },
+            ],
+        })
+    );
+
+    // cyclic object reference
+    assert_eq!(
+        decode(&[0x0a, 0x0b, 0x01, 0x03, b'a', 0x0a, 0x00, 0x01]),
+        Amf3Value::Object(Amf3Object {
+            traits: Amf3Traits {
+                class_name: "",
+                dynamic: true,
+                sealed_names: vec![],
+            },
+            sealed: vec![],
+            dynamic: vec![Amf3Property {
+                name: "a",
+                value: Amf3Value::Reference(0),
+            }],
+        })
+    );
+    // unknown object reference
+    assert!(Amf3Value::parse(&[0x0a, 0x0b, 0x01, 0x03, b'a', 0x0a, 0x02, 0x01]).is_err());
+    // externalizable object
+    assert!(Amf3Value::parse(&[0x0a, 0x07, 0x03, b'C']).is_err());
+}
+
+#[test]
+fn test_amf3_vectors_and_dictionary() {
+    assert_eq!(
+        decode(&[0x0d, 0x05, 0x00, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff]),
+        Amf3Value::VectorInt(Amf3Vector {
+            fixed: false,
+            items: vec![1, -1],
+        })
+    );
+    assert_eq!(
+        decode(&[0x0e, 0x03, 0x01, 0xff, 0xff, 0xff, 0xff]),
+        Amf3Value::VectorUInt(Amf3Vector {
+            fixed: true,
+            items: vec![u32::MAX],
+        })
+    );
+    let mut doubles = vec![0x0f, 0x03, 0x00];
+    doubles.extend_from_slice(&0.5f64.to_be_bytes());
+    assert_eq!(
+        decode(&doubles),
+        Amf3Value::VectorDouble(Amf3Vector {
+            fixed: false,
+            items: vec![0.5],
+        })
+    );
+    assert_eq!(
+        decode(&[0x10, 0x03, 0x01, 0x03, b'*', 0x01]),
+        Amf3Value::VectorObject(Amf3ObjectVector {
+            fixed: true,
+            type_name: "*",
+            items: vec![Amf3Value::Null],
+        })
+    );
+    assert_eq!(
+        decode(&[0x11, 0x03, 0x00, 0x06, 0x03, b'k', 0x04, 0x07]),
+        Amf3Value::Dictionary(Amf3Dictionary {
+            weak_keys: false,
+            entries: vec![(Amf3Value::String("k"), Amf3Value::Integer(7))],
+        })
+    );
+}
+
+#[test]
+fn test_amf3_reference_chain() {
+    // A dense array of 60 arrays, each holds two references to the previous one.
+    let mut input = vec![0x09, 60 << 1 | 1, 0x01, 0x09, 0x01, 0x01];
+    for index in 2..=60u8 {
+        input.extend_from_slice(&[0x09, 0x05, 0x01]);
+        input.extend_from_slice(&[0x09, (index - 1) << 1, 0x09, (index - 1) << 1]);
+    }
+    let array = match decode(&input) {
+        Amf3Value::Array(array) => array,
+        value => panic!("expected array, got {:?}", value),
+    };
+    assert_eq!(array.dense.len(), 60);
+    assert_eq!(
+        array.dense[59],
+        Amf3Value::Array(Amf3Array {
+            assoc: vec![],
+            dense: vec![Amf3Value::Reference(59), Amf3Value::Reference(59)],
+        })
+    );
+}
+
+#[test]
+fn test_amf3_max_depth() {
+    let nested = |depth| {
+        let mut input = [0x09, 0x03, 0x01].repeat(depth);
+        input.push(0x01);
+        input
+    };
+    assert!(Amf3Value::parse(&nested(AMF3_MAX_DEPTH - 1)).is_ok());
+    assert_eq!(
+        Amf3Value::parse(&nested(AMF3_MAX_DEPTH)).map(|_| ()),
+        Err(NomErr::Error(Error::new(&[0x01][..], ErrorKind::TooLarge)))
+    );
+    assert!(Amf3Value::parse(&nested(10_000)).is_err());
+}
+
+#[test]
+fn test_script_data_avm_plus() {
+    assert_eq!(
+        ScriptDataValue::parse(&[0x11, 0x04, 0x05]),
+        Ok((&b""[..], ScriptDataValue::AVMPlus(Amf3Value::Integer(5))))
+    );
+
+    let mut input = vec![0x02, 0x00, 0x0a];
+    input.extend_from_slice(b"onMetaData");
+    input.extend_from_slice(&[0x11, 0x0a, 0x0b, 0x01, 0x03, b'a', 0x03, 0x01]);
+    let (remain, tag) = ScriptTag::parse(&input, input.len()).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(tag.name, "onMetaData");
+    assert!(matches!(
+        tag.value,
+        ScriptDataValue::AVMPlus(Amf3Value::Object(_))
+    ));
+}
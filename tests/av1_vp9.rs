@@ -0,0 +1,128 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use flvparse::*;
+
+// Main profile, level 4.0, main tier, 10 bits, 4:2:0, no initial presentation delay.
+const AV1C: &[u8] = &[
+    0x81, 0x08, 0x4c, 0x00, // av1C
+    0x0a, 0x0b, 0x00, 0x00, 0x00, 0x24, 0xc6, 0xab, 0xdf, 0x3e, 0xfe, 0x24, 0x04, // OBU
+];
+
+// Profile 0, level 3.1, 8 bits, 4:2:0 colocated, limited range, BT.709.
+const VPCC: &[u8] = &[
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x82, 0x01, 0x01, 0x01, 0x00, 0x00,
+];
+
+#[test]
+fn test_av1_codec_configuration_record() {
+    let (remain, record) = Av1CodecConfigurationRecord::parse(AV1C).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(record.version, 1);
+    assert_eq!(record.seq_profile, 0);
+    assert_eq!(record.seq_level_idx_0, 8);
+    assert!(!record.seq_tier_0);
+    assert!(record.high_bitdepth);
+    assert!(!record.twelve_bit);
+    assert!(!record.monochrome);
+    assert!(record.chroma_subsampling_x);
+    assert!(record.chroma_subsampling_y);
+    assert_eq!(record.chroma_sample_position, 0);
+    assert_eq!(record.initial_presentation_delay_minus_one, None);
+    assert_eq!(record.config_obus, &AV1C[4..]);
+    assert_eq!(record.bit_depth(), 10);
+
+    // initial_presentation_delay_present + initial_presentation_delay_minus_one 3
+    let (_, record) = Av1CodecConfigurationRecord::parse(&[0x81, 0x08, 0x4c, 0x13]).unwrap();
+    assert_eq!(record.initial_presentation_delay_minus_one, Some(3));
+    assert!(record.config_obus.is_empty());
+
+    // The marker must be set.
+    assert!(matches!(
+        Av1CodecConfigurationRecord::parse(&[0x01, 0x08, 0x4c, 0x00]),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        Av1CodecConfigurationRecord::parse(&AV1C[..3]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_vp_codec_configuration_record() {
+    let (remain, record) = VpCodecConfigurationRecord::parse(VPCC).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(record.version, 1);
+    assert_eq!(record.flags, 0);
+    assert_eq!(record.profile, 0);
+    assert_eq!(record.level, 31);
+    assert_eq!(record.bit_depth, 8);
+    assert_eq!(record.chroma_subsampling, 1);
+    assert!(!record.video_full_range_flag);
+    assert_eq!(record.colour_primaries, 1);
+    assert_eq!(record.transfer_characteristics, 1);
+    assert_eq!(record.matrix_coefficients, 1);
+    assert!(record.codec_initialization_data.is_empty());
+
+    let mut data = VPCC.to_vec();
+    data[0] = 0;
+    assert!(matches!(
+        VpCodecConfigurationRecord::parse(&data),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        VpCodecConfigurationRecord::parse(&VPCC[..VPCC.len() - 1]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_video_codec_configuration() {
+    // IsExHeader + key frame + SequenceStart, av01
+    let mut data = vec![0x90, b'a', b'v', b'0', b'1'];
+    data.extend_from_slice(AV1C);
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    let record = tag.av1_codec_configuration_record().unwrap();
+    assert_eq!(record.seq_level_idx_0, 8);
+    assert_eq!(tag.vp9_codec_configuration_record(), None);
+    assert_eq!(tag.hevc_decoder_configuration_record(), None);
+    assert_eq!(
+        tag.codec_configuration(),
+        Some(VideoCodecConfiguration::Av1(record))
+    );
+
+    // IsExHeader + key frame + SequenceStart, vp09
+    let mut data = vec![0x90, b'v', b'p', b'0', b'9'];
+    data.extend_from_slice(VPCC);
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    let record = tag.vp9_codec_configuration_record().unwrap();
+    assert_eq!(record.level, 31);
+    assert_eq!(tag.av1_codec_configuration_record(), None);
+
+    // IsExHeader + key frame + CodedFrames, vp09
+    data[0] = 0x91;
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    assert_eq!(tag.vp9_codec_configuration_record(), None);
+    assert_eq!(tag.codec_configuration(), None);
+
+    // IsExHeader + key frame + SequenceStart, avc1
+    let avcc = [
+        0x01, 0x64, 0x00, 0x1f, 0xff, 0xe1, 0x00, 0x04, 0x67, 0x64, 0x00, 0x1f, 0x01, 0x00, 0x02,
+        0x68, 0xee,
+    ];
+    let mut data = vec![0x90, b'a', b'v', b'c', b'1'];
+    data.extend_from_slice(&avcc);
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    match tag.codec_configuration() {
+        Some(VideoCodecConfiguration::Avc(record)) => assert_eq!(record.nal_length_size(), 4),
+        other => panic!("unexpected codec configuration: {:?}", other),
+    }
+
+    // key frame + CodecID 7, sequence header
+    let mut data = vec![0x17, 0x00, 0x00, 0x00, 0x00];
+    data.extend_from_slice(&avcc);
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    assert!(matches!(
+        tag.codec_configuration(),
+        Some(VideoCodecConfiguration::Avc(_))
+    ));
+}
@@ -0,0 +1,229 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+#[test]
+fn test_avc_decoder_configuration_record() {
+    let (remain, record) = AvcDecoderConfigurationRecord::parse(&AVC_SEQUENCE_HEADER[5..]).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(record.configuration_version, 1);
+    assert_eq!(record.profile_indication, 100);
+    assert_eq!(record.profile_compatibility, 0);
+    assert_eq!(record.level_indication, 31);
+    assert_eq!(record.nal_length_size(), 4);
+    assert_eq!(
+        record.sequence_parameter_sets,
+        vec![&[0x67, 0x64, 0x00, 0x1f][..]]
+    );
+    assert_eq!(
+        record.picture_parameter_sets,
+        vec![&[0x68, 0xee, 0x3c, 0x80][..]]
+    );
+    assert_eq!(record.high_profile_extension, None);
+}
+
+#[test]
+fn test_avc_decoder_configuration_record_with_high_profile_extension() {
+    let mut data = AVC_SEQUENCE_HEADER[5..].to_vec();
+    // 4:2:2 chroma, 10 bits luma and chroma, one SPS extension.
+    data.extend_from_slice(&[0xfe, 0xfa, 0xfa, 0x01, 0x00, 0x02, 0x6d, 0x00]);
+    let (remain, record) = AvcDecoderConfigurationRecord::parse(&data).unwrap();
+    assert!(remain.is_empty());
+    let extension = record.high_profile_extension.unwrap();
+    assert_eq!(extension.chroma_format, 2);
+    assert_eq!(extension.bit_depth_luma_minus8, 2);
+    assert_eq!(extension.bit_depth_chroma_minus8, 2);
+    assert_eq!(
+        extension.sequence_parameter_set_extensions,
+        vec![&[0x6d, 0x00][..]]
+    );
+}
+
+#[test]
+fn test_avc_decoder_configuration_record_of_sequence_header() {
+    let (_, packet) =
+        avc_video_packet(&AVC_SEQUENCE_HEADER[1..], AVC_SEQUENCE_HEADER.len() - 1).unwrap();
+    let record = packet.decoder_configuration_record().unwrap();
+    assert_eq!(record.profile_indication, 100);
+    assert_eq!(record.sequence_parameter_sets.len(), 1);
+
+    let (_, packet) = avc_video_packet(&AVC_KEY_FRAME[1..], AVC_KEY_FRAME.len() - 1).unwrap();
+    assert_eq!(packet.decoder_configuration_record(), None);
+}
+
+#[test]
+fn test_invalid_avc_decoder_configuration_record() {
+    let mut data = AVC_SEQUENCE_HEADER[5..].to_vec();
+    data[0] = 2;
+    assert!(AvcDecoderConfigurationRecord::parse(&data).is_err());
+    // The PPS is truncated.
+    let data = &AVC_SEQUENCE_HEADER[5..AVC_SEQUENCE_HEADER.len() - 1];
+    assert!(matches!(
+        AvcDecoderConfigurationRecord::parse(data),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+// High profile, level 4.0, 1920x1088 with 8 lines cropped, 30000/1001 fps, 1:1 SAR.
+const HIGH_PROFILE_SPS: &[u8] = &[
+    0x67, 0x64, 0x00, 0x28, 0xac, 0xd9, 0x40, 0x78, 0x02, 0x27, 0xe5, 0xc0, 0x44, 0x00, 0x00, 0x0f,
+    0xa4, 0x00, 0x03, 0xa9, 0x82, 0x10,
+];
+
+// Constrained baseline profile, level 3.0, interlaced 720x576, 25 fps, 16:11 SAR,
+// with emulation prevention bytes.
+const INTERLACED_SPS: &[u8] = &[
+    0x67, 0x42, 0xc0, 0x1e, 0x54, 0x29, 0x9a, 0x68, 0x0b, 0x42, 0x45, 0xff, 0x80, 0x08, 0x00, 0x05,
+    0x88, 0x00, 0x00, 0x03, 0x00, 0x08, 0x00, 0x00, 0x03, 0x01, 0x90, 0x40,
+];
+
+#[test]
+fn test_avc_sequence_parameter_set() {
+    let (remain, sps) = AvcSequenceParameterSet::parse(HIGH_PROFILE_SPS).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(sps.profile_idc, 100);
+    assert_eq!(sps.level_idc, 40);
+    assert_eq!(sps.chroma_format_idc, 1);
+    assert_eq!(sps.bit_depth_luma(), 8);
+    assert_eq!(sps.bit_depth_chroma(), 8);
+    assert!(sps.frame_mbs_only_flag);
+    assert_eq!(
+        sps.frame_cropping,
+        Some(AvcFrameCropping {
+            bottom: 4,
+            ..Default::default()
+        })
+    );
+    assert_eq!(sps.width(), Some(1920));
+    assert_eq!(sps.height(), Some(1080));
+    assert_eq!(sps.sample_aspect_ratio(), Some((1, 1)));
+    let timing = sps.vui_parameters.unwrap().timing_info.unwrap();
+    assert_eq!(timing.num_units_in_tick, 1001);
+    assert_eq!(timing.time_scale, 60000);
+    assert!(timing.fixed_frame_rate_flag);
+    assert!((sps.frame_rate().unwrap() - 29.97).abs() < 0.001);
+}
+
+#[test]
+fn test_interlaced_avc_sequence_parameter_set() {
+    let (_, sps) = AvcSequenceParameterSet::parse(INTERLACED_SPS).unwrap();
+    assert_eq!(sps.profile_idc, 66);
+    assert_eq!(sps.constraint_set_flags, 0xc0);
+    assert_eq!(sps.level_idc, 30);
+    assert_eq!(sps.seq_parameter_set_id, 1);
+    assert!(!sps.frame_mbs_only_flag);
+    assert_eq!(sps.frame_cropping, None);
+    assert_eq!(sps.width(), Some(720));
+    assert_eq!(sps.height(), Some(576));
+    assert_eq!(sps.sample_aspect_ratio(), Some((16, 11)));
+    assert_eq!(sps.frame_rate(), Some(25.0));
+}
+
+#[test]
+fn test_sequence_parameter_set_of_avc_decoder_configuration_record() {
+    let mut data = AVC_SEQUENCE_HEADER[5..11].to_vec();
+    data.extend_from_slice(&(HIGH_PROFILE_SPS.len() as u16).to_be_bytes());
+    data.extend_from_slice(HIGH_PROFILE_SPS);
+    data.push(0);
+    let (_, record) = AvcDecoderConfigurationRecord::parse(&data).unwrap();
+    let sps = record.sequence_parameter_set().unwrap();
+    assert_eq!((sps.width(), sps.height()), (Some(1920), Some(1080)));
+}
+
+#[test]
+fn test_invalid_avc_sequence_parameter_set() {
+    // Not a SPS NAL unit.
+    assert!(matches!(
+        AvcSequenceParameterSet::parse(&[0x68, 0xee, 0x3c, 0x80]),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        AvcSequenceParameterSet::parse(&HIGH_PROFILE_SPS[..8]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+// Baseline profile SPS with the given size (in macroblocks minus 1) and frame cropping.
+fn baseline_sps(width: u32, height: u32, cropping: Option<[u32; 4]>) -> Vec<u8> {
+    // NAL unit header, profile 66, constraint flags, level 30
+    let mut sps = BitWriter::default().bits(0x6742_001e, 32);
+    // seq_parameter_set_id, log2_max_frame_num_minus4, pic_order_cnt_type = 2,
+    // max_num_ref_frames, gaps_in_frame_num_value_allowed_flag
+    sps = sps.ue(0).ue(0).ue(2).ue(1).bits(0, 1);
+    // pic_width_in_mbs_minus1, pic_height_in_map_units_minus1,
+    // frame_mbs_only_flag, direct_8x8_inference_flag
+    sps = sps.ue(width).ue(height).bits(0b11, 2);
+    sps = match cropping {
+        Some([left, right, top, bottom]) => sps.bits(1, 1).ue(left).ue(right).ue(top).ue(bottom),
+        None => sps.bits(0, 1),
+    };
+    // vui_parameters_present_flag
+    sps.bits(0, 1).finish()
+}
+
+#[test]
+fn test_avc_sequence_parameter_set_dimensions_overflow() {
+    let sps = baseline_sps(119, 67, Some([0, 0, 0, 4]));
+    let (_, sps) = AvcSequenceParameterSet::parse(&sps).unwrap();
+    assert_eq!((sps.width(), sps.height()), (Some(1920), Some(1080)));
+
+    // The size overflows.
+    let sps = baseline_sps(u32::MAX - 1, u32::MAX - 1, None);
+    let (_, sps) = AvcSequenceParameterSet::parse(&sps).unwrap();
+    assert_eq!((sps.width(), sps.height()), (None, None));
+
+    // The cropping overflows, or is bigger than the picture.
+    let sps = baseline_sps(119, 67, Some([u32::MAX - 1, u32::MAX - 1, 540, 540]));
+    let (_, sps) = AvcSequenceParameterSet::parse(&sps).unwrap();
+    assert_eq!((sps.width(), sps.height()), (None, None));
+}
+
+#[test]
+fn test_avc_sequence_parameter_set_out_of_range() {
+    // High profile with the scaling matrix.
+    let high_sps = |chroma_format_idc: u32, delta_scale: i32| {
+        BitWriter::default()
+            .bits(0x6764_001f, 32)
+            // seq_parameter_set_id, chroma_format_idc, bit depths
+            .ue(0)
+            .ue(chroma_format_idc)
+            .ue(0)
+            .ue(0)
+            // qpprime_y_zero_transform_bypass_flag, seq_scaling_matrix_present_flag,
+            // seq_scaling_list_present_flag[0]
+            .bits(0b011, 3)
+            .se(delta_scale)
+            // the other 15 delta_scale of the 4x4 list, and the other lists aren't present
+            .bits(0x7fff, 15)
+            .bits(0, 7)
+            // log2_max_frame_num_minus4, pic_order_cnt_type = 2, max_num_ref_frames,
+            // gaps_in_frame_num_value_allowed_flag
+            .ue(0)
+            .ue(2)
+            .ue(1)
+            .bits(0, 1)
+            // 1920x1088, frame_mbs_only_flag, direct_8x8_inference_flag,
+            // frame_cropping_flag, vui_parameters_present_flag
+            .ue(119)
+            .ue(67)
+            .bits(0b1100, 4)
+            .finish()
+    };
+    assert!(AvcSequenceParameterSet::parse(&high_sps(1, 127)).is_ok());
+    assert!(matches!(
+        AvcSequenceParameterSet::parse(&high_sps(1, 128)),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        AvcSequenceParameterSet::parse(&high_sps(1, -129)),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        AvcSequenceParameterSet::parse(&high_sps(4, 0)),
+        Err(NomErr::Error(_))
+    ));
+}
@@ -0,0 +1,159 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+// Helpers for building small FLV fixtures byte by byte.
+
+#![allow(dead_code)]
+
+pub const FLV_FILE_HEADER_LENGTH: usize = 9;
+pub const PREVIOUS_TAG_SIZE_LENGTH: usize = 4;
+pub const FLV_TAG_HEADER_LENGTH: usize = 11;
+
+pub const AUDIO_TAG_TYPE: u8 = 8;
+pub const VIDEO_TAG_TYPE: u8 = 9;
+pub const SCRIPT_TAG_TYPE: u8 = 18;
+pub const FILTER_BIT: u8 = 0x20;
+
+// AVC sequence header (key frame, AVC) with an AVCDecoderConfigurationRecord.
+pub const AVC_SEQUENCE_HEADER: &[u8] = &[
+    0x17, 0x00, 0x00, 0x00, 0x00, // video tag header + AVC packet type + composition time
+    0x01, 0x64, 0x00, 0x1f, 0xff,
+    0xe1, // version, profile, compatibility, level, NAL length size
+    0x00, 0x04, 0x67, 0x64, 0x00, 0x1f, // 1 SPS
+    0x01, 0x00, 0x04, 0x68, 0xee, 0x3c, 0x80, // 1 PPS
+];
+// AVC key frame with one IDR NALU.
+pub const AVC_KEY_FRAME: &[u8] = &[
+    0x17, 0x01, 0x00, 0x00, 0x00, // video tag header + AVC packet type + composition time
+    0x00, 0x00, 0x00, 0x05, 0x65, 0x88, 0x84, 0x00, 0x10,
+];
+// AVC inter frame with one non-IDR NALU.
+pub const AVC_INTER_FRAME: &[u8] = &[
+    0x27, 0x01, 0x00, 0x00, 0x28, // video tag header + AVC packet type + composition time
+    0x00, 0x00, 0x00, 0x03, 0x41, 0x9a, 0x02,
+];
+// AAC sequence header (AAC LC, 44100 Hz, stereo).
+pub const AAC_SEQUENCE_HEADER: &[u8] = &[0xaf, 0x00, 0x12, 0x10];
+// AAC raw frame.
+pub const AAC_RAW_FRAME: &[u8] = &[0xaf, 0x01, 0x21, 0x10, 0x04, 0x60, 0x8c, 0x1c];
+
+/// Build a FLV tag (tag header + tag data), without the following previous tag size.
+pub fn flv_tag(tag_type: u8, timestamp: u32, data: &[u8]) -> Vec<u8> {
+    let mut tag = vec![tag_type];
+    tag.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+    tag.extend_from_slice(&timestamp.to_be_bytes()[1..]);
+    tag.push((timestamp >> 24) as u8);
+    tag.extend_from_slice(&[0, 0, 0]);
+    tag.extend_from_slice(data);
+    tag
+}
+
+/// Build a filtered tag data: the unencrypted tag data header, the encryption tag header,
+/// the filter params and the filtered data.
+pub fn filtered_data(header: &[u8], filter_name: &str, params: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut filtered = header.to_vec();
+    filtered.push(1);
+    filtered.extend_from_slice(&(filter_name.len() as u16).to_be_bytes());
+    filtered.extend_from_slice(filter_name.as_bytes());
+    filtered.extend_from_slice(&(params.len() as u32).to_be_bytes()[1..]);
+    filtered.extend_from_slice(params);
+    filtered.extend_from_slice(data);
+    filtered
+}
+
+/// Build a FLV file with the given header flags and tags,
+/// every tag is followed by its previous tag size.
+pub fn flv_file(flags: u8, tags: &[Vec<u8>]) -> Vec<u8> {
+    let mut file = vec![0x46, 0x4c, 0x56, 0x01, flags, 0x00, 0x00, 0x00, 0x09];
+    file.extend_from_slice(&0u32.to_be_bytes());
+    for tag in tags {
+        file.extend_from_slice(tag);
+        file.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+    }
+    file
+}
+
+/// Build the data of `onMetaData` script tag with the given number properties.
+pub fn on_meta_data(properties: &[(&str, f64)]) -> Vec<u8> {
+    let mut data = vec![0x02];
+    data.extend_from_slice(&10u16.to_be_bytes());
+    data.extend_from_slice(b"onMetaData");
+    data.push(0x08);
+    data.extend_from_slice(&(properties.len() as u32).to_be_bytes());
+    for (name, value) in properties {
+        data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.push(0x00);
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&[0x00, 0x00, 0x09]);
+    data
+}
+
+/// The tags of a small FLV file with a script tag and AVC/AAC tags.
+pub fn test_flv_tags() -> Vec<Vec<u8>> {
+    vec![
+        flv_tag(
+            SCRIPT_TAG_TYPE,
+            0,
+            &on_meta_data(&[("duration", 0.04), ("width", 1920.0), ("height", 1080.0)]),
+        ),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+        flv_tag(AUDIO_TAG_TYPE, 0, AAC_SEQUENCE_HEADER),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+        flv_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME),
+        flv_tag(VIDEO_TAG_TYPE, 40, AVC_INTER_FRAME),
+    ]
+}
+
+/// A small FLV file with a script tag and AVC/AAC tags.
+pub fn test_flv_file() -> Vec<u8> {
+    flv_file(0b0000_0101, &test_flv_tags())
+}
+
+/// A bit writer for building NAL units (e.g. SPS) with Exp-Golomb coded values.
+#[derive(Default)]
+pub struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl BitWriter {
+    /// Write the lower `n` bits of value.
+    pub fn bits(mut self, value: u64, n: usize) -> Self {
+        for i in (0..n).rev() {
+            self.bits.push((value >> i) & 1 == 1);
+        }
+        self
+    }
+
+    /// Write an unsigned Exp-Golomb coded value, `ue(v)`.
+    pub fn ue(self, value: u32) -> Self {
+        let value = u64::from(value) + 1;
+        let len = 64 - value.leading_zeros() as usize;
+        self.bits(0, len - 1).bits(value, len)
+    }
+
+    /// Write a signed Exp-Golomb coded value, `se(v)`.
+    pub fn se(self, value: i32) -> Self {
+        let value = i64::from(value);
+        self.ue(if value > 0 { value * 2 - 1 } else { -value * 2 } as u32)
+    }
+
+    /// Finish the RBSP with the stop bit, and insert the emulation prevention bytes.
+    pub fn finish(self) -> Vec<u8> {
+        let mut bits = self.bits(1, 1).bits;
+        while !bits.len().is_multiple_of(8) {
+            bits.push(false);
+        }
+        let mut nal_unit = Vec::new();
+        for byte in bits.chunks(8) {
+            let byte = byte
+                .iter()
+                .fold(0u8, |byte, &bit| byte << 1 | u8::from(bit));
+            if byte <= 3 && nal_unit.ends_with(&[0, 0]) {
+                nal_unit.push(3);
+            }
+            nal_unit.push(byte);
+        }
+        nal_unit
+    }
+}
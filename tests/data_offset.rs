@@ -0,0 +1,142 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+const EXTENSION: &[u8] = b"muxer";
+
+// The test file with the extension data between the file header and the body.
+fn flv_file_with_extension() -> Vec<u8> {
+    let mut file = test_flv_file();
+    let data_offset = (FLV_FILE_HEADER_LENGTH + EXTENSION.len()) as u32;
+    file[5..9].copy_from_slice(&data_offset.to_be_bytes());
+    file.splice(
+        FLV_FILE_HEADER_LENGTH..FLV_FILE_HEADER_LENGTH,
+        EXTENSION.iter().copied(),
+    );
+    file
+}
+
+#[test]
+fn test_file_header_extension() {
+    let file = flv_file_with_extension();
+    let (remain, (header, extension)) = FlvFileHeader::parse_with_extension(&file).unwrap();
+    assert_eq!(header.data_offset, 14);
+    assert_eq!(header.extension_size(), EXTENSION.len());
+    assert_eq!(extension, EXTENSION);
+    assert_eq!(remain, &file[14..]);
+
+    assert!(matches!(
+        FlvFileHeader::parse_with_extension(&file[..12]),
+        Err(NomErr::Incomplete(_))
+    ));
+    // data_offset less than 9
+    let mut file = test_flv_file();
+    file[8] = 8;
+    assert!(matches!(
+        FlvFileHeader::parse_with_extension(&file),
+        Err(NomErr::Error(_))
+    ));
+    assert!(FlvFile::try_parse(&file).is_err());
+}
+
+#[test]
+fn test_parse_with_data_offset() {
+    let file = flv_file_with_extension();
+    let plain = test_flv_file();
+    let (_, expected) = FlvFile::parse(&plain).unwrap();
+
+    let (remain, flv) = FlvFile::parse(&file).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(flv.extension, EXTENSION);
+    assert_eq!(flv.body, expected.body);
+    assert_eq!(FlvFile::try_parse(&file).unwrap(), flv);
+    let (_, (recovered, skipped)) = FlvFile::parse_recovering(&file).unwrap();
+    assert_eq!(recovered, flv);
+    assert!(skipped.is_empty());
+
+    let mut demuxer = FlvDemuxer::new();
+    demuxer.push(&file);
+    let mut tags = 0;
+    while let Some(event) = demuxer.next_event().unwrap() {
+        if let FlvDemuxEvent::Tag(..) = event {
+            tags += 1;
+        }
+    }
+    assert_eq!(tags, expected.body.tags.len());
+
+    assert_eq!(validate(&file), vec![]);
+}
+
+#[test]
+fn test_write_with_extension() {
+    let file = flv_file_with_extension();
+    let (_, flv) = FlvFile::parse(&file).unwrap();
+    let mut written = Vec::new();
+    flv.write(&mut written).unwrap();
+    assert_eq!(written, file);
+    let (_, reparsed) = FlvFile::parse(&written).unwrap();
+    assert_eq!(reparsed, flv);
+
+    // The data offset is computed from the extension data.
+    let mut header = flv.header;
+    header.data_offset = 9;
+    let flv = FlvFile { header, ..flv };
+    let mut written = Vec::new();
+    flv.write(&mut written).unwrap();
+    assert_eq!(written, file);
+
+    // The writer writes no extension data.
+    let writer = FlvWriter::new(Vec::new(), &flv.header).unwrap();
+    let (_, (header, extension)) = FlvFileHeader::parse_with_extension(writer.get_ref()).unwrap();
+    assert_eq!(header.data_offset, 9);
+    assert!(extension.is_empty());
+}
+
+#[test]
+fn test_scan_inject_and_extract_with_data_offset() {
+    let file = flv_file_with_extension();
+    let (_, index) = KeyframeIndex::scan(&file).unwrap();
+    assert_eq!(index.keyframes.len(), 1);
+    // The key frame is the fourth tag.
+    let tags = test_flv_tags();
+    let position = FLV_FILE_HEADER_LENGTH
+        + EXTENSION.len()
+        + PREVIOUS_TAG_SIZE_LENGTH
+        + tags[..3]
+            .iter()
+            .map(|tag| tag.len() + PREVIOUS_TAG_SIZE_LENGTH)
+            .sum::<usize>();
+    assert_eq!(index.keyframes[0].file_position, position as u64);
+
+    let mut injected = Vec::new();
+    inject_metadata(&file, &mut injected).unwrap();
+    let (_, flv) = FlvFile::parse(&injected).unwrap();
+    assert_eq!(flv.header.data_offset, 9);
+    assert!(flv.extension.is_empty());
+    assert_eq!(flv.body.tags.len(), tags.len());
+
+    assert_eq!(extract_aac(&file, &mut Vec::new()).unwrap(), 1);
+    assert_eq!(extract_avc(&file, &mut Vec::new()).unwrap(), 2);
+}
+
+#[test]
+fn test_reserved_flags() {
+    let (_, header) = FlvFileHeader::parse(&test_flv_file()).unwrap();
+    assert!(header.reserved_flags().is_empty());
+
+    let (_, header) = FlvFileHeader::parse(b"FLV\x01\x8f\x00\x00\x00\x09").unwrap();
+    assert!(header.has_audio);
+    assert!(header.has_video);
+    assert_eq!(
+        header.reserved_flags(),
+        FlvFileHeaderReservedFlags {
+            high: 0b1_0001,
+            low: true,
+        }
+    );
+    assert_eq!(header.reserved_flags().bits(), 0b1000_1010);
+}
@@ -0,0 +1,126 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+#![cfg(feature = "decrypt")]
+
+mod common;
+
+use cbc::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+use flvparse::*;
+
+use self::common::*;
+
+const KEY: [u8; 16] = *b"0123456789abcdef";
+const IV: [u8; 16] = *b"fedcba9876543210";
+
+fn encrypt(data: &[u8], iv: [u8; 16]) -> Vec<u8> {
+    cbc::Encryptor::<aes::Aes128>::new(&KEY.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(data)
+}
+
+// Encrypt the tag data body, keeping the tag data header of `header_size` bytes unencrypted.
+fn encrypted_tag(tag_type: u8, timestamp: u32, data: &[u8], header_size: usize) -> Vec<u8> {
+    let (header, body) = data.split_at(header_size);
+    let data = filtered_data(header, "Encryption", &IV, &encrypt(body, IV));
+    flv_tag(tag_type | FILTER_BIT, timestamp, &data)
+}
+
+#[test]
+fn test_decrypt_tag() {
+    let tag = encrypted_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME, 2);
+    let (_, filtered) = FlvTag::parse(&tag).unwrap();
+    assert!(filtered.header.filter);
+
+    let mut buf = Vec::new();
+    let decrypted = FlvDecryptor::new(KEY)
+        .decrypt_tag(&filtered, &mut buf)
+        .unwrap();
+    let plain = flv_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME);
+    let (_, expected) = FlvTag::parse(&plain).unwrap();
+    assert_eq!(decrypted, expected);
+    assert!(!decrypted.header.filter);
+
+    // The tag that isn't filtered is returned as is.
+    let mut buf = Vec::new();
+    assert_eq!(
+        FlvDecryptor::new(KEY).decrypt_tag(&expected, &mut buf),
+        Ok(expected.clone())
+    );
+}
+
+#[test]
+fn test_decrypt_selective_encryption() {
+    let decryptor = FlvDecryptor::new(KEY);
+
+    let mut params = vec![0x80];
+    params.extend_from_slice(&IV);
+    let data = filtered_data(
+        &AVC_KEY_FRAME[..5],
+        "SE",
+        &params,
+        &encrypt(&AVC_KEY_FRAME[5..], IV),
+    );
+    let (_, filtered) = FilteredTag::parse(&data, FlvTagType::Video, data.len()).unwrap();
+    let mut buf = Vec::new();
+    decryptor.decrypt_data(&filtered, &mut buf).unwrap();
+    assert_eq!(buf, AVC_KEY_FRAME);
+
+    // The packet isn't encrypted.
+    let data = filtered_data(&AVC_INTER_FRAME[..5], "SE", &[0x00], &AVC_INTER_FRAME[5..]);
+    let (_, filtered) = FilteredTag::parse(&data, FlvTagType::Video, data.len()).unwrap();
+    let mut buf = Vec::new();
+    decryptor.decrypt_data(&filtered, &mut buf).unwrap();
+    assert_eq!(buf, AVC_INTER_FRAME);
+}
+
+#[test]
+fn test_decrypt_flv() {
+    let tags = test_flv_tags();
+    let encrypted = vec![
+        tags[0].clone(),
+        encrypted_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER, 5),
+        encrypted_tag(AUDIO_TAG_TYPE, 0, AAC_SEQUENCE_HEADER, 2),
+        encrypted_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME, 5),
+        encrypted_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME, 2),
+        tags[5].clone(),
+    ];
+    let file = flv_file(0b0000_0101, &encrypted);
+
+    let mut output = Vec::new();
+    assert_eq!(decrypt_flv(&file, KEY, &mut output).unwrap(), 4);
+    assert_eq!(output, test_flv_file());
+}
+
+#[test]
+fn test_decrypt_flv_file() {
+    // The encrypted file of the decrypt example in README.
+    let file = include_bytes!("../assets/test_encrypted.flv");
+    let key = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    let mut output = Vec::new();
+    assert_eq!(decrypt_flv(file, key, &mut output).unwrap(), 4);
+    assert_eq!(output, test_flv_file());
+}
+
+#[test]
+fn test_decrypt_errors() {
+    let tag = encrypted_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME, 2);
+    let (_, filtered) = FlvTag::parse(&tag).unwrap();
+    let mut buf = Vec::new();
+    assert_eq!(
+        FlvDecryptor::new(*b"fedcba9876543210").decrypt_tag(&filtered, &mut buf),
+        Err(DecryptError::InvalidPadding)
+    );
+
+    let data = filtered_data(&[], "Custom", &IV, &encrypt(b"data", IV));
+    let (_, filtered) = FilteredTag::parse(&data, FlvTagType::Script, data.len()).unwrap();
+    let mut buf = vec![0x01];
+    assert_eq!(
+        FlvDecryptor::new(KEY).decrypt_data(&filtered, &mut buf),
+        Err(DecryptError::UnsupportedFilter)
+    );
+    assert_eq!(buf, vec![0x01]);
+
+    let file = flv_file(0b0000_0100, &[tag]);
+    let err = decrypt_flv(&file, [0; 16], &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,130 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+fn demux_in_chunks(file: &[u8], chunk_size: usize) {
+    let expected = FlvFile::parse(file).unwrap().1;
+    let mut expected_tags = expected.body.tags.iter();
+
+    let mut demuxer = FlvDemuxer::new();
+    let mut header = None;
+    for chunk in file.chunks(chunk_size) {
+        demuxer.push(chunk);
+        while let Some(event) = demuxer.next_event().unwrap() {
+            match event {
+                FlvDemuxEvent::Header(h) => {
+                    assert!(header.is_none());
+                    header = Some(h);
+                }
+                FlvDemuxEvent::Tag(tag, previous_tag_size) => {
+                    let (expected_tag, expected_size) = expected_tags.next().unwrap();
+                    assert_eq!(&tag, expected_tag);
+                    assert_eq!(previous_tag_size, *expected_size);
+                }
+            }
+        }
+    }
+    assert_eq!(header, Some(expected.header));
+    assert_eq!(demuxer.first_previous_tag_size(), Some(0));
+    assert!(expected_tags.next().is_none());
+    assert_eq!(demuxer.buffered_len(), 0);
+}
+
+#[test]
+fn test_demux_whole_file() {
+    let file = test_flv_file();
+    demux_in_chunks(&file, file.len());
+}
+
+#[test]
+fn test_demux_byte_by_byte() {
+    demux_in_chunks(&test_flv_file(), 1);
+}
+
+#[test]
+fn test_demux_odd_chunks() {
+    let file = test_flv_file();
+    for chunk_size in &[2, 3, 7, 13, 64] {
+        demux_in_chunks(&file, *chunk_size);
+    }
+}
+
+#[test]
+fn test_demux_split_tag_header() {
+    let file = flv_file(0b0000_0001, &[flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME)]);
+    let split = FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH + 5;
+
+    let mut demuxer = FlvDemuxer::new();
+    demuxer.push(&file[..split]);
+    assert!(matches!(
+        demuxer.next_event(),
+        Ok(Some(FlvDemuxEvent::Header(_)))
+    ));
+    assert_eq!(demuxer.next_event(), Ok(None));
+    assert_eq!(demuxer.buffered_len(), 5);
+
+    demuxer.push(&file[split..]);
+    match demuxer.next_event() {
+        Ok(Some(FlvDemuxEvent::Tag(tag, previous_tag_size))) => {
+            assert_eq!(tag.header.tag_type, FlvTagType::Video);
+            assert_eq!(tag.header.data_size as usize, AVC_KEY_FRAME.len());
+            assert_eq!(
+                previous_tag_size as usize,
+                FLV_TAG_HEADER_LENGTH + AVC_KEY_FRAME.len()
+            );
+        }
+        other => panic!("unexpected demux result: {:?}", other),
+    }
+    assert_eq!(demuxer.next_event(), Ok(None));
+}
+
+#[test]
+fn test_demux_invalid_data() {
+    let mut demuxer = FlvDemuxer::new();
+    demuxer.push(b"FLX\x01\x05\x00\x00\x00\x09");
+    assert!(demuxer.next_event().is_err());
+
+    let mut file = flv_file(0b0000_0001, &[flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME)]);
+    // Invalid tag type.
+    file[FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH] = 0x07;
+    let mut demuxer = FlvDemuxer::new();
+    demuxer.push(&file);
+    assert!(matches!(
+        demuxer.next_event(),
+        Ok(Some(FlvDemuxEvent::Header(_)))
+    ));
+    assert!(demuxer.next_event().is_err());
+}
+
+#[test]
+fn test_demux_error_offset_after_dropping_demuxed_bytes() {
+    let tags = test_flv_tags();
+    let mut file = flv_file(0b0000_0101, &tags);
+    // Invalid tag type of the last tag.
+    let last = file.len() - PREVIOUS_TAG_SIZE_LENGTH - tags[tags.len() - 1].len();
+    file[last] = 0x07;
+
+    let mut demuxer = FlvDemuxer::new();
+    let mut tag_count = 0;
+    for chunk in file.chunks(7) {
+        demuxer.push(chunk);
+        loop {
+            match demuxer.next_event() {
+                Ok(Some(FlvDemuxEvent::Tag(..))) => tag_count += 1,
+                Ok(Some(FlvDemuxEvent::Header(_))) => {}
+                Ok(None) => break,
+                Err(err) => {
+                    assert_eq!(tag_count, tags.len() - 1);
+                    assert_eq!(err.offset, last as u64);
+                    assert_eq!(err.tag_index, Some(tags.len() - 1));
+                    return;
+                }
+            }
+        }
+    }
+    panic!("expected the invalid tag type error");
+}
@@ -0,0 +1,208 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+// IsExHeader + key frame + SequenceStart, hvc1
+const HEVC_SEQUENCE_START: &[u8] = &[0x90, b'h', b'v', b'c', b'1', 0x01, 0x01, 0x60];
+// IsExHeader + key frame + CodedFrames, hvc1, composition time 40
+const HEVC_CODED_FRAMES: &[u8] = &[
+    0x91, b'h', b'v', b'c', b'1', 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x02, 0x26, 0x01,
+];
+// IsExHeader + inter frame + CodedFramesX, av01
+const AV1_CODED_FRAMES_X: &[u8] = &[0xa3, b'a', b'v', b'0', b'1', 0x12, 0x00, 0x0a];
+
+#[test]
+fn test_ex_video_tag_header() {
+    let (remain, header) = VideoTagHeader::parse(HEVC_SEQUENCE_START, 5).unwrap();
+    assert_eq!(remain, &HEVC_SEQUENCE_START[5..]);
+    assert_eq!(
+        header,
+        VideoTagHeader {
+            frame_type: FrameType::Key,
+            codec_id: CodecID::ExHeader,
+            ex_header: Some(ExVideoTagHeader {
+                packet_type: VideoPacketType::SequenceStart,
+                fourcc: FourCC::HEVC,
+            }),
+        }
+    );
+    assert_eq!(header.size(), 5);
+
+    let (_, header) = VideoTagHeader::parse(AV1_CODED_FRAMES_X, 5).unwrap();
+    assert_eq!(header.frame_type, FrameType::Inter);
+    let ex_header = header.ex_header.unwrap();
+    assert_eq!(ex_header.packet_type, VideoPacketType::CodedFramesX);
+    assert_eq!(ex_header.fourcc, FourCC::AV1);
+    assert_eq!(format!("{:?}", ex_header.fourcc), "FourCC(\"av01\")");
+
+    // IsExHeader + key frame + unknown packet type, vp09
+    let (_, header) = VideoTagHeader::parse(&[0x9f, b'v', b'p', b'0', b'9'], 5).unwrap();
+    let ex_header = header.ex_header.unwrap();
+    assert_eq!(ex_header.packet_type, VideoPacketType::Unknown(15));
+    assert_eq!(ex_header.fourcc, FourCC::VP9);
+
+    assert!(matches!(
+        VideoTagHeader::parse(&HEVC_SEQUENCE_START[..3], 3),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_ex_video_tag() {
+    let (remain, tag) = VideoTag::parse(HEVC_CODED_FRAMES, HEVC_CODED_FRAMES.len()).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(tag.body.data, &HEVC_CODED_FRAMES[5..]);
+
+    let ex_header = tag.header.ex_header.unwrap();
+    let (_, packet) = ex_video_packet(&ex_header, tag.body.data, tag.body.data.len()).unwrap();
+    assert_eq!(packet.packet_type, VideoPacketType::CodedFrames);
+    assert_eq!(packet.fourcc, FourCC::HEVC);
+    assert_eq!(packet.composition_time, 40);
+    assert_eq!(packet.data, &HEVC_CODED_FRAMES[8..]);
+
+    // No composition time for CodedFramesX.
+    let (_, tag) = VideoTag::parse(AV1_CODED_FRAMES_X, AV1_CODED_FRAMES_X.len()).unwrap();
+    let ex_header = tag.header.ex_header.unwrap();
+    let (_, packet) = ex_video_packet(&ex_header, tag.body.data, tag.body.data.len()).unwrap();
+    assert_eq!(packet.composition_time, 0);
+    assert_eq!(packet.data, &AV1_CODED_FRAMES_X[5..]);
+}
+
+#[test]
+fn test_write_ex_video_tag() {
+    for data in &[HEVC_SEQUENCE_START, HEVC_CODED_FRAMES, AV1_CODED_FRAMES_X] {
+        let (_, tag) = VideoTag::parse(data, data.len()).unwrap();
+        let mut written = Vec::new();
+        tag.write(&mut written).unwrap();
+        assert_eq!(&written, data);
+    }
+}
+
+#[test]
+fn test_keyframe_index_of_ex_video_tags() {
+    let file = flv_file(
+        0b0000_0001,
+        &[
+            flv_tag(VIDEO_TAG_TYPE, 0, HEVC_SEQUENCE_START),
+            flv_tag(VIDEO_TAG_TYPE, 0, HEVC_CODED_FRAMES),
+            flv_tag(VIDEO_TAG_TYPE, 40, AV1_CODED_FRAMES_X),
+        ],
+    );
+    let (_, index) = KeyframeIndex::scan(&file).unwrap();
+    assert_eq!(index.keyframes.len(), 1);
+    assert_eq!(index.keyframes[0].timestamp, 0);
+    assert_eq!(
+        index.keyframes[0].file_position as usize,
+        FLV_FILE_HEADER_LENGTH
+            + PREVIOUS_TAG_SIZE_LENGTH
+            + FLV_TAG_HEADER_LENGTH
+            + HEVC_SEQUENCE_START.len()
+            + PREVIOUS_TAG_SIZE_LENGTH
+    );
+}
+
+// ExHeader + SequenceStart, Opus
+const OPUS_SEQUENCE_START: &[u8] = &[
+    0x90, b'O', b'p', b'u', b's', b'O', b'p', b'u', b's', b'H', b'e', b'a', b'd',
+];
+// ExHeader + CodedFrames, fLaC
+const FLAC_CODED_FRAMES: &[u8] = &[0x91, b'f', b'L', b'a', b'C', 0xff, 0xf8, 0x69, 0x08];
+
+#[test]
+fn test_ex_audio_tag_header() {
+    let (remain, header) = AudioTagHeader::parse(OPUS_SEQUENCE_START, 5).unwrap();
+    assert_eq!(remain, &OPUS_SEQUENCE_START[5..]);
+    assert_eq!(header.sound_format, SoundFormat::ExHeader);
+    assert_eq!(
+        header.ex_header,
+        Some(ExAudioTagHeader {
+            packet_type: AudioPacketType::SequenceStart,
+            fourcc: FourCC::OPUS,
+        })
+    );
+    assert_eq!(header.size(), 5);
+
+    for (data, packet_type, fourcc) in &[
+        (b"\x92ac-3", AudioPacketType::SequenceEnd, FourCC::AC3),
+        (
+            b"\x94ec-3",
+            AudioPacketType::MultichannelConfig,
+            FourCC::EAC3,
+        ),
+        (b"\x91.mp3", AudioPacketType::CodedFrames, FourCC::MP3),
+        (b"\x97Opus", AudioPacketType::Unknown(7), FourCC::OPUS),
+    ] {
+        let (_, header) = AudioTagHeader::parse(&data[..], 5).unwrap();
+        let ex_header = header.ex_header.unwrap();
+        assert_eq!(ex_header.packet_type, *packet_type);
+        assert_eq!(ex_header.fourcc, *fourcc);
+    }
+
+    assert!(matches!(
+        AudioTagHeader::parse(&OPUS_SEQUENCE_START[..3], 3),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_ex_audio_tag() {
+    let (remain, tag) = AudioTag::parse(FLAC_CODED_FRAMES, FLAC_CODED_FRAMES.len()).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(
+        tag.header.ex_header.unwrap().packet_type,
+        AudioPacketType::CodedFrames
+    );
+    assert_eq!(tag.body.data, &FLAC_CODED_FRAMES[5..]);
+
+    for data in &[OPUS_SEQUENCE_START, FLAC_CODED_FRAMES] {
+        let (_, tag) = AudioTag::parse(data, data.len()).unwrap();
+        let mut written = Vec::new();
+        tag.write(&mut written).unwrap();
+        assert_eq!(&written, data);
+    }
+}
+
+#[test]
+fn test_audio_multichannel_config() {
+    // Native order, 6 channels: FL, FR, FC, LFE, BL, BR.
+    let (remain, config) =
+        AudioMultichannelConfig::parse(&[0x01, 0x06, 0x00, 0x00, 0x00, 0x3f]).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(config.channel_count, 6);
+    assert_eq!(config.channel_order, AudioChannelOrder::Native(0x3f));
+
+    // Custom order, 2 channels: FR, FL.
+    let (_, config) = AudioMultichannelConfig::parse(&[0x02, 0x02, 0x01, 0x00]).unwrap();
+    assert_eq!(
+        config.channel_order,
+        AudioChannelOrder::Custom(&[0x01, 0x00])
+    );
+
+    let (_, config) = AudioMultichannelConfig::parse(&[0x00, 0x08]).unwrap();
+    assert_eq!(config.channel_count, 8);
+    assert_eq!(config.channel_order, AudioChannelOrder::Unspecified);
+
+    assert!(AudioMultichannelConfig::parse(&[0x03, 0x02]).is_err());
+}
+
+#[test]
+fn test_inject_metadata_with_ex_audio() {
+    let file = flv_file(
+        0b0000_0100,
+        &[
+            flv_tag(AUDIO_TAG_TYPE, 0, OPUS_SEQUENCE_START),
+            flv_tag(AUDIO_TAG_TYPE, 20, FLAC_CODED_FRAMES),
+        ],
+    );
+    let meta = inject_metadata(&file, &mut Vec::new()).unwrap();
+    assert_eq!(
+        meta.audio_codec_id,
+        Some(f64::from(u32::from_be_bytes(*b"Opus")))
+    );
+    assert_eq!(meta.audio_sample_rate, None);
+    assert_eq!(meta.stereo, None);
+}
@@ -0,0 +1,163 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+const TAGS_START: usize = FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH;
+
+#[test]
+fn test_try_parse() {
+    let file = test_flv_file();
+    let (_, expected) = FlvFile::parse(&file).unwrap();
+    assert_eq!(FlvFile::try_parse(&file), Ok(expected));
+}
+
+#[test]
+fn test_file_header_error() {
+    let err = FlvFile::try_parse(b"FLX\x01\x05\x00\x00\x00\x09").unwrap_err();
+    assert_eq!(err.structure, FlvStructure::FileHeader);
+    assert_eq!(err.kind, FlvErrorKind::Malformed(ErrorKind::Tag));
+    assert_eq!(err.offset, 0);
+    assert_eq!(err.tag_index, None);
+
+    let err = FlvFile::try_parse(b"FLV\x01\x05\x00\x00\x00\x09\x00\x00").unwrap_err();
+    assert_eq!(err.structure, FlvStructure::PreviousTagSize);
+    assert!(err.is_truncated());
+    assert_eq!(err.offset, 9);
+}
+
+#[test]
+fn test_tag_errors() {
+    let tags = test_flv_tags();
+    let second_tag = TAGS_START + tags[0].len() + PREVIOUS_TAG_SIZE_LENGTH;
+
+    // unknown tag type
+    let mut file = test_flv_file();
+    file[second_tag] = 0x07;
+    let err = FlvFile::try_parse(&file).unwrap_err();
+    assert_eq!(
+        err,
+        FlvError {
+            structure: FlvStructure::TagHeader,
+            kind: FlvErrorKind::Malformed(ErrorKind::Switch),
+            offset: second_tag as u64,
+            tag_index: Some(1),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "malformed tag header (Switch) at offset {} in tag 1",
+            second_tag
+        )
+    );
+
+    // The tag data is truncated.
+    let file = test_flv_file();
+    let err = FlvFile::try_parse(&file[..second_tag + FLV_TAG_HEADER_LENGTH + 2]).unwrap_err();
+    assert_eq!(err.structure, FlvStructure::TagData);
+    assert_eq!(err.kind, FlvErrorKind::Truncated);
+    assert_eq!(err.offset, (second_tag + FLV_TAG_HEADER_LENGTH) as u64);
+
+    // The previous tag size is truncated.
+    let err = FlvFile::try_parse(&file[..file.len() - 1]).unwrap_err();
+    assert_eq!(err.structure, FlvStructure::PreviousTagSize);
+    assert!(err.is_truncated());
+    assert_eq!(err.tag_index, Some(tags.len() - 1));
+    assert_eq!(err.offset, (file.len() - PREVIOUS_TAG_SIZE_LENGTH) as u64);
+}
+
+#[test]
+fn test_tag_errors_with_extension() {
+    // The data offset of file header is 12, followed by 3 bytes of extension data.
+    let tags = test_flv_tags();
+    let mut file = test_flv_file();
+    file[8] = 12;
+    file.splice(
+        FLV_FILE_HEADER_LENGTH..FLV_FILE_HEADER_LENGTH,
+        vec![0xaa; 3],
+    );
+    let second_tag = TAGS_START + 3 + tags[0].len() + PREVIOUS_TAG_SIZE_LENGTH;
+    file[second_tag] = 0x07;
+    let expected = FlvError {
+        structure: FlvStructure::TagHeader,
+        kind: FlvErrorKind::Malformed(ErrorKind::Switch),
+        offset: second_tag as u64,
+        tag_index: Some(1),
+    };
+    assert_eq!(FlvFile::try_parse(&file), Err(expected));
+
+    let (_, (_, extension, _, mut iter)) = FlvFile::parse_lazy(&file).unwrap();
+    assert_eq!(extension, &[0xaa; 3]);
+    assert_eq!(iter.by_ref().count(), 1);
+    assert_eq!(iter.error(), Some(&expected));
+
+    let (remain, parsed) = FlvFile::parse(&file).unwrap();
+    assert_eq!(parsed.body.tags.len(), 1);
+    assert_eq!(remain, &file[second_tag..]);
+}
+
+#[test]
+fn test_tag_data_errors() {
+    // The extended video tag header is shorter than the data size, which is malformed.
+    let tags = vec![
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+        flv_tag(VIDEO_TAG_TYPE, 40, &[0x91, b'h', b'v']),
+    ];
+    let second_tag = TAGS_START + tags[0].len() + PREVIOUS_TAG_SIZE_LENGTH;
+    let file = flv_file(0b0000_0001, &tags);
+    let err = FlvFile::try_parse(&file).unwrap_err();
+    assert_eq!(err.structure, FlvStructure::VideoTagHeader);
+    assert_eq!(err.kind, FlvErrorKind::Malformed(ErrorKind::Eof));
+    assert_eq!(err.offset, (second_tag + FLV_TAG_HEADER_LENGTH) as u64);
+    assert_eq!(err.tag_index, Some(1));
+
+    // The script data has an invalid AMF value type.
+    let mut data = on_meta_data(&[("duration", 1.0)]);
+    data[13] = 0x0f;
+    let file = flv_file(0b0000_0000, &[flv_tag(SCRIPT_TAG_TYPE, 0, &data)]);
+    let err = FlvFile::try_parse(&file).unwrap_err();
+    assert_eq!(err.structure, FlvStructure::ScriptData);
+    assert!(matches!(err.kind, FlvErrorKind::Malformed(_)));
+    assert_eq!(err.offset, (TAGS_START + FLV_TAG_HEADER_LENGTH + 13) as u64);
+}
+
+#[test]
+fn test_tag_iter_error() {
+    let mut file = test_flv_file();
+    file.truncate(file.len() - 2);
+    let mut iter = FlvTagIter::new(&file[TAGS_START..]);
+    assert_eq!(iter.by_ref().count(), test_flv_tags().len() - 1);
+    let err = iter.error().unwrap();
+    assert!(err.is_truncated());
+    assert_eq!(err.tag_index, Some(test_flv_tags().len() - 1));
+
+    let file = test_flv_file();
+    let mut iter = FlvTagIter::new(&file[TAGS_START..]);
+    assert_eq!(iter.by_ref().count(), test_flv_tags().len());
+    assert_eq!(iter.error(), None);
+}
+
+#[test]
+fn test_demux_error() {
+    let mut file = flv_file(0b0000_0001, &[flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME)]);
+    file[TAGS_START] = 0x07;
+    let mut demuxer = FlvDemuxer::new();
+    demuxer.push(&file[..10]);
+    assert!(matches!(
+        demuxer.next_event(),
+        Ok(Some(FlvDemuxEvent::Header(_)))
+    ));
+    assert_eq!(demuxer.next_event(), Ok(None));
+    demuxer.push(&file[10..]);
+    let err = demuxer.next_event().unwrap_err();
+    assert_eq!(err.structure, FlvStructure::TagHeader);
+    assert_eq!(err.offset, TAGS_START as u64);
+    assert_eq!(err.tag_index, Some(0));
+
+    let io_err = std::io::Error::from(err);
+    assert_eq!(io_err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,194 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+#[test]
+fn test_adts_header() {
+    let (_, config) = AudioSpecificConfig::parse(&[0x12, 0x10]).unwrap();
+    // AAC LC, 44100 Hz, stereo, frame length 7 + 6.
+    assert_eq!(
+        config.adts_header(6).unwrap(),
+        [0xff, 0xf1, 0x50, 0x80, 0x01, 0xbf, 0xfc]
+    );
+    assert!(config.adts_header(0x2000).is_err());
+
+    let (_, config) = AudioSpecificConfig::parse(&[0x17, 0x80, 0x56, 0x22, 0x08]).unwrap();
+    assert!(config.adts_header(6).is_err());
+}
+
+#[test]
+fn test_extract_aac() {
+    let file = test_flv_file();
+    let mut output = Vec::new();
+    assert_eq!(extract_aac(&file, &mut output).unwrap(), 1);
+
+    let raw = &AAC_RAW_FRAME[2..];
+    assert_eq!(output.len(), ADTS_HEADER_SIZE + raw.len());
+    assert_eq!(&output[..3], &[0xff, 0xf1, 0x50]);
+    assert_eq!(&output[ADTS_HEADER_SIZE..], raw);
+}
+
+#[test]
+fn test_extract_aac_with_invalid_tag() {
+    let mut tags = test_flv_tags();
+    // The extended audio tag header is truncated.
+    tags.push(flv_tag(AUDIO_TAG_TYPE, 46, &[0x91, b'm', b'p']));
+    tags.push(flv_tag(AUDIO_TAG_TYPE, 69, AAC_RAW_FRAME));
+    let file = flv_file(0b0000_0101, &tags);
+    let err = extract_aac(&file, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_extract_aac_with_changed_sequence_header() {
+    // AAC LC, 22050 Hz, mono.
+    let mono_sequence_header = [0xaf, 0x00, 0x13, 0x88];
+    let file = flv_file(
+        0b0000_0100,
+        &[
+            flv_tag(AUDIO_TAG_TYPE, 0, AAC_SEQUENCE_HEADER),
+            flv_tag(AUDIO_TAG_TYPE, 0, AAC_RAW_FRAME),
+            flv_tag(AUDIO_TAG_TYPE, 23, &mono_sequence_header),
+            flv_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME),
+        ],
+    );
+    let mut output = Vec::new();
+    assert_eq!(extract_aac(&file, &mut output).unwrap(), 2);
+
+    let frame_length = ADTS_HEADER_SIZE + AAC_RAW_FRAME.len() - 2;
+    assert_eq!(output.len(), frame_length * 2);
+    // 44100 Hz (index 4), 2 channels
+    assert_eq!(output[2], 0x50);
+    assert_eq!(output[3] >> 6, 0b10);
+    // 22050 Hz (index 7), 1 channel
+    assert_eq!(output[frame_length + 2], 0x5c);
+    assert_eq!(output[frame_length + 3] >> 6, 0b01);
+}
+
+#[test]
+fn test_extract_aac_without_sequence_header() {
+    let file = flv_file(0b0000_0100, &[flv_tag(AUDIO_TAG_TYPE, 0, AAC_RAW_FRAME)]);
+    let err = extract_aac(&file, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_adts_writer() {
+    let mut writer = AdtsWriter::new(Vec::new());
+    let (_, sequence_header) = aac_audio_packet(&AAC_SEQUENCE_HEADER[1..], 3).unwrap();
+    writer.write_packet(&sequence_header).unwrap();
+    assert_eq!(writer.config().unwrap().sampling_frequency, 44100);
+    let (_, raw) = aac_audio_packet(&AAC_RAW_FRAME[1..], AAC_RAW_FRAME.len() - 1).unwrap();
+    writer.write_packet(&raw).unwrap();
+    assert_eq!(writer.frames(), 1);
+    let output = writer.into_inner().unwrap();
+    assert_eq!(&output[ADTS_HEADER_SIZE..], &AAC_RAW_FRAME[2..]);
+}
+
+const SPS: &[u8] = &[0x67, 0x64, 0x00, 0x1f];
+const PPS: &[u8] = &[0x68, 0xee, 0x3c, 0x80];
+
+fn annex_b(nal_units: &[&[u8]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for nal_unit in nal_units {
+        data.extend_from_slice(&ANNEX_B_START_CODE);
+        data.extend_from_slice(nal_unit);
+    }
+    data
+}
+
+#[test]
+fn test_extract_avc() {
+    let file = test_flv_file();
+    let mut output = Vec::new();
+    assert_eq!(extract_avc(&file, &mut output).unwrap(), 2);
+    assert_eq!(
+        output,
+        annex_b(&[
+            SPS,
+            PPS,
+            &[0x65, 0x88, 0x84, 0x00, 0x10],
+            &[0x41, 0x9a, 0x02]
+        ])
+    );
+}
+
+#[test]
+fn test_extract_avc_with_invalid_tag() {
+    let mut tags = test_flv_tags();
+    // The extended video tag header is truncated.
+    tags.push(flv_tag(VIDEO_TAG_TYPE, 80, &[0x91, b'a', b'v']));
+    tags.push(flv_tag(VIDEO_TAG_TYPE, 120, AVC_INTER_FRAME));
+    let file = flv_file(0b0000_0101, &tags);
+    let err = extract_avc(&file, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_extract_avc_with_nal_length_size() {
+    // lengthSizeMinusOne = 1
+    let mut sequence_header = AVC_SEQUENCE_HEADER.to_vec();
+    sequence_header[9] = 0xfd;
+    let key_frame = [
+        0x17, 0x01, 0x00, 0x00, 0x00, // video tag header + AVC packet type + composition time
+        0x00, 0x02, 0x09, 0xf0, // access unit delimiter
+        0x00, 0x03, 0x65, 0x88, 0x84, // IDR slice
+        0x00, 0x03, 0x65, 0x88, 0x85, // IDR slice
+    ];
+    let file = flv_file(
+        0b0000_0001,
+        &[
+            flv_tag(VIDEO_TAG_TYPE, 0, &sequence_header),
+            flv_tag(VIDEO_TAG_TYPE, 0, &key_frame),
+            flv_tag(VIDEO_TAG_TYPE, 40, &key_frame),
+        ],
+    );
+    let mut output = Vec::new();
+    assert_eq!(extract_avc(&file, &mut output).unwrap(), 2);
+    let access_unit = annex_b(&[
+        &[0x09, 0xf0],
+        SPS,
+        PPS,
+        &[0x65, 0x88, 0x84],
+        &[0x65, 0x88, 0x85],
+    ]);
+    assert_eq!(output, [access_unit.clone(), access_unit].concat());
+}
+
+#[test]
+fn test_extract_avc_keeps_inline_parameter_sets() {
+    let key_frame = [
+        0x17, 0x01, 0x00, 0x00, 0x00, // video tag header + AVC packet type + composition time
+        0x00, 0x00, 0x00, 0x04, 0x67, 0x64, 0x00, 0x1f, // SPS
+        0x00, 0x00, 0x00, 0x04, 0x68, 0xee, 0x3c, 0x80, // PPS
+        0x00, 0x00, 0x00, 0x02, 0x65, 0x88, // IDR slice
+    ];
+    let file = flv_file(
+        0b0000_0001,
+        &[
+            flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+            flv_tag(VIDEO_TAG_TYPE, 0, &key_frame),
+        ],
+    );
+    let mut output = Vec::new();
+    extract_avc(&file, &mut output).unwrap();
+    assert_eq!(output, annex_b(&[SPS, PPS, &[0x65, 0x88]]));
+}
+
+#[test]
+fn test_extract_avc_with_truncated_nal_unit() {
+    let key_frame = [0x17, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x65];
+    let file = flv_file(
+        0b0000_0001,
+        &[
+            flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+            flv_tag(VIDEO_TAG_TYPE, 0, &key_frame),
+        ],
+    );
+    let err = extract_avc(&file, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
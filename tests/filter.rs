@@ -0,0 +1,149 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+const IV: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const ENCRYPTED: &[u8] = &[0xde, 0xad, 0xbe, 0xef];
+
+fn filtered_flv_tags() -> Vec<Vec<u8>> {
+    let mut se_params = vec![0x80];
+    se_params.extend_from_slice(&IV);
+    vec![
+        // encrypted AAC raw frame
+        flv_tag(
+            AUDIO_TAG_TYPE | FILTER_BIT,
+            23,
+            &filtered_data(&[0xaf, 0x01], "Encryption", &IV, ENCRYPTED),
+        ),
+        // selectively encrypted AVC key frame
+        flv_tag(
+            VIDEO_TAG_TYPE | FILTER_BIT,
+            40,
+            &filtered_data(&AVC_KEY_FRAME[..5], "SE", &se_params, ENCRYPTED),
+        ),
+        // AVC inter frame which is not selected for encryption
+        flv_tag(
+            VIDEO_TAG_TYPE | FILTER_BIT,
+            80,
+            &filtered_data(&AVC_INTER_FRAME[..5], "SE", &[0x00], &AVC_INTER_FRAME[5..]),
+        ),
+    ]
+}
+
+#[test]
+fn test_tag_header_filter_bit() {
+    let tag = flv_tag(VIDEO_TAG_TYPE | FILTER_BIT, 0, &[]);
+    let (_, header) = FlvTagHeader::parse(&tag).unwrap();
+    assert_eq!(header.reserved, 0);
+    assert!(header.filter);
+    assert_eq!(header.tag_type, FlvTagType::Video);
+
+    let tag = flv_tag(0xc0 | SCRIPT_TAG_TYPE, 0, &[]);
+    let (_, header) = FlvTagHeader::parse(&tag).unwrap();
+    assert_eq!(header.reserved, 3);
+    assert!(!header.filter);
+    assert_eq!(header.tag_type, FlvTagType::Script);
+
+    let mut written = Vec::new();
+    header.write(&mut written).unwrap();
+    assert_eq!(written, tag);
+}
+
+#[test]
+fn test_parse_filtered_tags() {
+    let file = flv_file(0b0000_0101, &filtered_flv_tags());
+    let flv = FlvFile::try_parse(&file).unwrap();
+    let tags = flv
+        .body
+        .tags
+        .iter()
+        .map(|(tag, _)| match &tag.data {
+            FlvTagData::Filtered(filtered) => filtered.clone(),
+            data => panic!("unexpected tag data: {:?}", data),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tags.len(), 3);
+
+    assert_eq!(tags[0].tag_type, FlvTagType::Audio);
+    assert_eq!(tags[0].header, &[0xaf, 0x01]);
+    assert_eq!(
+        tags[0].audio_header().unwrap().sound_format,
+        SoundFormat::AAC
+    );
+    assert_eq!(
+        tags[0].encryption_header,
+        EncryptionTagHeader {
+            num_filters: 1,
+            filter_name: ENCRYPTION_FILTER_NAME,
+            length: 16,
+        }
+    );
+    assert_eq!(
+        tags[0].filter_params,
+        FilterParams::Encryption(EncryptionFilterParams { iv: IV })
+    );
+    assert_eq!(tags[0].data, ENCRYPTED);
+
+    assert_eq!(tags[1].header, &AVC_KEY_FRAME[..5]);
+    assert_eq!(tags[1].video_header().unwrap().frame_type, FrameType::Key);
+    assert_eq!(
+        tags[1].encryption_header.filter_name,
+        SELECTIVE_ENCRYPTION_FILTER_NAME
+    );
+    assert_eq!(tags[1].filter_params.iv(), Some(&IV));
+    assert_eq!(tags[1].data, ENCRYPTED);
+
+    assert_eq!(
+        tags[2].filter_params,
+        FilterParams::SelectiveEncryption(SelectiveEncryptionFilterParams {
+            encrypted_au: false,
+            iv: None,
+        })
+    );
+    assert_eq!(tags[2].data, &AVC_INTER_FRAME[5..]);
+}
+
+#[test]
+fn test_unknown_filter() {
+    let data = filtered_data(&[], "Custom", &[0x01, 0x02], ENCRYPTED);
+    let (_, filtered) = FilteredTag::parse(&data, FlvTagType::Script, data.len()).unwrap();
+    assert_eq!(filtered.header, &[]);
+    assert_eq!(filtered.filter_params, FilterParams::Unknown(&[0x01, 0x02]));
+    assert_eq!(filtered.filter_params.iv(), None);
+    assert_eq!(filtered.data, ENCRYPTED);
+}
+
+#[test]
+fn test_write_filtered_tags() {
+    let file = flv_file(0b0000_0101, &filtered_flv_tags());
+    let (_, flv) = FlvFile::parse(&file).unwrap();
+    let mut written = Vec::new();
+    flv.write(&mut written).unwrap();
+    assert_eq!(written, file);
+}
+
+#[test]
+fn test_filtered_tag_errors() {
+    // The filter params are too short for the IV.
+    let data = filtered_data(&[0xaf, 0x01], "Encryption", &IV[..8], &[]);
+    let file = flv_file(
+        0b0000_0100,
+        &[flv_tag(AUDIO_TAG_TYPE | FILTER_BIT, 0, &data)],
+    );
+    let err = FlvFile::try_parse(&file).unwrap_err();
+    assert_eq!(err.structure, FlvStructure::EncryptionHeader);
+    assert_eq!(err.kind, FlvErrorKind::Malformed(ErrorKind::Eof));
+    assert_eq!(err.tag_index, Some(0));
+
+    assert!(validate(&file)
+        .iter()
+        .any(|diagnostic| diagnostic.kind == DiagnosticKind::InvalidTagData));
+    let file = flv_file(0b0000_0101, &filtered_flv_tags());
+    assert_eq!(validate(&file), vec![]);
+}
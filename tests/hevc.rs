@@ -0,0 +1,272 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+const VPS: &[u8] = &[0x40, 0x01, 0x0c, 0x01];
+const SPS: &[u8] = &[0x42, 0x01, 0x01, 0x01];
+const PPS: &[u8] = &[0x44, 0x01, 0xc1, 0x72];
+
+// Main profile, Main tier, level 3.1, 4:2:0, 8 bits, NAL length size 4.
+fn hvcc() -> Vec<u8> {
+    hvcc_with_sps(SPS)
+}
+
+fn hvcc_with_sps(sps: &[u8]) -> Vec<u8> {
+    let mut data = vec![
+        0x01, // configurationVersion
+        0x01, // general_profile_space + general_tier_flag + general_profile_idc
+        0x60, 0x00, 0x00, 0x00, // general_profile_compatibility_flags
+        0x90, 0x00, 0x00, 0x00, 0x00, 0x00, // general_constraint_indicator_flags
+        0x5d, // general_level_idc
+        0xf0, 0x00, // min_spatial_segmentation_idc
+        0xfc, // parallelismType
+        0xfd, // chromaFormat
+        0xf8, // bitDepthLumaMinus8
+        0xf8, // bitDepthChromaMinus8
+        0x00, 0x00, // avgFrameRate
+        0x0f, // constantFrameRate + numTemporalLayers + temporalIdNested + lengthSizeMinusOne
+        0x03, // numOfArrays
+    ];
+    for (nal_unit_type, nal_unit) in &[(0xa0, VPS), (0xa1, sps), (0xa2, PPS)] {
+        data.extend_from_slice(&[*nal_unit_type, 0x00, 0x01]);
+        data.extend_from_slice(&(nal_unit.len() as u16).to_be_bytes());
+        data.extend_from_slice(nal_unit);
+    }
+    data
+}
+
+#[test]
+fn test_hevc_decoder_configuration_record() {
+    let data = hvcc();
+    let (remain, record) = HevcDecoderConfigurationRecord::parse(&data).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(record.general_profile_space, 0);
+    assert!(!record.general_tier_flag);
+    assert_eq!(record.general_profile_idc, 1);
+    assert_eq!(record.general_profile_compatibility_flags, 0x6000_0000);
+    assert_eq!(record.general_constraint_indicator_flags, 0x9000_0000_0000);
+    assert_eq!(record.general_level_idc, 93);
+    assert_eq!(record.chroma_format_idc, 1);
+    assert_eq!(record.bit_depth_luma_minus8, 0);
+    assert_eq!(record.bit_depth_chroma_minus8, 0);
+    assert_eq!(record.num_temporal_layers, 1);
+    assert!(record.temporal_id_nested);
+    assert_eq!(record.nal_length_size(), 4);
+    assert_eq!(record.arrays.len(), 3);
+    assert!(record.arrays[0].array_completeness);
+    assert_eq!(record.arrays[0].nal_unit_type, HEVC_NAL_UNIT_TYPE_VPS);
+    assert_eq!(record.video_parameter_sets(), vec![VPS]);
+    assert_eq!(record.sequence_parameter_sets(), vec![SPS]);
+    assert_eq!(record.picture_parameter_sets(), vec![PPS]);
+}
+
+#[test]
+fn test_invalid_hevc_decoder_configuration_record() {
+    let mut data = hvcc();
+    data[0] = 0;
+    assert!(matches!(
+        HevcDecoderConfigurationRecord::parse(&data),
+        Err(NomErr::Error(_))
+    ));
+    let data = hvcc();
+    assert!(matches!(
+        HevcDecoderConfigurationRecord::parse(&data[..data.len() - 1]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_legacy_hevc_video_tag() {
+    // key frame + CodecID 12, sequence header
+    let mut data = vec![0x1c, 0x00, 0x00, 0x00, 0x00];
+    data.extend_from_slice(&hvcc());
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    assert_eq!(tag.header.codec_id, CodecID::HEVC);
+    assert_eq!(tag.header.ex_header, None);
+    let record = tag.hevc_decoder_configuration_record().unwrap();
+    assert_eq!(record.general_level_idc, 93);
+
+    // key frame + CodecID 12, NALU with composition time 40
+    let frame = [
+        0x1c, 0x01, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x02, 0x26, 0x01,
+    ];
+    let (_, tag) = VideoTag::parse(&frame, frame.len()).unwrap();
+    assert_eq!(tag.hevc_decoder_configuration_record(), None);
+    let (_, packet) = avc_video_packet(tag.body.data, tag.body.data.len()).unwrap();
+    assert_eq!(packet.packet_type, AvcPacketType::NALU);
+    assert_eq!(packet.composition_time, 40);
+
+    // The sequence header isn't a keyframe.
+    let file = flv_file(
+        0b0000_0001,
+        &[
+            flv_tag(VIDEO_TAG_TYPE, 0, &data),
+            flv_tag(VIDEO_TAG_TYPE, 0, &frame),
+        ],
+    );
+    let (_, index) = KeyframeIndex::scan(&file).unwrap();
+    assert_eq!(index.keyframes.len(), 1);
+
+    let mut written = Vec::new();
+    tag.write(&mut written).unwrap();
+    assert_eq!(written, frame);
+}
+
+#[test]
+fn test_ex_hevc_video_tag() {
+    // IsExHeader + key frame + SequenceStart, hvc1
+    let mut data = vec![0x90, b'h', b'v', b'c', b'1'];
+    data.extend_from_slice(&hvcc());
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    let record = tag.hevc_decoder_configuration_record().unwrap();
+    assert_eq!(record.sequence_parameter_sets(), vec![SPS]);
+
+    // av01 isn't HEVC.
+    data[2..5].copy_from_slice(b"v01");
+    data[1] = b'a';
+    let (_, tag) = VideoTag::parse(&data, data.len()).unwrap();
+    assert_eq!(tag.hevc_decoder_configuration_record(), None);
+}
+
+// Main profile, level 3.1, 1920x1088 with 8 lines cropped, 30000/1001 fps, 1:1 SAR,
+// with the scaling list, PCM, short-term and long-term reference picture sets.
+const MAIN_PROFILE_SPS: &[u8] = &[
+    0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+    0x00, 0x5d, 0xa0, 0x03, 0xc0, 0x80, 0x11, 0x07, 0xcb, 0x96, 0x57, 0x92, 0x4f, 0x55, 0x5a, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xaa, 0xcb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xfe, 0xaa, 0xab, 0x77, 0xa3, 0x6b, 0x5a, 0xb7, 0x40, 0xbf, 0x01, 0x6a, 0x02, 0x02, 0x02, 0x1f,
+    0x80, 0x00, 0x01, 0xf4, 0x80, 0x00, 0x3a, 0x98, 0x04,
+];
+
+// Main 10 profile, 3 sub-layers, 4:2:2, 3840x2160, 50 fps, 4:3 SAR.
+const SUB_LAYERS_SPS: &[u8] = &[
+    0x42, 0x01, 0x05, 0x02, 0x20, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+    0x00, 0x5d, 0xf0, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00,
+    0x00, 0x03, 0x00, 0x00, 0x5a, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00,
+    0x03, 0x00, 0x00, 0x03, 0x00, 0x5a, 0xb0, 0x01, 0xe0, 0x20, 0x02, 0x1c, 0x4d, 0x96, 0x57, 0x2b,
+    0x95, 0xe4, 0x93, 0xd5, 0x56, 0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xb2, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xaa, 0xdd, 0xe8, 0xda, 0xd6, 0xad, 0xd0, 0x2f,
+    0xff, 0xc0, 0x01, 0x00, 0x00, 0xda, 0x80, 0x80, 0x80, 0x87, 0xe0, 0x00, 0x00, 0x03, 0x00, 0x20,
+    0x00, 0x00, 0x06, 0x58, 0x80,
+];
+
+#[test]
+fn test_hevc_sequence_parameter_set() {
+    let (remain, sps) = HevcSequenceParameterSet::parse(MAIN_PROFILE_SPS).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(sps.sps_max_sub_layers_minus1, 0);
+    assert_eq!(sps.profile_tier_level.general_profile_idc, 1);
+    assert!(!sps.profile_tier_level.general_tier_flag);
+    assert_eq!(sps.profile_tier_level.general_level_idc, 93);
+    assert_eq!(
+        sps.profile_tier_level.general_constraint_indicator_flags,
+        0x9000_0000_0000
+    );
+    assert_eq!(sps.chroma_format_idc, 1);
+    assert_eq!(sps.pic_width_in_luma_samples, 1920);
+    assert_eq!(sps.pic_height_in_luma_samples, 1088);
+    assert_eq!(
+        sps.conformance_window,
+        Some(HevcConformanceWindow {
+            bottom: 4,
+            ..Default::default()
+        })
+    );
+    assert_eq!(sps.width(), Some(1920));
+    assert_eq!(sps.height(), Some(1080));
+    assert_eq!(sps.bit_depth_luma(), 8);
+    assert_eq!(sps.bit_depth_chroma(), 8);
+    assert_eq!(sps.sample_aspect_ratio(), Some((1, 1)));
+    let timing = sps.vui_parameters.unwrap().timing_info.unwrap();
+    assert_eq!(timing.num_units_in_tick, 1001);
+    assert_eq!(timing.time_scale, 30000);
+    assert_eq!(timing.num_ticks_poc_diff_one, None);
+    assert!((sps.frame_rate().unwrap() - 29.97).abs() < 0.001);
+}
+
+#[test]
+fn test_hevc_sequence_parameter_set_with_sub_layers() {
+    let (_, sps) = HevcSequenceParameterSet::parse(SUB_LAYERS_SPS).unwrap();
+    assert_eq!(sps.sps_max_sub_layers_minus1, 2);
+    assert_eq!(sps.profile_tier_level.general_profile_idc, 2);
+    assert_eq!(sps.profile_tier_level.general_level_idc, 93);
+    assert_eq!(sps.chroma_format_idc, 2);
+    assert_eq!(sps.conformance_window, None);
+    assert_eq!((sps.width(), sps.height()), (Some(3840), Some(2160)));
+    assert_eq!(sps.bit_depth_luma(), 10);
+    assert_eq!(sps.bit_depth_chroma(), 10);
+    assert_eq!(sps.sample_aspect_ratio(), Some((4, 3)));
+    let timing = sps.vui_parameters.unwrap().timing_info.unwrap();
+    assert_eq!(timing.num_ticks_poc_diff_one, Some(1));
+    assert_eq!(sps.frame_rate(), Some(50.0));
+}
+
+#[test]
+fn test_sequence_parameter_set_of_hevc_decoder_configuration_record() {
+    // The SPS of `hvcc()` is incomplete.
+    let data = hvcc();
+    let (_, record) = HevcDecoderConfigurationRecord::parse(&data).unwrap();
+    assert_eq!(record.sequence_parameter_set(), None);
+
+    let data = hvcc_with_sps(MAIN_PROFILE_SPS);
+    let (_, record) = HevcDecoderConfigurationRecord::parse(&data).unwrap();
+    let sps = record.sequence_parameter_set().unwrap();
+    assert_eq!((sps.width(), sps.height()), (Some(1920), Some(1080)));
+}
+
+#[test]
+fn test_hevc_sequence_parameter_set_conformance_window_overflow() {
+    let (_, mut sps) = HevcSequenceParameterSet::parse(MAIN_PROFILE_SPS).unwrap();
+    // The window overflows.
+    sps.conformance_window = Some(HevcConformanceWindow {
+        left: u32::MAX,
+        right: 1,
+        top: u32::MAX / 2,
+        bottom: 0,
+    });
+    assert_eq!((sps.width(), sps.height()), (None, None));
+    // The window is bigger than the picture.
+    sps.conformance_window = Some(HevcConformanceWindow {
+        left: 480,
+        right: 481,
+        top: 272,
+        bottom: 272,
+    });
+    assert_eq!((sps.width(), sps.height()), (None, Some(0)));
+}
+
+#[test]
+fn test_invalid_hevc_sequence_parameter_set() {
+    // Not a SPS NAL unit.
+    assert!(matches!(
+        HevcSequenceParameterSet::parse(PPS),
+        Err(NomErr::Error(_))
+    ));
+    assert!(matches!(
+        HevcSequenceParameterSet::parse(&MAIN_PROFILE_SPS[..20]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_inject_metadata_dimensions_from_hevc_sps() {
+    // IsExHeader + key frame + SequenceStart, hvc1
+    let mut sequence_start = vec![0x90, b'h', b'v', b'c', b'1'];
+    sequence_start.extend_from_slice(&hvcc_with_sps(MAIN_PROFILE_SPS));
+    let tags = vec![
+        flv_tag(
+            SCRIPT_TAG_TYPE,
+            0,
+            &on_meta_data(&[("width", 640.0), ("height", 360.0)]),
+        ),
+        flv_tag(VIDEO_TAG_TYPE, 0, &sequence_start),
+    ];
+    let file = flv_file(0b0000_0001, &tags);
+    let meta = inject_metadata(&file, &mut Vec::new()).unwrap();
+    assert_eq!(meta.width, Some(1920.0));
+    assert_eq!(meta.height, Some(1080.0));
+}
@@ -0,0 +1,187 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+fn inject(file: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let meta = inject_metadata(file, &mut output).unwrap();
+
+    // The injected metadata is the first tag of the new file.
+    let flv = FlvFile::parse(&output).unwrap().1;
+    match &flv.body.tags[0].0.data {
+        FlvTagData::Script(script) => {
+            assert_eq!(OnMetaData::from_script_tag(script).as_ref(), Some(&meta))
+        }
+        data => panic!("unexpected first tag: {:?}", data),
+    }
+    // The previous tag sizes are correct.
+    for (tag, previous_tag_size) in &flv.body.tags {
+        assert_eq!(*previous_tag_size, 11 + tag.header.data_size);
+    }
+    assert_eq!(meta.file_size, Some(output.len() as f64));
+    // The keyframe file positions account for the size of the metadata tag.
+    let (_, index) = KeyframeIndex::scan(&output).unwrap();
+    assert_eq!(meta.keyframes, Some(index));
+    output
+}
+
+#[test]
+fn test_inject_metadata() {
+    let output = inject(&test_flv_file());
+    let flv = FlvFile::parse(&output).unwrap().1;
+    // The existing `onMetaData` is replaced.
+    assert_eq!(flv.body.tags.len(), test_flv_tags().len());
+
+    let meta = match &flv.body.tags[0].0.data {
+        FlvTagData::Script(script) => OnMetaData::from_script_tag(script).unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(meta.duration, Some(0.04));
+    assert_eq!(meta.last_timestamp, Some(0.04));
+    assert_eq!(meta.last_keyframe_timestamp, Some(0.0));
+    // The dimensions are kept from the existing metadata, since the SPS is incomplete.
+    assert_eq!(meta.width, Some(1920.0));
+    assert_eq!(meta.height, Some(1080.0));
+    assert_eq!(meta.video_codec_id, Some(7.0));
+    assert_eq!(meta.audio_codec_id, Some(10.0));
+    assert_eq!(meta.audio_sample_rate, Some(44100.0));
+    assert_eq!(meta.stereo, Some(true));
+    assert_eq!(meta.has_video, Some(true));
+    assert_eq!(meta.has_audio, Some(true));
+    assert_eq!(meta.can_seek_to_end, Some(false));
+    assert_eq!(
+        meta.video_size,
+        Some((AVC_SEQUENCE_HEADER.len() + AVC_KEY_FRAME.len() + AVC_INTER_FRAME.len()) as f64)
+    );
+    assert_eq!(
+        meta.audio_size,
+        Some((AAC_SEQUENCE_HEADER.len() + AAC_RAW_FRAME.len()) as f64)
+    );
+    // 2 video frames in 40 ms
+    assert_eq!(meta.frame_rate, Some(25.0));
+    assert_eq!(meta.keyframes.unwrap().keyframes.len(), 1);
+}
+
+#[test]
+fn test_inject_metadata_without_existing_metadata() {
+    let tags = vec![
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+        flv_tag(VIDEO_TAG_TYPE, 40, AVC_INTER_FRAME),
+        flv_tag(VIDEO_TAG_TYPE, 1000, AVC_KEY_FRAME),
+    ];
+    // The header flags are wrong.
+    let file = flv_file(0b0000_0100, &tags);
+    let output = inject(&file);
+
+    let flv = FlvFile::parse(&output).unwrap().1;
+    assert!(flv.header.has_video);
+    assert!(!flv.header.has_audio);
+    assert_eq!(flv.body.tags.len(), tags.len() + 1);
+    for (i, (tag, _)) in flv.body.tags[1..].iter().enumerate() {
+        let mut written = Vec::new();
+        tag.write(&mut written).unwrap();
+        assert_eq!(written, tags[i]);
+    }
+
+    let meta = match &flv.body.tags[0].0.data {
+        FlvTagData::Script(script) => OnMetaData::from_script_tag(script).unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(meta.duration, Some(1.0));
+    assert_eq!(meta.last_keyframe_timestamp, Some(1.0));
+    assert_eq!(meta.can_seek_to_end, Some(true));
+    assert_eq!(meta.has_audio, Some(false));
+    assert_eq!(meta.audio_codec_id, None);
+    assert_eq!(meta.width, None);
+    assert_eq!(meta.keyframes.unwrap().keyframes.len(), 2);
+}
+
+#[test]
+fn test_inject_metadata_invalid_file() {
+    assert!(inject_metadata(b"FLX", &mut Vec::new()).is_err());
+
+    // The tag that cannot be parsed fails the injection instead of dropping the rest.
+    let mut tags = test_flv_tags();
+    tags.insert(3, flv_tag(AUDIO_TAG_TYPE, 0, &[0x91, b'm', b'p']));
+    let mut output = Vec::new();
+    let err = inject_metadata(&flv_file(0b0000_0101, &tags), &mut output).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_inject_metadata_dimensions_from_sps() {
+    // High profile, 1920x1080.
+    let sps = [
+        0x67, 0x64, 0x00, 0x28, 0xac, 0xd9, 0x40, 0x78, 0x02, 0x27, 0xe5, 0xc0, 0x44, 0x00, 0x00,
+        0x0f, 0xa4, 0x00, 0x03, 0xa9, 0x82, 0x10,
+    ];
+    let mut sequence_header = AVC_SEQUENCE_HEADER[..11].to_vec();
+    sequence_header.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+    sequence_header.extend_from_slice(&sps);
+    sequence_header.push(0);
+    let tags = vec![
+        flv_tag(
+            SCRIPT_TAG_TYPE,
+            0,
+            &on_meta_data(&[("width", 640.0), ("height", 360.0)]),
+        ),
+        flv_tag(VIDEO_TAG_TYPE, 0, &sequence_header),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+    ];
+    let output = inject(&flv_file(0b0000_0001, &tags));
+
+    let flv = FlvFile::parse(&output).unwrap().1;
+    let meta = match &flv.body.tags[0].0.data {
+        FlvTagData::Script(script) => OnMetaData::from_script_tag(script).unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(meta.width, Some(1920.0));
+    assert_eq!(meta.height, Some(1080.0));
+}
+
+#[test]
+fn test_inject_metadata_replaces_malformed_properties() {
+    let mut data = vec![0x02, 0x00, 0x0a];
+    data.extend_from_slice(b"onMetaData");
+    data.extend_from_slice(&[0x08, 0x00, 0x00, 0x00, 0x03]);
+    // keyframes = 1.0, which isn't a keyframe index
+    data.extend_from_slice(&[0x00, 0x09]);
+    data.extend_from_slice(b"keyframes");
+    data.push(0x00);
+    data.extend_from_slice(&1.0f64.to_be_bytes());
+    // duration = "1s", which isn't a number
+    data.extend_from_slice(&[0x00, 0x08]);
+    data.extend_from_slice(b"duration");
+    data.extend_from_slice(&[0x02, 0x00, 0x02, b'1', b's']);
+    // title = "flv"
+    data.extend_from_slice(&[0x00, 0x05]);
+    data.extend_from_slice(b"title");
+    data.extend_from_slice(&[0x02, 0x00, 0x03, b'f', b'l', b'v']);
+    data.extend_from_slice(&[0x00, 0x00, 0x09]);
+    let mut tags = test_flv_tags();
+    tags[0] = flv_tag(SCRIPT_TAG_TYPE, 0, &data);
+
+    let output = inject(&flv_file(0b0000_0101, &tags));
+    let flv = FlvFile::parse(&output).unwrap().1;
+    let properties = match &flv.body.tags[0].0.data {
+        FlvTagData::Script(ScriptTag {
+            value: ScriptDataValue::ECMAArray(properties),
+            ..
+        }) => properties.clone(),
+        data => panic!("unexpected first tag: {:?}", data),
+    };
+    let count = |name| properties.iter().filter(|p| p.name == name).count();
+    assert_eq!(count("keyframes"), 1);
+    assert_eq!(count("duration"), 1);
+    assert_eq!(count("title"), 1);
+    let meta = OnMetaData::from_properties(&properties);
+    assert_eq!(meta.duration, Some(0.04));
+    assert_eq!(meta.keyframes.unwrap().keyframes.len(), 1);
+    assert_eq!(meta.extra.len(), 1);
+}
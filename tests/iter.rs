@@ -0,0 +1,72 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+use nom::{combinator::complete, multi::many0, number::streaming::be_u32, sequence::pair};
+
+use self::common::*;
+
+// Parse the tags like `FlvFileBody::parse` did before `FlvTagIter`.
+fn many0_tags(input: &[u8]) -> (&[u8], Vec<(FlvTag<'_>, u32)>) {
+    many0(complete(pair(FlvTag::parse, be_u32)))(input).unwrap()
+}
+
+#[test]
+fn test_flv_tag_iter() {
+    let file = test_flv_file();
+    let body = &file[FLV_FILE_HEADER_LENGTH..];
+    let (remain, expected) = many0_tags(&body[PREVIOUS_TAG_SIZE_LENGTH..]);
+    assert!(remain.is_empty());
+
+    let (_, (first_previous_tag_size, iter)) = FlvFileBody::parse_lazy(body).unwrap();
+    assert_eq!(first_previous_tag_size, 0);
+    let tags = iter.collect::<Vec<_>>();
+    assert_eq!(tags, expected);
+    let types = tags
+        .iter()
+        .map(|(tag, previous_tag_size)| (tag.header.tag_type, *previous_tag_size as usize))
+        .collect::<Vec<_>>();
+    let sizes = test_flv_tags()
+        .iter()
+        .map(|tag| tag.len())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            (FlvTagType::Script, sizes[0]),
+            (FlvTagType::Video, sizes[1]),
+            (FlvTagType::Audio, sizes[2]),
+            (FlvTagType::Video, sizes[3]),
+            (FlvTagType::Audio, sizes[4]),
+            (FlvTagType::Video, sizes[5]),
+        ]
+    );
+}
+
+#[test]
+fn test_flv_tag_iter_stops_at_invalid_tag() {
+    let mut file = test_flv_file();
+    // Truncate the last tag.
+    file.truncate(file.len() - PREVIOUS_TAG_SIZE_LENGTH - 2);
+    let body = &file[FLV_FILE_HEADER_LENGTH..];
+
+    let (remain, expected) = many0_tags(&body[PREVIOUS_TAG_SIZE_LENGTH..]);
+    let (_, (_, mut iter)) = FlvFileBody::parse_lazy(body).unwrap();
+    let tags = iter.by_ref().collect::<Vec<_>>();
+    assert_eq!(tags, expected);
+    assert_eq!(tags.len(), test_flv_tags().len() - 1);
+    assert_eq!(iter.remain(), remain);
+    assert!(iter.error().unwrap().is_truncated());
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_flv_tag_iter_timestamps() {
+    let file = test_flv_file();
+    let start = FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH;
+    let timestamps = FlvTagIter::new(&file[start..])
+        .map(|(tag, _)| tag.header.timestamp)
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps, vec![0, 0, 0, 0, 23, 40]);
+}
@@ -0,0 +1,203 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+fn number<'a>(name: &'a str, value: f64) -> ScriptDataObjectProperty<'a> {
+    ScriptDataObjectProperty {
+        name,
+        value: ScriptDataValue::Number(value),
+    }
+}
+
+#[test]
+fn test_on_meta_data_from_script_tag() {
+    let data = on_meta_data(&[("duration", 0.04), ("width", 1920.0), ("height", 1080.0)]);
+    let (_, tag) = ScriptTag::parse(&data, data.len()).unwrap();
+
+    let meta = OnMetaData::from_script_tag(&tag).unwrap();
+    assert_eq!(meta.duration, Some(0.04));
+    assert_eq!(meta.width, Some(1920.0));
+    assert_eq!(meta.height, Some(1080.0));
+    assert_eq!(meta.frame_rate, None);
+    assert!(meta.extra.is_empty());
+
+    let other = ScriptTag {
+        name: "onCuePoint",
+        value: tag.value.clone(),
+        raw: None,
+    };
+    assert_eq!(OnMetaData::from_script_tag(&other), None);
+}
+
+#[test]
+fn test_on_meta_data_typed_and_extra_properties() {
+    let properties = vec![
+        number("framerate", 29.97),
+        ScriptDataObjectProperty {
+            name: "stereo",
+            value: ScriptDataValue::Boolean(true),
+        },
+        ScriptDataObjectProperty {
+            name: "encoder",
+            value: ScriptDataValue::String("Lavf58.29.100"),
+        },
+        // unexpected type of well-known property
+        ScriptDataObjectProperty {
+            name: "videocodecid",
+            value: ScriptDataValue::String("avc1"),
+        },
+        number("custom", 1.0),
+    ];
+    let meta = OnMetaData::from_properties(&properties);
+    assert_eq!(meta.frame_rate, Some(29.97));
+    assert_eq!(meta.stereo, Some(true));
+    assert_eq!(meta.encoder, Some("Lavf58.29.100"));
+    assert_eq!(meta.video_codec_id, None);
+    assert_eq!(
+        meta.extra,
+        vec![properties[3].clone(), properties[4].clone()]
+    );
+}
+
+#[test]
+fn test_on_meta_data_into_script_data_value() {
+    let meta = OnMetaData {
+        duration: Some(10.0),
+        audio_sample_rate: Some(44100.0),
+        has_video: Some(false),
+        extra: vec![number("custom", 1.0)],
+        ..Default::default()
+    };
+    let value = ScriptDataValue::from(&meta);
+    assert_eq!(
+        value,
+        ScriptDataValue::ECMAArray(vec![
+            number("duration", 10.0),
+            number("audiosamplerate", 44100.0),
+            ScriptDataObjectProperty {
+                name: "hasVideo",
+                value: ScriptDataValue::Boolean(false),
+            },
+            number("custom", 1.0),
+        ])
+    );
+
+    let tag = meta.to_script_tag();
+    assert_eq!(tag.name, "onMetaData");
+    assert_eq!(OnMetaData::from_script_tag(&tag), Some(meta));
+}
+
+#[test]
+fn test_keyframe_index_scan() {
+    let tags = test_flv_tags();
+    let file = flv_file(0b0000_0101, &tags);
+
+    // The 4th tag is the only key frame (the AVC sequence header is skipped).
+    let file_position = FLV_FILE_HEADER_LENGTH
+        + PREVIOUS_TAG_SIZE_LENGTH
+        + tags[..3]
+            .iter()
+            .map(|tag| tag.len() + PREVIOUS_TAG_SIZE_LENGTH)
+            .sum::<usize>();
+    let (_, index) = KeyframeIndex::scan(&file).unwrap();
+    assert_eq!(
+        index.keyframes,
+        vec![Keyframe {
+            file_position: file_position as u64,
+            timestamp: 0,
+        }]
+    );
+    assert_eq!(file[file_position], VIDEO_TAG_TYPE);
+
+    let key_frame = flv_tag(VIDEO_TAG_TYPE, 2002, AVC_KEY_FRAME);
+    let file = flv_file(0b0000_0001, &[key_frame.clone(), key_frame]);
+    let (_, index) = KeyframeIndex::scan(&file).unwrap();
+    assert_eq!(index.keyframes.len(), 2);
+    assert_eq!(index.keyframes[1].file_position, 13 + 11 + 14 + 4);
+    assert_eq!(index.keyframes[1].timestamp, 2002);
+}
+
+#[test]
+fn test_keyframe_index_script_data() {
+    let index = KeyframeIndex {
+        keyframes: vec![
+            Keyframe {
+                file_position: 1234,
+                timestamp: 0,
+            },
+            Keyframe {
+                file_position: 56789,
+                timestamp: 2002,
+            },
+        ],
+    };
+    let value = index.to_script_data_value();
+    assert_eq!(
+        value,
+        ScriptDataValue::Object(vec![
+            ScriptDataObjectProperty {
+                name: "filepositions",
+                value: ScriptDataValue::StrictArray(vec![
+                    ScriptDataValue::Number(1234.0),
+                    ScriptDataValue::Number(56789.0),
+                ]),
+            },
+            ScriptDataObjectProperty {
+                name: "times",
+                value: ScriptDataValue::StrictArray(vec![
+                    ScriptDataValue::Number(0.0),
+                    ScriptDataValue::Number(2.002),
+                ]),
+            },
+        ])
+    );
+    assert_eq!(
+        KeyframeIndex::from_script_data_value(&value),
+        Some(index.clone())
+    );
+
+    // The keyframes object of `onMetaData`.
+    let properties = vec![ScriptDataObjectProperty {
+        name: "keyframes",
+        value,
+    }];
+    let meta = OnMetaData::from_properties(&properties);
+    assert_eq!(meta.keyframes.as_ref(), Some(&index));
+    assert_eq!(meta.to_properties(), properties);
+
+    // A stale index.
+    let mut stale = index.clone();
+    stale.keyframes[1].file_position += 1;
+    assert_ne!(stale, index);
+}
+
+#[test]
+fn test_keyframe_index_invalid_script_data() {
+    let mismatched = ScriptDataValue::Object(vec![
+        ScriptDataObjectProperty {
+            name: "filepositions",
+            value: ScriptDataValue::StrictArray(vec![ScriptDataValue::Number(1.0)]),
+        },
+        ScriptDataObjectProperty {
+            name: "times",
+            value: ScriptDataValue::StrictArray(vec![]),
+        },
+    ]);
+    assert_eq!(KeyframeIndex::from_script_data_value(&mismatched), None);
+    assert_eq!(
+        KeyframeIndex::from_script_data_value(&ScriptDataValue::Null),
+        None
+    );
+
+    let properties = vec![ScriptDataObjectProperty {
+        name: "keyframes",
+        value: mismatched,
+    }];
+    let meta = OnMetaData::from_properties(&properties);
+    assert_eq!(meta.keyframes, None);
+    assert_eq!(meta.extra, properties);
+}
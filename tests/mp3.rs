@@ -0,0 +1,185 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+// MPEG-1 Layer III, 128 kbit/s, 44100 Hz, joint stereo, padded, not protected.
+const MPEG1_HEADER: &[u8] = &[0xff, 0xfb, 0x92, 0x64];
+// MPEG-2.5 Layer III, 8 kbit/s, 8000 Hz, mono, 72 bytes per frame.
+const MPEG25_HEADER: &[u8] = &[0xff, 0xe3, 0x18, 0xc0];
+const MPEG25_FRAME_SIZE: usize = 72;
+
+fn mpeg25_frame(fill: u8) -> Vec<u8> {
+    let mut frame = MPEG25_HEADER.to_vec();
+    frame.resize(MPEG25_FRAME_SIZE, fill);
+    frame
+}
+
+// MP3 8 kHz, 16 bits, mono
+fn mp3_audio_tag(body: &[u8]) -> Vec<u8> {
+    let mut data = vec![0xe2];
+    data.extend_from_slice(body);
+    data
+}
+
+#[test]
+fn test_mpeg_audio_frame_header() {
+    let (remain, header) = MpegAudioFrameHeader::parse(MPEG1_HEADER).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(header.version, MpegVersion::Mpeg1);
+    assert_eq!(header.layer, MpegLayer::Layer3);
+    assert!(!header.crc_protected);
+    assert_eq!(header.bitrate, 128);
+    assert_eq!(header.sampling_frequency, 44100);
+    assert!(header.padding);
+    assert_eq!(header.channel_mode, MpegChannelMode::JointStereo);
+    assert_eq!(header.mode_extension, 2);
+    assert!(header.original);
+    assert_eq!(header.channels(), 2);
+    assert_eq!(header.samples_per_frame(), 1152);
+    assert_eq!(header.frame_size(), Some(418));
+
+    let (_, header) = MpegAudioFrameHeader::parse(MPEG25_HEADER).unwrap();
+    assert_eq!(header.version, MpegVersion::Mpeg25);
+    assert_eq!(header.bitrate, 8);
+    assert_eq!(header.sampling_frequency, 8000);
+    assert_eq!(header.channel_mode, MpegChannelMode::Mono);
+    assert_eq!(header.channels(), 1);
+    assert_eq!(header.samples_per_frame(), 576);
+    assert_eq!(header.frame_size(), Some(MPEG25_FRAME_SIZE));
+
+    // MPEG-2 Layer I, 32 kbit/s, 24000 Hz, 4 bytes per slot.
+    let (_, header) = MpegAudioFrameHeader::parse(&[0xff, 0xf6, 0x14, 0x00]).unwrap();
+    assert_eq!(header.version, MpegVersion::Mpeg2);
+    assert_eq!(header.layer, MpegLayer::Layer1);
+    assert!(header.crc_protected);
+    assert_eq!(header.frame_size(), Some(64));
+
+    // free format
+    let (_, header) = MpegAudioFrameHeader::parse(&[0xff, 0xfb, 0x00, 0x00]).unwrap();
+    assert_eq!(header.frame_size(), None);
+}
+
+#[test]
+fn test_invalid_mpeg_audio_frame_header() {
+    for header in &[
+        [0xff, 0x7b, 0x90, 0x00], // no frame sync
+        [0xff, 0xeb, 0x90, 0x00], // reserved version
+        [0xff, 0xf9, 0x90, 0x00], // reserved layer
+        [0xff, 0xfb, 0xf0, 0x00], // bad bitrate index
+        [0xff, 0xfb, 0x9c, 0x00], // reserved sampling frequency index
+    ] {
+        assert!(matches!(
+            MpegAudioFrameHeader::parse(header),
+            Err(NomErr::Error(_))
+        ));
+    }
+    assert!(matches!(
+        MpegAudioFrameHeader::parse(&MPEG1_HEADER[..3]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_mpeg_audio_frames() {
+    let frame = mpeg25_frame(0x11);
+    let (remain, parsed) = MpegAudioFrame::parse(&frame).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(parsed.data, &frame[..]);
+    assert!(matches!(
+        MpegAudioFrame::parse(&frame[..40]),
+        Err(NomErr::Incomplete(_))
+    ));
+
+    // The rest of the previous frame, two frames, and the beginning of the next frame.
+    let mut data = vec![0x55; 10];
+    data.extend_from_slice(&mpeg25_frame(0x11));
+    data.extend_from_slice(&mpeg25_frame(0x22));
+    data.extend_from_slice(&mpeg25_frame(0x33)[..20]);
+    let frames = MpegAudioFrames::split(&data);
+    assert_eq!(frames.leading, &data[..10]);
+    assert_eq!(frames.frames.len(), 2);
+    assert_eq!(frames.frames[1].data, &mpeg25_frame(0x22)[..]);
+    assert_eq!(frames.trailing, &data[10 + 2 * MPEG25_FRAME_SIZE..]);
+    assert!(frames.is_split());
+
+    let frames = MpegAudioFrames::split(&frame);
+    assert_eq!(frames.frames.len(), 1);
+    assert!(!frames.is_split());
+
+    // The partial frame header at the end.
+    let mut data = mpeg25_frame(0x11);
+    data.extend_from_slice(&MPEG25_HEADER[..2]);
+    let frames = MpegAudioFrames::split(&data);
+    assert_eq!(frames.frames.len(), 1);
+    assert_eq!(frames.trailing, &MPEG25_HEADER[..2]);
+
+    let tag = mp3_audio_tag(&data);
+    let (_, tag) = AudioTag::parse(&tag, tag.len()).unwrap();
+    assert_eq!(tag.mpeg_audio_frames().unwrap().frames.len(), 1);
+    let (_, tag) = AudioTag::parse(AAC_RAW_FRAME, AAC_RAW_FRAME.len()).unwrap();
+    assert_eq!(tag.mpeg_audio_frames(), None);
+}
+
+#[test]
+fn test_extract_mp3() {
+    // The second frame is split across the second and third tags.
+    let second = mpeg25_frame(0x22);
+    let mut second_tag = mpeg25_frame(0x11);
+    second_tag.extend_from_slice(&second[..30]);
+    let mut third_tag = second[30..].to_vec();
+    third_tag.extend_from_slice(&mpeg25_frame(0x33));
+    let file = flv_file(
+        0b0000_0100,
+        &[
+            flv_tag(AUDIO_TAG_TYPE, 0, &mp3_audio_tag(&[0x00, 0x00])),
+            flv_tag(AUDIO_TAG_TYPE, 0, &mp3_audio_tag(&second_tag)),
+            flv_tag(AUDIO_TAG_TYPE, 72, &mp3_audio_tag(&third_tag)),
+            flv_tag(AUDIO_TAG_TYPE, 144, AAC_RAW_FRAME),
+        ],
+    );
+    let mut output = Vec::new();
+    assert_eq!(extract_mp3(&file, &mut output).unwrap(), 3);
+    let mut expected = mpeg25_frame(0x11);
+    expected.extend_from_slice(&second);
+    expected.extend_from_slice(&mpeg25_frame(0x33));
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_extract_mp3_with_invalid_tag() {
+    let file = flv_file(
+        0b0000_0100,
+        &[
+            flv_tag(AUDIO_TAG_TYPE, 0, &mp3_audio_tag(&mpeg25_frame(0x11))),
+            // The extended audio tag header is truncated.
+            flv_tag(AUDIO_TAG_TYPE, 72, &[0x91, b'.', b'm']),
+            flv_tag(AUDIO_TAG_TYPE, 144, &mp3_audio_tag(&mpeg25_frame(0x22))),
+        ],
+    );
+    let err = extract_mp3(&file, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_mp3_writer() {
+    let frame = mpeg25_frame(0x11);
+    let mut writer = Mp3Writer::new(Vec::new());
+    writer.write_data(&[0x00, 0x01, 0x02]).unwrap();
+    writer.write_data(&frame[..50]).unwrap();
+    assert_eq!(writer.frames(), 0);
+    writer.write_data(&frame[50..]).unwrap();
+    writer.write_data(&frame[..10]).unwrap();
+    assert_eq!(writer.frames(), 1);
+    assert_eq!(writer.split_frames(), 1);
+    assert_eq!(writer.skipped_bytes(), 3);
+    assert_eq!(writer.get_ref(), &frame);
+
+    let (_, aac) = AudioTag::parse(AAC_RAW_FRAME, AAC_RAW_FRAME.len()).unwrap();
+    assert!(writer.write_audio_tag(&aac).is_err());
+    // The incomplete frame at the end is dropped.
+    assert_eq!(writer.into_inner().unwrap(), frame);
+}
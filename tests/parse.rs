@@ -90,6 +90,8 @@ fn test_flv_tag() {
             &b""[..],
             FlvTag {
                 header: FlvTagHeader {
+                    reserved: 0,
+                    filter: false,
                     tag_type: FlvTagType::Audio, // 0x08
                     data_size: 7,                // 0x000007
                     timestamp: 0,                // 0x00000000
@@ -102,6 +104,7 @@ fn test_flv_tag() {
                         sound_rate: SoundRate::_44KHZ,  // 0b11 = 3
                         sound_size: SoundSize::_16Bit,  // 0b01 = 1
                         sound_type: SoundType::Stereo,  // 0b01 = 1
+                        ex_header: None,
                     },
                     // 0x0012 1056 e500, 6 bytes
                     body: AudioTagBody {
@@ -130,6 +133,8 @@ fn test_flv_tag_header() {
         Ok((
             &b""[..],
             FlvTagHeader {
+                reserved: 0,
+                filter: false,
                 tag_type: FlvTagType::Script, // 0x12
                 data_size: 1030,              // 0x000406
                 timestamp: 0,                 // 0x00000000
@@ -150,6 +155,8 @@ fn test_flv_tag_header() {
         Ok((
             &b""[..],
             FlvTagHeader {
+                reserved: 0,
+                filter: false,
                 tag_type: FlvTagType::Video, // 0x09
                 data_size: 48,               // 0x000030
                 timestamp: 0,                // 0x00000000
@@ -170,6 +177,8 @@ fn test_flv_tag_header() {
         Ok((
             &b""[..],
             FlvTagHeader {
+                reserved: 0,
+                filter: false,
                 tag_type: FlvTagType::Audio, // 0x08
                 data_size: 7,                // 0x000007
                 timestamp: 0,                // 0x00000000
@@ -209,6 +218,7 @@ fn test_flv_tag_data() {
                     sound_rate: SoundRate::_44KHZ,  // 0b11 = 3
                     sound_size: SoundSize::_16Bit,  // 0b01 = 1
                     sound_type: SoundType::Stereo,  // 0b01 = 1
+                    ex_header: None,
                 },
                 // 0x0012 1056 e500, 6 bytes
                 body: AudioTagBody {
@@ -247,6 +257,7 @@ fn test_audio_tag() {
                     sound_rate: SoundRate::_44KHZ,  // 0b11 = 3
                     sound_size: SoundSize::_16Bit,  // 0b01 = 1
                     sound_type: SoundType::Stereo,  // 0b01 = 1
+                    ex_header: None,
                 },
                 // 0x0012 1056 e500, 6 bytes
                 body: AudioTagBody {
@@ -286,6 +297,7 @@ fn test_audio_tag_header() {
                 sound_rate: SoundRate::_44KHZ,  // 0b11 = 3
                 sound_size: SoundSize::_16Bit,  // 0b01 = 1
                 sound_type: SoundType::Stereo,  // 0b01 = 1
+                ex_header: None,
             }
         ))
     );
@@ -346,6 +358,7 @@ fn test_video_tag() {
                 header: VideoTagHeader {
                     frame_type: FrameType::Key, // 0b0001 = 1
                     codec_id: CodecID::AVC,     // 0b0111 = 7
+                    ex_header: None,
                 },
                 // 0x0000 0000 0164 0028 ffe1 001b 6764 0028 acd9 4078
                 //   0227 e5c0 4400 0003 0004 0000 0300 c03c 60c6 5801
@@ -386,6 +399,7 @@ fn test_video_tag_header() {
             VideoTagHeader {
                 frame_type: FrameType::Key, // 0b0001 = 1
                 codec_id: CodecID::AVC,     // 0b0111 = 7
+                ex_header: None,
             }
         ))
     );
@@ -541,6 +555,7 @@ fn test_script_tag() {
                         ])
                     ),
                 ]),
+                raw: Some(&TEST_FLV_FILE[start..end]),
             }
         ))
     );
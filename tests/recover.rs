@@ -0,0 +1,130 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+const TAGS_START: usize = FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH;
+
+fn tag_sizes(tags: &[Vec<u8>]) -> Vec<usize> {
+    tags.iter()
+        .map(|tag| tag.len() + PREVIOUS_TAG_SIZE_LENGTH)
+        .collect()
+}
+
+#[test]
+fn test_recovering_iter_without_corruption() {
+    let file = test_flv_file();
+    let events = FlvRecoveringTagIter::new(&file[TAGS_START..]).collect::<Vec<_>>();
+    let (_, flv) = FlvFile::parse(&file).unwrap();
+    assert_eq!(events.len(), flv.body.tags.len());
+    for (event, (tag, previous_tag_size)) in events.into_iter().zip(flv.body.tags) {
+        assert_eq!(event, FlvRecoveryEvent::Tag(tag, previous_tag_size));
+    }
+}
+
+#[test]
+fn test_recovering_iter_with_garbage() {
+    let tags = test_flv_tags();
+    let sizes = tag_sizes(&tags);
+    let mut body = Vec::new();
+    for (index, tag) in tags.iter().enumerate() {
+        if index == 3 {
+            body.extend_from_slice(&[0x09, 0x00, 0x00, 0xff, 0x12]);
+        }
+        body.extend_from_slice(tag);
+        body.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+    }
+    // A truncated tag at the end.
+    body.extend_from_slice(&tags[5][..10]);
+
+    let mut iter = FlvRecoveringTagIter::new(&body);
+    let events = iter.by_ref().collect::<Vec<_>>();
+    assert!(iter.remain().is_empty());
+    assert_eq!(events.len(), 8);
+    let garbage_start = sizes[..3].iter().sum::<usize>();
+    assert_eq!(
+        events[3],
+        FlvRecoveryEvent::Skipped(garbage_start..garbage_start + 5)
+    );
+    assert!(
+        matches!(&events[4], FlvRecoveryEvent::Tag(tag, _) if tag.header.tag_type == FlvTagType::Video)
+    );
+    assert_eq!(
+        events[7],
+        FlvRecoveryEvent::Skipped(body.len() - 10..body.len())
+    );
+}
+
+#[test]
+fn test_recovering_iter_parses_expected_tag_as_usual() {
+    let mut tags = test_flv_tags();
+    // The non-zero stream ids are not plausible.
+    tags[1][10] = 1;
+    tags[2][10] = 1;
+    let sizes = tag_sizes(&tags);
+    let mut body = Vec::new();
+    for (index, tag) in tags.iter().enumerate() {
+        if index == 2 {
+            body.extend_from_slice(&[0xff, 0xff, 0xff]);
+        }
+        body.extend_from_slice(tag);
+        body.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+    }
+
+    let events = FlvRecoveringTagIter::new(&body).collect::<Vec<_>>();
+    assert_eq!(events.len(), tags.len());
+    // The tag at the expected position is parsed as usual.
+    assert!(matches!(&events[1], FlvRecoveryEvent::Tag(tag, _) if tag.header.stream_id == 1));
+    // The implausible tag found while scanning is skipped.
+    let garbage_start = sizes[..2].iter().sum::<usize>();
+    assert_eq!(
+        events[2],
+        FlvRecoveryEvent::Skipped(garbage_start..garbage_start + 3 + sizes[2])
+    );
+    assert!(matches!(&events[3], FlvRecoveryEvent::Tag(tag, _) if tag.header.stream_id == 0));
+}
+
+#[test]
+fn test_parse_recovering() {
+    let tags = test_flv_tags();
+    let sizes = tag_sizes(&tags);
+    let mut file = flv_file(0b0000_0101, &tags);
+    // Corrupt the data size of the AAC sequence header, so it cannot be parsed.
+    let corrupted = TAGS_START + sizes[0] + sizes[1];
+    file[corrupted + 3] = 0xff;
+    // Corrupt the previous tag size of the AVC inter frame, which is parsed as usual.
+    let last = file.len() - 1;
+    file[last] ^= 1;
+
+    // The strict parsing stops at the corrupted tag.
+    let (_, flv) = FlvFile::parse(&file).unwrap();
+    assert_eq!(flv.body.tags.len(), 2);
+
+    let (remain, (flv, skipped)) = FlvFile::parse_recovering(&file).unwrap();
+    assert!(remain.is_empty());
+    assert_eq!(skipped, vec![corrupted..corrupted + sizes[2]]);
+    let types = flv
+        .body
+        .tags
+        .iter()
+        .map(|(tag, _)| tag.header.tag_type)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            FlvTagType::Script,
+            FlvTagType::Video,
+            FlvTagType::Video,
+            FlvTagType::Audio,
+            FlvTagType::Video
+        ]
+    );
+
+    assert!(matches!(
+        FlvFile::parse_recovering(&file[..FLV_FILE_HEADER_LENGTH]),
+        Err(NomErr::Incomplete(_))
+    ));
+}
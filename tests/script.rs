@@ -0,0 +1,134 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+use flvparse::*;
+
+fn encode(value: &ScriptDataValue) -> Vec<u8> {
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    written
+}
+
+fn assert_round_trip(value: ScriptDataValue) {
+    let written = encode(&value);
+    assert_eq!(ScriptDataValue::parse(&written), Ok((&b""[..], value)));
+}
+
+#[test]
+fn test_write_script_data_simple_values() {
+    assert_eq!(
+        encode(&ScriptDataValue::Number(1.5)),
+        [0x00, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(encode(&ScriptDataValue::Boolean(true)), [0x01, 0x01]);
+    assert_eq!(
+        encode(&ScriptDataValue::String("flv")),
+        [0x02, 0x00, 0x03, b'f', b'l', b'v']
+    );
+    assert_eq!(encode(&ScriptDataValue::MovieClip), [0x04]);
+    assert_eq!(encode(&ScriptDataValue::Null), [0x05]);
+    assert_eq!(encode(&ScriptDataValue::Undefined), [0x06]);
+    assert_eq!(
+        encode(&ScriptDataValue::Reference(0x0102)),
+        [0x07, 0x01, 0x02]
+    );
+
+    assert_round_trip(ScriptDataValue::Number(-0.25));
+    assert_round_trip(ScriptDataValue::Boolean(false));
+    assert_round_trip(ScriptDataValue::String("onMetaData"));
+    assert_round_trip(ScriptDataValue::Reference(7));
+}
+
+#[test]
+fn test_write_script_data_ecma_array() {
+    let value = ScriptDataValue::ECMAArray(vec![
+        ScriptDataObjectProperty {
+            name: "width",
+            value: ScriptDataValue::Number(1280.0),
+        },
+        ScriptDataObjectProperty {
+            name: "stereo",
+            value: ScriptDataValue::Boolean(true),
+        },
+    ]);
+    let written = encode(&value);
+    // type marker + ECMA array length
+    assert_eq!(written[..5], [0x08, 0x00, 0x00, 0x00, 0x02]);
+    // object end marker
+    assert_eq!(written[written.len() - 3..], [0x00, 0x00, 0x09]);
+    assert_round_trip(value);
+}
+
+#[test]
+fn test_write_script_data_object_and_strict_array() {
+    assert_eq!(
+        encode(&ScriptDataValue::Object(vec![])),
+        [0x03, 0x00, 0x00, 0x09]
+    );
+    assert_round_trip(ScriptDataValue::Object(vec![ScriptDataObjectProperty {
+        name: "times",
+        value: ScriptDataValue::StrictArray(vec![
+            ScriptDataValue::Number(0.0),
+            ScriptDataValue::Number(2.0),
+            ScriptDataValue::Null,
+        ]),
+    }]));
+    assert_eq!(
+        encode(&ScriptDataValue::StrictArray(vec![ScriptDataValue::Null])),
+        [0x0a, 0x00, 0x00, 0x00, 0x01, 0x05]
+    );
+}
+
+#[test]
+fn test_write_script_data_date() {
+    let date = ScriptDataDate {
+        date_time: 1_600_000_000_000.0,
+        local_date_time_offset: -480,
+    };
+    let written = encode(&ScriptDataValue::Date(date));
+    assert_eq!(written.len(), 1 + 8 + 2);
+    assert_eq!(written[0], 0x0b);
+    assert_eq!(written[9..], [0xfe, 0x20]);
+    assert_round_trip(ScriptDataValue::Date(date));
+}
+
+#[test]
+fn test_write_script_data_long_string() {
+    let long = "x".repeat(u16::MAX as usize + 1);
+    let written = encode(&ScriptDataValue::String(&long));
+    assert_eq!(written[..5], [0x0c, 0x00, 0x01, 0x00, 0x00]);
+    assert_eq!(
+        ScriptDataValue::parse(&written),
+        Ok((&b""[..], ScriptDataValue::LongString(&long)))
+    );
+
+    let short = "x".repeat(u16::MAX as usize);
+    assert_eq!(
+        encode(&ScriptDataValue::String(&short))[..3],
+        [0x02, 0xff, 0xff]
+    );
+    assert_round_trip(ScriptDataValue::LongString("short long string"));
+}
+
+#[test]
+fn test_write_script_tag() {
+    let tag = ScriptTag {
+        name: "onMetaData",
+        value: ScriptDataValue::ECMAArray(vec![ScriptDataObjectProperty {
+            name: "duration",
+            value: ScriptDataValue::Number(10.0),
+        }]),
+        raw: None,
+    };
+    let mut written = Vec::new();
+    tag.write(&mut written).unwrap();
+    assert_eq!(
+        ScriptTag::parse(&written, written.len()),
+        Ok((
+            &b""[..],
+            ScriptTag {
+                raw: Some(&written),
+                ..tag
+            }
+        ))
+    );
+}
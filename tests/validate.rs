@@ -0,0 +1,227 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+// The offsets of the tags in the file, after the file header and the first previous tag size.
+fn tag_offsets(tags: &[Vec<u8>]) -> Vec<u64> {
+    let mut offset = (FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH) as u64;
+    tags.iter()
+        .map(|tag| {
+            let start = offset;
+            offset += (tag.len() + PREVIOUS_TAG_SIZE_LENGTH) as u64;
+            start
+        })
+        .collect()
+}
+
+fn kinds(diagnostics: &[Diagnostic]) -> Vec<DiagnosticKind> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.kind)
+        .collect()
+}
+
+#[test]
+fn test_validate_valid_file() {
+    assert_eq!(validate(&test_flv_file()), vec![]);
+}
+
+#[test]
+fn test_validate_file_header() {
+    assert_eq!(
+        validate(b"FLX\x01\x05\x00\x00\x00\x09"),
+        vec![Diagnostic {
+            kind: DiagnosticKind::InvalidFileHeader,
+            offset: 0,
+            tag_index: None,
+        }]
+    );
+
+    // Reserved flags, no video tags but has_video flag, audio tags without has_audio flag.
+    let tags = vec![flv_tag(AUDIO_TAG_TYPE, 0, AAC_SEQUENCE_HEADER)];
+    let mut file = flv_file(0b1000_0001, &tags);
+    // The first previous tag size.
+    file[12] = 0x0f;
+    assert_eq!(
+        validate(&file),
+        vec![
+            Diagnostic {
+                kind: DiagnosticKind::ReservedFileHeaderFlags(0b1000_0000),
+                offset: 4,
+                tag_index: None,
+            },
+            Diagnostic {
+                kind: DiagnosticKind::NonZeroFirstPreviousTagSize(15),
+                offset: 9,
+                tag_index: None,
+            },
+            Diagnostic {
+                kind: DiagnosticKind::HeaderFlagMismatch {
+                    tag_type: FlvTagType::Audio,
+                    flag: false,
+                },
+                offset: 4,
+                tag_index: Some(0),
+            },
+            Diagnostic {
+                kind: DiagnosticKind::HeaderFlagMismatch {
+                    tag_type: FlvTagType::Video,
+                    flag: true,
+                },
+                offset: 4,
+                tag_index: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_validate_tag_headers() {
+    let mut tags = test_flv_tags();
+    // reserved bits
+    tags[1][0] |= 0x40;
+    // stream id
+    tags[2][10] = 1;
+    // audio timestamp 23 -> 0 after the video timestamp 40 is fine, but not 23 -> 10.
+    tags.push(flv_tag(AUDIO_TAG_TYPE, 10, AAC_RAW_FRAME));
+    let offsets = tag_offsets(&tags);
+    let mut file = flv_file(0b0000_0101, &tags);
+    // The previous tag size of the script tag.
+    let script_size_offset = offsets[1] as usize - PREVIOUS_TAG_SIZE_LENGTH;
+    file[script_size_offset + 3] ^= 1;
+
+    let diagnostics = validate(&file);
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                kind: DiagnosticKind::PreviousTagSizeMismatch {
+                    expected: tags[0].len() as u32,
+                    actual: tags[0].len() as u32 ^ 1,
+                },
+                offset: script_size_offset as u64,
+                tag_index: Some(0),
+            },
+            Diagnostic {
+                kind: DiagnosticKind::ReservedTagHeaderBits(0x40),
+                offset: offsets[1],
+                tag_index: Some(1),
+            },
+            Diagnostic {
+                kind: DiagnosticKind::NonZeroStreamId(1),
+                offset: offsets[2] + 8,
+                tag_index: Some(2),
+            },
+            Diagnostic {
+                kind: DiagnosticKind::NonMonotonicTimestamp {
+                    tag_type: FlvTagType::Audio,
+                    previous: 23,
+                    timestamp: 10,
+                },
+                offset: offsets[6] + 4,
+                tag_index: Some(6),
+            },
+        ]
+    );
+    assert_eq!(
+        diagnostics[3].to_string(),
+        format!(
+            "offset {}, tag 6: Audio timestamp 10 is less than the previous timestamp 23",
+            offsets[6] + 4
+        )
+    );
+    assert_eq!(diagnostics[3].kind.code(), "non-monotonic-timestamp");
+}
+
+#[test]
+fn test_validate_sequence_headers() {
+    let tags = vec![
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_KEY_FRAME),
+        // A repeated sequence header after frames is fine.
+        flv_tag(VIDEO_TAG_TYPE, 40, AVC_SEQUENCE_HEADER),
+        flv_tag(AUDIO_TAG_TYPE, 40, AAC_RAW_FRAME),
+        flv_tag(AUDIO_TAG_TYPE, 63, AAC_RAW_FRAME),
+    ];
+    let offsets = tag_offsets(&tags);
+    let diagnostics = validate(&flv_file(0b0000_0101, &tags));
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![
+            DiagnosticKind::MissingSequenceHeader(FlvTagType::Video),
+            DiagnosticKind::DuplicateSequenceHeader(FlvTagType::Video),
+            DiagnosticKind::MissingSequenceHeader(FlvTagType::Audio),
+        ]
+    );
+    assert_eq!(diagnostics[0].offset, offsets[0]);
+    assert_eq!(diagnostics[1].tag_index, Some(2));
+    assert_eq!(diagnostics[2].tag_index, Some(5));
+}
+
+#[test]
+fn test_validate_truncated_and_invalid_tags() {
+    let tags = test_flv_tags();
+    let offsets = tag_offsets(&tags);
+    let file = flv_file(0b0000_0101, &tags);
+    let diagnostics = validate(&file[..file.len() - 2]);
+    assert_eq!(
+        diagnostics,
+        vec![Diagnostic {
+            kind: DiagnosticKind::Truncated,
+            offset: offsets[5],
+            tag_index: Some(5),
+        }]
+    );
+
+    // unknown tag type, and an empty video tag
+    let tags = vec![
+        flv_tag(7, 0, &[0x01, 0x02]),
+        flv_tag(VIDEO_TAG_TYPE, 0, &[]),
+    ];
+    let offsets = tag_offsets(&tags);
+    let diagnostics = validate(&flv_file(0b0000_0001, &tags));
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![
+            DiagnosticKind::UnknownTagType(7),
+            DiagnosticKind::InvalidTagData
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].offset,
+        offsets[1] + FLV_TAG_HEADER_LENGTH as u64
+    );
+}
+
+#[test]
+fn test_validate_continues_after_invalid_tag_data() {
+    // two empty video tags, with a valid one in between
+    let tags = vec![
+        flv_tag(VIDEO_TAG_TYPE, 0, &[]),
+        flv_tag(VIDEO_TAG_TYPE, 0, AVC_SEQUENCE_HEADER),
+        flv_tag(VIDEO_TAG_TYPE, 40, &[]),
+    ];
+    let offsets = tag_offsets(&tags);
+    let diagnostics = validate(&flv_file(0b0000_0001, &tags));
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                kind: DiagnosticKind::InvalidTagData,
+                offset: offsets[0] + FLV_TAG_HEADER_LENGTH as u64,
+                tag_index: Some(0),
+            },
+            Diagnostic {
+                kind: DiagnosticKind::InvalidTagData,
+                offset: offsets[2] + FLV_TAG_HEADER_LENGTH as u64,
+                tag_index: Some(2),
+            },
+        ]
+    );
+}
@@ -0,0 +1,194 @@
+// Copyright 2019-2021 koushiro. Licensed under MIT.
+
+mod common;
+
+use flvparse::*;
+
+use self::common::*;
+
+#[test]
+fn test_write_flv_file() {
+    let file = test_flv_file();
+    let flv = FlvFile::parse(&file).unwrap().1;
+
+    let mut written = Vec::new();
+    flv.write(&mut written).unwrap();
+    assert_eq!(written, file);
+}
+
+#[test]
+fn test_write_flv_file_as_parsed() {
+    let mut wrong_count = on_meta_data(&[("duration", 0.04), ("width", 1920.0)]);
+    // The declared ECMA array count is 0.
+    wrong_count[14..18].copy_from_slice(&[0, 0, 0, 0]);
+    let mut trailing = on_meta_data(&[("duration", 0.04)]);
+    trailing.extend_from_slice(&[0x05, 0x05]);
+    let mut avm_plus = vec![0x02, 0x00, 0x0a];
+    avm_plus.extend_from_slice(b"onMetaData");
+    avm_plus.extend_from_slice(&[0x11, 0x0a, 0x0b, 0x01, 0x03, b'a', 0x04, 0x05, 0x01]);
+    let mut tags = test_flv_tags();
+    tags.push(flv_tag(SCRIPT_TAG_TYPE, 80, &wrong_count));
+    tags.push(flv_tag(SCRIPT_TAG_TYPE, 80, &trailing));
+    tags.push(flv_tag(SCRIPT_TAG_TYPE, 80, &avm_plus));
+    let mut file = flv_file(0b0000_0101, &tags);
+    // The wrong first previous tag size and the last previous tag size.
+    file[FLV_FILE_HEADER_LENGTH + PREVIOUS_TAG_SIZE_LENGTH - 1] = 1;
+    let last = file.len() - 1;
+    file[last] ^= 1;
+
+    let (_, flv) = FlvFile::parse(&file).unwrap();
+    assert_eq!(flv.body.tags.len(), tags.len());
+    let mut written = Vec::new();
+    flv.write(&mut written).unwrap();
+    assert_eq!(written, file);
+
+    // The previous tag sizes are computed.
+    let mut written = Vec::new();
+    flv.write_with_computed_sizes(&mut written).unwrap();
+    assert_eq!(written, flv_file(0b0000_0101, &tags));
+}
+
+#[test]
+fn test_flv_writer() {
+    let file = test_flv_file();
+    let flv = FlvFile::parse(&file).unwrap().1;
+
+    let mut writer = FlvWriter::new(Vec::new(), &flv.header).unwrap();
+    for (tag, _) in &flv.body.tags {
+        writer.write_tag(tag).unwrap();
+    }
+    assert_eq!(writer.into_inner().unwrap(), file);
+}
+
+#[test]
+fn test_write_flv_tag_header() {
+    let header = FlvTagHeader {
+        reserved: 0,
+        filter: false,
+        tag_type: FlvTagType::Script,
+        data_size: 0x01_0203,
+        timestamp: 0x0405_0607,
+        stream_id: 0,
+    };
+    let mut written = Vec::new();
+    header.write(&mut written).unwrap();
+    assert_eq!(
+        written,
+        [0x12, 0x01, 0x02, 0x03, 0x05, 0x06, 0x07, 0x04, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(FlvTagHeader::parse(&written), Ok((&b""[..], header)));
+}
+
+#[test]
+fn test_flv_tag_type_value() {
+    assert_eq!(FlvTagType::Audio as u8, AUDIO_TAG_TYPE);
+    assert_eq!(FlvTagType::Video as u8, VIDEO_TAG_TYPE);
+    // 18 (0x12), not 0x18.
+    assert_eq!(FlvTagType::Script as u8, SCRIPT_TAG_TYPE);
+
+    let bytes = flv_tag(SCRIPT_TAG_TYPE, 0, &[]);
+    let (_, header) = FlvTagHeader::parse(&bytes).unwrap();
+    assert_eq!(header.tag_type, FlvTagType::Script);
+    let mut written = Vec::new();
+    header.write(&mut written).unwrap();
+    assert_eq!(written, &bytes[..FLV_TAG_HEADER_LENGTH]);
+}
+
+#[test]
+fn test_write_flv_tag_previous_tag_size() {
+    let bytes = flv_tag(AUDIO_TAG_TYPE, 23, AAC_RAW_FRAME);
+    let (_, mut tag) = FlvTag::parse(&bytes).unwrap();
+    // The data size of tag header is computed from the tag data.
+    tag.header.data_size = 0;
+
+    let mut written = Vec::new();
+    let tag_size = tag.write(&mut written).unwrap();
+    assert_eq!(tag_size as usize, written.len());
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn test_write_audio_tag_header() {
+    let header = AudioTagHeader {
+        sound_format: SoundFormat::AAC,
+        sound_rate: SoundRate::_44KHZ,
+        sound_size: SoundSize::_16Bit,
+        sound_type: SoundType::Stereo,
+        ex_header: None,
+    };
+    let mut written = Vec::new();
+    header.write(&mut written).unwrap();
+    assert_eq!(written, [0xaf]);
+
+    // The unknown values are written back as is.
+    for data in &[&[0xc2][..], &[0xd5], b"\x97Opus"] {
+        let (_, header) = AudioTagHeader::parse(data, data.len()).unwrap();
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(&written[..], *data);
+    }
+}
+
+#[test]
+fn test_write_aac_audio_packet() {
+    let (_, packet) = aac_audio_packet(&AAC_RAW_FRAME[1..], AAC_RAW_FRAME.len() - 1).unwrap();
+    let mut written = Vec::new();
+    packet.write(&mut written).unwrap();
+    assert_eq!(written, &AAC_RAW_FRAME[1..]);
+}
+
+#[test]
+fn test_write_video_tag_header() {
+    let header = VideoTagHeader {
+        frame_type: FrameType::Inter,
+        codec_id: CodecID::AVC,
+        ex_header: None,
+    };
+    let mut written = Vec::new();
+    header.write(&mut written).unwrap();
+    assert_eq!(written, [0x27]);
+
+    // The unknown values are written back as is.
+    for data in &[&[0x6d][..], &[0x0f], &[0x9f, b'v', b'p', b'0', b'9']] {
+        let (_, header) = VideoTagHeader::parse(data, data.len()).unwrap();
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(&written[..], *data);
+    }
+
+    let header = VideoTagHeader {
+        frame_type: FrameType::Unknown(0x10),
+        codec_id: CodecID::AVC,
+        ex_header: None,
+    };
+    assert!(header.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_write_avc_video_packet() {
+    let packet = AvcVideoPacket {
+        packet_type: AvcPacketType::NALU,
+        composition_time: -40,
+        avc_data: &[0x00, 0x00, 0x00, 0x01, 0x09],
+    };
+    let mut written = Vec::new();
+    packet.write(&mut written).unwrap();
+    assert_eq!(
+        written,
+        [0x01, 0xff, 0xff, 0xd8, 0x00, 0x00, 0x00, 0x01, 0x09]
+    );
+    assert_eq!(
+        avc_video_packet(&written, written.len()),
+        Ok((&b""[..], packet))
+    );
+}
+
+#[test]
+fn test_write_unknown_avc_video_packet() {
+    let data = [0x05, 0x00, 0x00, 0x00, 0x01];
+    let (_, packet) = avc_video_packet(&data, data.len()).unwrap();
+    assert_eq!(packet.packet_type, AvcPacketType::Unknown(5));
+    let mut written = Vec::new();
+    packet.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}